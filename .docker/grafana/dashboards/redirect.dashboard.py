@@ -42,7 +42,7 @@ dashboard = Dashboard(
             ),
             Template(
                 name="slug",
-                label="Slug/Path",
+                label="Route",
                 dataSource=PROM_DS,
                 query='label_values(http_requests_by_ip_total{job="redirect"}, path)',
                 includeAll=True,
@@ -31,7 +31,7 @@ dashboard = Dashboard(
             ),
             Template(
                 name="slug",
-                label="Slug (path)",
+                label="Route",
                 dataSource=PROM_DS,
                 query='label_values(http_requests_by_ip_total{job="redirect"}, path)',
                 includeAll=True,
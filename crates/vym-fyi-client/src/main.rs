@@ -5,7 +5,7 @@ use crate::shared::cli::{Command, Opt};
 use crate::shared::config::{ResolvedClient, load_client_config, resolve_client};
 use clap::Parser;
 use env_logger::{Builder, Env};
-use vym_fyi_model::models::errors::AppResult;
+use vym_fyi_model::models::errors::{AppError, AppResult};
 use vym_fyi_model::services::http_client::HttpClient;
 use vym_fyi_model::services::query_adapter::{LinkListQueryAdapter, QueryParamsBuilder};
 
@@ -42,6 +42,22 @@ impl LinkListQueryAdapter for LinksListParams {
     }
 }
 
+/// Parameters for the `links-create` CLI command.
+#[derive(Debug)]
+struct LinksCreateParams {
+    slug: Option<String>,
+    target: String,
+    expires_at: Option<String>,
+    ttl: Option<String>,
+    slug_style: Option<String>,
+    slug_length: Option<usize>,
+    redirect_type: Option<String>,
+    query_passthrough: Option<String>,
+    path_passthrough: bool,
+    param_template: Option<String>,
+    overwrite: bool,
+}
+
 /// Entry point: configures logging and runs the app workflow.
 #[tokio::main]
 async fn main() {
@@ -67,8 +83,37 @@ async fn app() -> AppResult<()> {
 
     match opt.command {
         Command::Ping => ping(&resolved, opt.use_master).await,
-        Command::LinksCreate { slug, target } => {
-            links_create(&resolved, opt.use_master, slug, target).await
+        Command::LinksCreate {
+            slug,
+            target,
+            expires_at,
+            ttl,
+            slug_style,
+            slug_length,
+            redirect_type,
+            query_passthrough,
+            path_passthrough,
+            param_template,
+            overwrite,
+        } => {
+            links_create(
+                &resolved,
+                opt.use_master,
+                LinksCreateParams {
+                    slug,
+                    target,
+                    expires_at,
+                    ttl,
+                    slug_style,
+                    slug_length,
+                    redirect_type,
+                    query_passthrough,
+                    path_passthrough,
+                    param_template,
+                    overwrite,
+                },
+            )
+            .await
         }
         Command::LinksList {
             page,
@@ -98,6 +143,37 @@ async fn app() -> AppResult<()> {
             )
             .await
         }
+        Command::LinksStats {
+            slug,
+            from,
+            to,
+            granularity,
+        } => {
+            let mut params = QueryParamsBuilder::new();
+            params
+                .push_trimmed("from", &from)
+                .push_trimmed("to", &to)
+                .push_trimmed("granularity", &granularity);
+            links_stats(&resolved, opt.use_master, &slug, params.into_vec()).await
+        }
+        Command::LinksRules { slug, set } => {
+            link_settings(&resolved, opt.use_master, &slug, "rules", set.as_deref()).await
+        }
+        Command::LinksVariants { slug, set } => {
+            link_settings(&resolved, opt.use_master, &slug, "variants", set.as_deref()).await
+        }
+        Command::KeysCreate { tenant, name, role } => {
+            let tenant = tenant.unwrap_or_else(|| resolved.id.clone());
+            keys_create(&resolved, &tenant, name, role).await
+        }
+        Command::KeysList { tenant } => {
+            let tenant = tenant.unwrap_or_else(|| resolved.id.clone());
+            keys_list(&resolved, &tenant).await
+        }
+        Command::KeysRevoke { tenant, id } => {
+            let tenant = tenant.unwrap_or_else(|| resolved.id.clone());
+            keys_revoke(&resolved, &tenant, &id).await
+        }
     }
 }
 
@@ -135,33 +211,74 @@ async fn ping(client: &ResolvedClient, use_master: bool) -> AppResult<()> {
 async fn links_create(
     client: &ResolvedClient,
     use_master: bool,
-    slug: Option<String>,
-    target: String,
+    params: LinksCreateParams,
 ) -> AppResult<()> {
+    let LinksCreateParams {
+        slug,
+        target,
+        expires_at,
+        ttl,
+        slug_style,
+        slug_length,
+        redirect_type,
+        query_passthrough,
+        path_passthrough,
+        param_template,
+        overwrite,
+    } = params;
     let http = HttpClient::global()?;
-    let url = format!("{}/api/links", client.base_url.trim_end_matches('/'));
+    let base = client.base_url.trim_end_matches('/');
 
-    match &slug {
-        Some(s) => info!("Creating link slug={} target={}", s, target),
-        None => info!("Creating link with generated slug target={}", target),
-    }
+    // Create is strict (409 on an existing slug); replacing goes through PUT.
+    let request = match (&slug, overwrite) {
+        (Some(s), true) => {
+            info!("Creating or replacing link slug={} target={}", s, target);
+            http.client().put(format!("{}/api/links/{}", base, s))
+        }
+        (Some(s), false) => {
+            info!("Creating link slug={} target={}", s, target);
+            http.client().post(format!("{}/api/links", base))
+        }
+        (None, _) => {
+            info!("Creating link with generated slug target={}", target);
+            http.client().post(format!("{}/api/links", base))
+        }
+    };
 
     let api_key = select_api_key(client, use_master);
 
-    let body = if let Some(slug) = slug {
-        serde_json::json!({
-            "slug": slug,
-            "target_url": target,
-        })
-    } else {
-        serde_json::json!({
-            "target_url": target,
-        })
-    };
+    let mut body = serde_json::json!({
+        "target_url": target,
+    });
+    if let Some(slug) = slug.filter(|_| !overwrite) {
+        body["slug"] = serde_json::Value::String(slug);
+    }
+    if let Some(expires_at) = expires_at {
+        body["expires_at"] = serde_json::Value::String(expires_at);
+    }
+    if let Some(ttl) = ttl {
+        body["ttl"] = serde_json::Value::String(ttl);
+    }
+    if let Some(slug_style) = slug_style {
+        body["slug_style"] = serde_json::Value::String(slug_style);
+    }
+    if let Some(slug_length) = slug_length {
+        body["slug_length"] = serde_json::Value::from(slug_length);
+    }
+    if let Some(redirect_type) = redirect_type.and_then(|t| t.parse::<u16>().ok()) {
+        body["redirect_type"] = serde_json::Value::from(redirect_type);
+    }
+    if let Some(query_passthrough) = query_passthrough {
+        body["query_passthrough"] = serde_json::Value::String(query_passthrough);
+    }
+    if path_passthrough {
+        body["path_passthrough"] = serde_json::Value::Bool(true);
+    }
+    if let Some(param_template) = param_template {
+        body["param_template"] = serde_json::Value::String(param_template);
+    }
 
-    let response = http
-        .client()
-        .post(&url)
+    let response = request
         .header("X-API-Key", api_key)
         .header("X-Client-Id", &client.id)
         .json(&body)
@@ -171,6 +288,9 @@ async fn links_create(
     let status = response.status();
     let text = response.text().await.unwrap_or_default();
     info!("Server responded with {}: {}", status, text);
+    if status == 409 && !overwrite {
+        warn!("Slug already exists; pass --overwrite to replace it");
+    }
 
     Ok(())
 }
@@ -208,3 +328,165 @@ async fn links_list(
 
     Ok(())
 }
+
+async fn links_stats(
+    client: &ResolvedClient,
+    use_master: bool,
+    slug: &str,
+    query_params: Vec<(&'static str, String)>,
+) -> AppResult<()> {
+    let http = HttpClient::global()?;
+    let url = format!(
+        "{}/api/links/{}/stats",
+        client.base_url.trim_end_matches('/'),
+        slug
+    );
+
+    info!("Fetching stats slug={}", slug);
+
+    let response = http
+        .client()
+        .get(&url)
+        .header("X-API-Key", select_api_key(client, use_master))
+        .header("X-Client-Id", &client.id)
+        .query(&query_params)
+        .send()
+        .await?;
+
+    let status = response.status();
+    let text = response.text().await.unwrap_or_default();
+    info!("Server responded with {}", status);
+    println!("{text}");
+
+    Ok(())
+}
+
+/// Print a link's `rules` or `variants`, or replace them with the JSON in
+/// `file`.
+async fn link_settings(
+    client: &ResolvedClient,
+    use_master: bool,
+    slug: &str,
+    resource: &str,
+    file: Option<&str>,
+) -> AppResult<()> {
+    let http = HttpClient::global()?;
+    let url = format!(
+        "{}/api/links/{}/{}",
+        client.base_url.trim_end_matches('/'),
+        slug,
+        resource
+    );
+
+    let request = match file {
+        Some(path) => {
+            let raw = std::fs::read_to_string(path)?;
+            let body: serde_json::Value = serde_json::from_str(&raw).map_err(|e| {
+                AppError::Config(format!("invalid {} file {}: {}", resource, path, e))
+            })?;
+            info!("Replacing {} slug={} from {}", resource, slug, path);
+            http.client().put(&url).json(&body)
+        }
+        None => {
+            info!("Fetching {} slug={}", resource, slug);
+            http.client().get(&url)
+        }
+    };
+
+    let response = request
+        .header("X-API-Key", select_api_key(client, use_master))
+        .header("X-Client-Id", &client.id)
+        .send()
+        .await?;
+
+    let status = response.status();
+    let text = response.text().await.unwrap_or_default();
+    info!("Server responded with {}", status);
+    println!("{text}");
+
+    Ok(())
+}
+
+fn keys_url(client: &ResolvedClient, tenant: &str) -> String {
+    format!(
+        "{}/api/admin/tenants/{}/keys",
+        client.base_url.trim_end_matches('/'),
+        tenant
+    )
+}
+
+/// Key management is master-only, so these commands always use the master key.
+async fn keys_create(
+    client: &ResolvedClient,
+    tenant: &str,
+    name: String,
+    role: Option<String>,
+) -> AppResult<()> {
+    let http = HttpClient::global()?;
+    let url = keys_url(client, tenant);
+
+    info!("Creating API key name={} tenant={}", name, tenant);
+
+    let mut body = serde_json::json!({
+        "name": name,
+    });
+    if let Some(role) = role {
+        body["role"] = serde_json::Value::String(role);
+    }
+
+    let response = http
+        .client()
+        .post(&url)
+        .header("X-API-Key", select_api_key(client, true))
+        .json(&body)
+        .send()
+        .await?;
+
+    let status = response.status();
+    let text = response.text().await.unwrap_or_default();
+    info!("Server responded with {}", status);
+    println!("{text}");
+
+    Ok(())
+}
+
+async fn keys_list(client: &ResolvedClient, tenant: &str) -> AppResult<()> {
+    let http = HttpClient::global()?;
+    let url = keys_url(client, tenant);
+
+    info!("Listing API keys tenant={}", tenant);
+
+    let response = http
+        .client()
+        .get(&url)
+        .header("X-API-Key", select_api_key(client, true))
+        .send()
+        .await?;
+
+    let status = response.status();
+    let text = response.text().await.unwrap_or_default();
+    info!("Server responded with {}", status);
+    println!("{text}");
+
+    Ok(())
+}
+
+async fn keys_revoke(client: &ResolvedClient, tenant: &str, id: &str) -> AppResult<()> {
+    let http = HttpClient::global()?;
+    let url = format!("{}/{}", keys_url(client, tenant), id);
+
+    info!("Revoking API key id={} tenant={}", id, tenant);
+
+    let response = http
+        .client()
+        .delete(&url)
+        .header("X-API-Key", select_api_key(client, true))
+        .send()
+        .await?;
+
+    let status = response.status();
+    let text = response.text().await.unwrap_or_default();
+    info!("Server responded with {}: {}", status, text);
+
+    Ok(())
+}
@@ -40,6 +40,52 @@ pub enum Command {
         /// Target URL that the slug should redirect to.
         #[arg(long)]
         target: String,
+
+        /// Expire the link at this timestamp (RFC3339, e.g. 2025-12-31T23:59:59Z).
+        #[arg(long = "expires-at", conflicts_with = "ttl")]
+        expires_at: Option<String>,
+
+        /// Expire the link after a relative duration (e.g. 30m, 12h, 7d, 2w).
+        #[arg(long)]
+        ttl: Option<String>,
+
+        /// Style of the generated slug: hex, base62, words or hashid.
+        /// Defaults to the tenant's style.
+        #[arg(long = "slug-style", conflicts_with = "slug")]
+        slug_style: Option<String>,
+
+        /// Minimum length of the generated slug.
+        #[arg(long = "slug-length", conflicts_with = "slug")]
+        slug_length: Option<usize>,
+
+        /// Redirect status: 301 or 308 (permanent, cacheable) or 302 or 307
+        /// (temporary, never cached). Defaults to 307.
+        #[arg(long = "redirect-type", value_parser = ["301", "302", "307", "308"])]
+        redirect_type: Option<String>,
+
+        /// Merge the visitor's query string into the target: off,
+        /// target_wins, request_wins or append. Defaults to off.
+        #[arg(
+            long = "query-passthrough",
+            value_parser = ["off", "target_wins", "request_wins", "append"]
+        )]
+        query_passthrough: Option<String>,
+
+        /// Make this a prefix link: `/{slug}/rest` redirects to the target
+        /// with `rest` appended to its path.
+        #[arg(long = "path-passthrough")]
+        path_passthrough: bool,
+
+        /// Query parameters added to the target on redirect, e.g.
+        /// 'utm_medium=qr&utm_campaign={{slug}}'. Overrides same-named
+        /// parameters of the tenant's template.
+        #[arg(long = "param-template")]
+        param_template: Option<String>,
+
+        /// Replace the link if the slug already exists (sends `PUT`). Without
+        /// it, an existing slug is an error.
+        #[arg(long, requires = "slug")]
+        overwrite: bool,
     },
 
     /// List short links visible to this client (with optional filters).
@@ -80,4 +126,82 @@ pub enum Command {
         #[arg(long = "expires-after")]
         expires_after: Option<String>,
     },
+
+    /// Show click statistics for a short link.
+    LinksStats {
+        /// Slug of the link.
+        #[arg(long)]
+        slug: String,
+
+        /// Start of the window (RFC3339, inclusive).
+        #[arg(long)]
+        from: Option<String>,
+
+        /// End of the window (RFC3339, exclusive). Defaults to now.
+        #[arg(long)]
+        to: Option<String>,
+
+        /// Bucket size of the time series: 'hour' or 'day' (server default is 'day').
+        #[arg(long)]
+        granularity: Option<String>,
+    },
+
+    /// Show the conditional redirect rules of a short link, or replace them.
+    LinksRules {
+        /// Slug of the link.
+        #[arg(long)]
+        slug: String,
+
+        /// JSON file with the new rules, in evaluation order (an array of
+        /// objects with 'target_url' and optional 'languages', 'devices',
+        /// 'not_before' and 'not_after'). '[]' removes all rules.
+        #[arg(long)]
+        set: Option<String>,
+    },
+
+    /// Show the A/B split of a short link, or replace it.
+    LinksVariants {
+        /// Slug of the link.
+        #[arg(long)]
+        slug: String,
+
+        /// JSON file with the new split: an object with 'variants' (an array
+        /// of objects with 'name', 'target_url' and 'weight') and optional
+        /// 'sticky'. An empty 'variants' array turns the split off.
+        #[arg(long)]
+        set: Option<String>,
+    },
+
+    /// Mint a new API key for a tenant (master key required; the secret is printed once).
+    KeysCreate {
+        /// Tenant (client id) owning the key. Defaults to --client.
+        #[arg(long)]
+        tenant: Option<String>,
+
+        /// Human-readable key name (e.g. 'ci-deploy').
+        #[arg(long)]
+        name: String,
+
+        /// Role for the key ('admin', 'url', 'read-only' or 'link-writer'; server default is 'url').
+        #[arg(long)]
+        role: Option<String>,
+    },
+
+    /// List API key metadata for a tenant (master key required).
+    KeysList {
+        /// Tenant (client id) whose keys to list. Defaults to --client.
+        #[arg(long)]
+        tenant: Option<String>,
+    },
+
+    /// Revoke an API key by id (master key required).
+    KeysRevoke {
+        /// Tenant (client id) owning the key. Defaults to --client.
+        #[arg(long)]
+        tenant: Option<String>,
+
+        /// Id of the key to revoke (as shown by keys-list).
+        #[arg(long)]
+        id: String,
+    },
 }
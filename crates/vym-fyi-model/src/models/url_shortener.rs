@@ -1,5 +1,8 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::str::FromStr;
+
+use crate::models::errors::AppError;
 
 /// Identifier for a tenant (logical client).
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -13,12 +16,85 @@ pub struct ShortLinkId(pub String);
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct ApiKeyId(pub String);
 
-/// Role associated with an API key.
+/// Role associated with an API key. Each role grants a fixed set of scopes.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-#[serde(rename_all = "lowercase")]
+#[serde(rename_all = "kebab-case")]
 pub enum Role {
+    /// Full control over the tenant, including its API keys.
     Admin,
+    /// Create, read, update and delete links.
     Url,
+    /// Read links only (e.g. dashboards).
+    ReadOnly,
+    /// Create and update links only (e.g. CI pipelines).
+    LinkWriter,
+}
+
+impl Role {
+    /// Value stored in the `api_keys.role` column.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Admin => "admin",
+            Role::Url => "url",
+            Role::ReadOnly => "read-only",
+            Role::LinkWriter => "link-writer",
+        }
+    }
+
+    /// Scopes granted to keys with this role.
+    pub fn scopes(&self) -> &'static [Scope] {
+        match self {
+            Role::Admin => Scope::ALL,
+            Role::Url => &[Scope::LinksRead, Scope::LinksWrite, Scope::LinksDelete],
+            Role::ReadOnly => &[Scope::LinksRead],
+            Role::LinkWriter => &[Scope::LinksWrite],
+        }
+    }
+}
+
+impl FromStr for Role {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "admin" => Ok(Role::Admin),
+            "url" => Ok(Role::Url),
+            "read-only" => Ok(Role::ReadOnly),
+            "link-writer" => Ok(Role::LinkWriter),
+            other => Err(AppError::Config(format!("unknown role: {}", other))),
+        }
+    }
+}
+
+/// Permission checked by individual CRUD handlers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Scope {
+    #[serde(rename = "links:read")]
+    LinksRead,
+    #[serde(rename = "links:write")]
+    LinksWrite,
+    #[serde(rename = "links:delete")]
+    LinksDelete,
+    #[serde(rename = "tenant:admin")]
+    TenantAdmin,
+}
+
+impl Scope {
+    pub const ALL: &'static [Scope] = &[
+        Scope::LinksRead,
+        Scope::LinksWrite,
+        Scope::LinksDelete,
+        Scope::TenantAdmin,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Scope::LinksRead => "links:read",
+            Scope::LinksWrite => "links:write",
+            Scope::LinksDelete => "links:delete",
+            Scope::TenantAdmin => "tenant:admin",
+        }
+    }
 }
 
 /// Tenant represents an isolated client of the system.
@@ -29,13 +105,227 @@ pub struct Tenant {
     pub status: TenantStatus,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum TenantStatus {
     Active,
     Suspended,
 }
 
+impl TenantStatus {
+    /// Value stored in the `tenants.status` column.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TenantStatus::Active => "active",
+            TenantStatus::Suspended => "suspended",
+        }
+    }
+}
+
+impl FromStr for TenantStatus {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "active" => Ok(TenantStatus::Active),
+            "suspended" => Ok(TenantStatus::Suspended),
+            other => Err(AppError::Config(format!(
+                "unknown tenant status: {}",
+                other
+            ))),
+        }
+    }
+}
+
+/// Bucket size for click statistics time series.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StatsGranularity {
+    Hour,
+    #[default]
+    Day,
+}
+
+impl StatsGranularity {
+    /// Field name understood by Postgres `date_trunc`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StatsGranularity::Hour => "hour",
+            StatsGranularity::Day => "day",
+        }
+    }
+}
+
+/// How the server generates slugs for links created without one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SlugStyle {
+    /// Random hex, e.g. `a3f9b2`.
+    #[default]
+    Hex,
+    /// Random letters and digits, e.g. `q7XbK2`.
+    Base62,
+    /// Readable word combinations, e.g. `brave-otter-42`.
+    Words,
+    /// Hashids-style encoding of a database sequence, e.g. `Xk9Lp2`.
+    Hashid,
+}
+
+impl FromStr for SlugStyle {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "hex" => Ok(SlugStyle::Hex),
+            "base62" => Ok(SlugStyle::Base62),
+            "words" => Ok(SlugStyle::Words),
+            "hashid" => Ok(SlugStyle::Hashid),
+            other => Err(AppError::Config(format!("unknown slug style: {}", other))),
+        }
+    }
+}
+
+/// HTTP status a short link redirects with, (de)serialized as the number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(try_from = "u16", into = "u16")]
+pub enum RedirectType {
+    /// `301 Moved Permanently`.
+    MovedPermanently,
+    /// `302 Found`.
+    Found,
+    /// `307 Temporary Redirect`.
+    #[default]
+    TemporaryRedirect,
+    /// `308 Permanent Redirect`.
+    PermanentRedirect,
+}
+
+impl RedirectType {
+    pub fn status_code(&self) -> u16 {
+        match self {
+            RedirectType::MovedPermanently => 301,
+            RedirectType::Found => 302,
+            RedirectType::TemporaryRedirect => 307,
+            RedirectType::PermanentRedirect => 308,
+        }
+    }
+
+    /// Whether clients and caches may remember the redirect.
+    pub fn is_permanent(&self) -> bool {
+        matches!(
+            self,
+            RedirectType::MovedPermanently | RedirectType::PermanentRedirect
+        )
+    }
+}
+
+impl TryFrom<u16> for RedirectType {
+    type Error = AppError;
+
+    fn try_from(code: u16) -> Result<Self, Self::Error> {
+        match code {
+            301 => Ok(RedirectType::MovedPermanently),
+            302 => Ok(RedirectType::Found),
+            307 => Ok(RedirectType::TemporaryRedirect),
+            308 => Ok(RedirectType::PermanentRedirect),
+            other => Err(AppError::Config(format!(
+                "unsupported redirect type: {} (expected 301, 302, 307 or 308)",
+                other
+            ))),
+        }
+    }
+}
+
+impl From<RedirectType> for u16 {
+    fn from(redirect_type: RedirectType) -> Self {
+        redirect_type.status_code()
+    }
+}
+
+/// Whether and how the query string of a redirect request is merged into
+/// the link's target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryPassthrough {
+    /// The incoming query string is dropped.
+    #[default]
+    Off,
+    /// Incoming parameters are added, except names the target already sets.
+    TargetWins,
+    /// Incoming parameters are added and replace the target's values of the
+    /// same name.
+    RequestWins,
+    /// Incoming parameters are appended, keeping duplicates.
+    Append,
+}
+
+impl QueryPassthrough {
+    /// Value stored in the `short_links.query_passthrough` column.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QueryPassthrough::Off => "off",
+            QueryPassthrough::TargetWins => "target_wins",
+            QueryPassthrough::RequestWins => "request_wins",
+            QueryPassthrough::Append => "append",
+        }
+    }
+}
+
+impl FromStr for QueryPassthrough {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "off" => Ok(QueryPassthrough::Off),
+            "target_wins" => Ok(QueryPassthrough::TargetWins),
+            "request_wins" => Ok(QueryPassthrough::RequestWins),
+            "append" => Ok(QueryPassthrough::Append),
+            other => Err(AppError::Config(format!(
+                "unknown query passthrough policy: {}",
+                other
+            ))),
+        }
+    }
+}
+
+/// Device class of a visitor, derived from the `User-Agent`, that link rules
+/// can route on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeviceClass {
+    Ios,
+    Android,
+    Desktop,
+    /// Anything else: bots, command-line clients, unknown agents.
+    Other,
+}
+
+impl DeviceClass {
+    /// Value stored in the `link_rules.devices` column.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeviceClass::Ios => "ios",
+            DeviceClass::Android => "android",
+            DeviceClass::Desktop => "desktop",
+            DeviceClass::Other => "other",
+        }
+    }
+}
+
+impl FromStr for DeviceClass {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ios" => Ok(DeviceClass::Ios),
+            "android" => Ok(DeviceClass::Android),
+            "desktop" => Ok(DeviceClass::Desktop),
+            "other" => Ok(DeviceClass::Other),
+            other => Err(AppError::Config(format!("unknown device class: {}", other))),
+        }
+    }
+}
+
 /// ApiKey represents a hashed API key bound to a tenant and role.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ApiKey {
@@ -73,12 +363,120 @@ pub struct ServerSection {
     pub master_api_key: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ClientEntry {
     /// Display name for this client configuration.
     pub name: String,
     /// API key value, possibly containing env placeholders like "$(CLIENT_A_SECRET)".
     pub api_key: String,
-    /// Optional role information (informational for the CLI).
+    /// Role of the key seeded for this client; defaults to `url`.
     pub role: Option<Role>,
+    /// Tenant status enforced on startup. When omitted, the status stored in
+    /// the database (e.g. set through the admin API) is left unchanged.
+    #[serde(default)]
+    pub status: Option<TenantStatus>,
+    /// Rate limit for this tenant's keys; defaults to the server-wide limit.
+    #[serde(default)]
+    pub rate_limit: Option<RateLimitPolicy>,
+    /// Minimum length of slugs the server generates for this tenant;
+    /// defaults to 6.
+    #[serde(default)]
+    pub min_slug_len: Option<usize>,
+    /// Style of slugs the server generates for this tenant; defaults to `hex`.
+    #[serde(default)]
+    pub slug_style: Option<SlugStyle>,
+    /// Extra rules for slugs chosen by this tenant's callers.
+    #[serde(default)]
+    pub vanity_slugs: Option<VanitySlugPolicy>,
+    /// Domains (including their subdomains) and IPs/CIDRs this tenant's
+    /// links may not point to, on top of the server-wide blocklist.
+    #[serde(default)]
+    pub blocked_targets: Option<Vec<String>>,
+    /// Query parameters added to the targets of all of this tenant's links on
+    /// redirect, e.g. `utm_source={{tenant}}&utm_campaign={{slug}}`. A link's
+    /// own template overrides same-named parameters.
+    #[serde(default)]
+    pub param_template: Option<String>,
+}
+
+/// Per-tenant rules for caller-chosen ("vanity") slugs, checked on top of
+/// the global slug rules.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VanitySlugPolicy {
+    /// Required prefix, e.g. `acme-`.
+    #[serde(default)]
+    pub prefix: Option<String>,
+    /// Regular expression the whole slug must match.
+    #[serde(default)]
+    pub pattern: Option<String>,
+}
+
+/// Token-bucket rate limit: up to `burst` requests at once, refilled at
+/// `per_second` requests per second.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RateLimitPolicy {
+    pub burst: u32,
+    pub per_second: f64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn role_round_trips_through_column_value() {
+        for role in [Role::Admin, Role::Url, Role::ReadOnly, Role::LinkWriter] {
+            assert_eq!(role.as_str().parse::<Role>().unwrap(), role);
+            let yaml = serde_yaml::to_string(&role).unwrap();
+            assert_eq!(yaml.trim(), role.as_str());
+        }
+        assert!("owner".parse::<Role>().is_err());
+    }
+
+    #[test]
+    fn tenant_status_round_trips_through_column_value() {
+        for status in [TenantStatus::Active, TenantStatus::Suspended] {
+            assert_eq!(status.as_str().parse::<TenantStatus>().unwrap(), status);
+        }
+        assert!("deleted".parse::<TenantStatus>().is_err());
+    }
+
+    #[test]
+    fn query_passthrough_round_trips_through_column_value() {
+        for policy in [
+            QueryPassthrough::Off,
+            QueryPassthrough::TargetWins,
+            QueryPassthrough::RequestWins,
+            QueryPassthrough::Append,
+        ] {
+            assert_eq!(policy.as_str().parse::<QueryPassthrough>().unwrap(), policy);
+            let yaml = serde_yaml::to_string(&policy).unwrap();
+            assert_eq!(yaml.trim(), policy.as_str());
+        }
+        assert!("merge".parse::<QueryPassthrough>().is_err());
+    }
+
+    #[test]
+    fn redirect_type_round_trips_through_status_code() {
+        for code in [301u16, 302, 307, 308] {
+            let redirect_type = RedirectType::try_from(code).unwrap();
+            assert_eq!(redirect_type.status_code(), code);
+            assert_eq!(
+                serde_yaml::to_string(&redirect_type).unwrap().trim(),
+                code.to_string()
+            );
+        }
+        assert!(RedirectType::try_from(303).is_err());
+        assert!(serde_yaml::from_str::<RedirectType>("200").is_err());
+        assert!(RedirectType::PermanentRedirect.is_permanent());
+        assert!(!RedirectType::default().is_permanent());
+    }
+
+    #[test]
+    fn restricted_roles_have_narrow_scopes() {
+        assert_eq!(Role::ReadOnly.scopes(), &[Scope::LinksRead]);
+        assert_eq!(Role::LinkWriter.scopes(), &[Scope::LinksWrite]);
+        assert!(!Role::Url.scopes().contains(&Scope::TenantAdmin));
+        assert!(Role::Admin.scopes().contains(&Scope::TenantAdmin));
+    }
 }
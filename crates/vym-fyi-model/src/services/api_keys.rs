@@ -0,0 +1,137 @@
+use argon2::Argon2;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use hmac::{Hmac, Mac};
+use rand::Rng;
+use rand::distr::Alphanumeric;
+use sha2::Sha256;
+
+use crate::models::errors::{AppError, AppResult};
+
+/// Prefix marking keys minted by the server (e.g. `vfyi_3kX9…`).
+pub const GENERATED_KEY_PREFIX: &str = "vfyi_";
+
+/// Bytes of the HMAC kept as the lookup digest. Enough to narrow the
+/// candidates to a single row without identifying the key.
+const LOOKUP_DIGEST_LEN: usize = 8;
+
+/// Shortest accepted `API_KEY_LOOKUP_SECRET`.
+pub const MIN_LOOKUP_SECRET_LEN: usize = 32;
+
+const GENERATED_SECRET_LEN: usize = 40;
+
+/// Generate a new random API key secret.
+pub fn generate_api_key() -> String {
+    let secret: String = rand::rng()
+        .sample_iter(&Alphanumeric)
+        .take(GENERATED_SECRET_LEN)
+        .map(char::from)
+        .collect();
+    format!("{}{}", GENERATED_KEY_PREFIX, secret)
+}
+
+/// Computes the lookup digests stored in `api_keys.key_lookup`: hex of a
+/// truncated HMAC-SHA256 keyed with a server-side secret. Without the secret
+/// a database dump gives no shortcut around the Argon2 hashes, even for
+/// short keys from the tenants config.
+///
+/// The digest only narrows the candidate rows; the full key is always
+/// verified against the stored hash. Changing the secret invalidates every
+/// stored digest, so tenant keys stop working until they are re-issued
+/// (config keys are re-seeded on the next start).
+#[derive(Clone)]
+pub struct ApiKeyLookup {
+    secret: Vec<u8>,
+}
+
+impl ApiKeyLookup {
+    pub fn new(secret: &str) -> AppResult<Self> {
+        if secret.len() < MIN_LOOKUP_SECRET_LEN {
+            return Err(AppError::Config(format!(
+                "API_KEY_LOOKUP_SECRET must be at least {} characters",
+                MIN_LOOKUP_SECRET_LEN
+            )));
+        }
+        Ok(Self {
+            secret: secret.as_bytes().to_vec(),
+        })
+    }
+
+    /// Read the secret from `API_KEY_LOOKUP_SECRET`.
+    pub fn from_env() -> AppResult<Self> {
+        let secret =
+            std::env::var("API_KEY_LOOKUP_SECRET").map_err(|_| AppError::MissingEnvVar {
+                name: "API_KEY_LOOKUP_SECRET".into(),
+            })?;
+        Self::new(&secret)
+    }
+
+    /// Lookup digest of `api_key`.
+    pub fn digest(&self, api_key: &str) -> String {
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(&self.secret).expect("HMAC accepts any key length");
+        mac.update(api_key.as_bytes());
+        mac.finalize().into_bytes()[..LOOKUP_DIGEST_LEN]
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+}
+
+/// Hash an API key with Argon2id, returning a PHC string for `api_keys.key_hash`.
+pub fn hash_api_key(api_key: &str) -> AppResult<String> {
+    let salt_bytes: [u8; 16] = rand::rng().random();
+    let salt = SaltString::encode_b64(&salt_bytes)
+        .map_err(|e| AppError::Server(format!("failed to encode API key salt: {}", e)))?;
+    Argon2::default()
+        .hash_password(api_key.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| AppError::Server(format!("failed to hash API key: {}", e)))
+}
+
+/// Verify an API key against a stored PHC hash. Malformed hashes never match.
+pub fn verify_api_key(api_key: &str, key_hash: &str) -> bool {
+    PasswordHash::new(key_hash)
+        .map(|parsed| {
+            Argon2::default()
+                .verify_password(api_key.as_bytes(), &parsed)
+                .is_ok()
+        })
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_keys_are_unique_and_prefixed() {
+        let a = generate_api_key();
+        let b = generate_api_key();
+        assert!(a.starts_with(GENERATED_KEY_PREFIX));
+        assert_eq!(a.len(), GENERATED_KEY_PREFIX.len() + GENERATED_SECRET_LEN);
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn lookup_digest_depends_on_the_secret() {
+        let lookup = ApiKeyLookup::new(&"s".repeat(MIN_LOOKUP_SECRET_LEN)).unwrap();
+        let digest = lookup.digest("client-a-key");
+        assert_eq!(digest.len(), LOOKUP_DIGEST_LEN * 2);
+        assert!(digest.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_eq!(digest, lookup.digest("client-a-key"));
+        assert_ne!(digest, lookup.digest("client-a-kez"));
+
+        let other = ApiKeyLookup::new(&"t".repeat(MIN_LOOKUP_SECRET_LEN)).unwrap();
+        assert_ne!(digest, other.digest("client-a-key"));
+        assert!(ApiKeyLookup::new("short").is_err());
+    }
+
+    #[test]
+    fn hash_round_trips() {
+        let hash = hash_api_key("client-a-key").expect("hashing should succeed");
+        assert!(hash.starts_with("$argon2"));
+        assert!(verify_api_key("client-a-key", &hash));
+        assert!(!verify_api_key("client-b-key", &hash));
+        assert!(!verify_api_key("client-a-key", "not-a-hash"));
+    }
+}
@@ -1,16 +1,141 @@
 use axum::{
     body::Body,
-    extract::connect_info::ConnectInfo,
+    extract::MatchedPath,
     http::{Request, Response, StatusCode},
     middleware::Next,
 };
 use axum_prometheus::metrics_exporter_prometheus::PrometheusHandle;
 use axum_prometheus::{GenericMetricLayer, Handle, PrometheusMetricLayerBuilder};
 use metrics::{counter, histogram};
-use std::{net::SocketAddr, time::Instant};
+use once_cell::sync::OnceCell;
+use std::collections::HashSet;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::net::IpAddr;
+use std::str::FromStr;
+use std::time::Instant;
+
+use crate::models::errors::{AppError, AppResult};
+use crate::services::client_ip::client_ip;
+use crate::services::user_agent::UserAgentClass;
 
 pub const DEFAULT_IGNORE_PATHS: &[&str] = &["/health", "/metrics"];
 
+/// Label value used for slugs outside the allow-list.
+pub const OTHER_LABEL: &str = "other";
+
+static LABEL_CONFIG: OnceCell<MetricsLabelConfig> = OnceCell::new();
+
+/// How the `client_ip` label is derived.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ClientIpLabel {
+    /// The address as seen (unbounded cardinality; debugging only).
+    Raw,
+    /// IPv4 /24 or IPv6 /48 network.
+    Subnet,
+    /// Short salted hash of the address (hides addresses, not cardinality).
+    Hash,
+    /// No client address; the label is always `none`.
+    #[default]
+    None,
+}
+
+impl FromStr for ClientIpLabel {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "raw" => Ok(ClientIpLabel::Raw),
+            "subnet" => Ok(ClientIpLabel::Subnet),
+            "hash" => Ok(ClientIpLabel::Hash),
+            "none" => Ok(ClientIpLabel::None),
+            other => Err(AppError::Config(format!(
+                "unknown METRICS_CLIENT_IP mode: {} (expected raw, subnet, hash or none)",
+                other
+            ))),
+        }
+    }
+}
+
+/// Cardinality controls for request and slug metric labels.
+#[derive(Debug, Clone, Default)]
+pub struct MetricsLabelConfig {
+    pub client_ip: ClientIpLabel,
+    pub ip_hash_salt: String,
+    /// Slugs that get their own `slug` label; all others are `other`.
+    pub slug_allowlist: HashSet<String>,
+}
+
+impl MetricsLabelConfig {
+    /// Read `METRICS_CLIENT_IP`, `METRICS_IP_HASH_SALT` and
+    /// `METRICS_SLUG_ALLOWLIST` (comma-separated).
+    pub fn from_env() -> AppResult<Self> {
+        let client_ip = match std::env::var("METRICS_CLIENT_IP") {
+            Ok(raw) => raw.trim().parse()?,
+            Err(_) => ClientIpLabel::default(),
+        };
+        let slug_allowlist = std::env::var("METRICS_SLUG_ALLOWLIST")
+            .map(|raw| {
+                raw.split(',')
+                    .map(str::trim)
+                    .filter(|s| !s.is_empty())
+                    .map(str::to_owned)
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(Self {
+            client_ip,
+            ip_hash_salt: std::env::var("METRICS_IP_HASH_SALT").unwrap_or_default(),
+            slug_allowlist,
+        })
+    }
+
+    /// Label value for a client address according to `client_ip`.
+    pub fn ip_label(&self, ip: IpAddr) -> String {
+        match (self.client_ip, ip) {
+            (ClientIpLabel::None, _) => "none".to_string(),
+            (ClientIpLabel::Raw, addr) => addr.to_string(),
+            (ClientIpLabel::Subnet, IpAddr::V4(v4)) => {
+                let [a, b, c, _] = v4.octets();
+                format!("{}.{}.{}.0/24", a, b, c)
+            }
+            (ClientIpLabel::Subnet, IpAddr::V6(v6)) => {
+                let s = v6.segments();
+                format!("{:x}:{:x}:{:x}::/48", s[0], s[1], s[2])
+            }
+            (ClientIpLabel::Hash, addr) => {
+                let mut hasher = DefaultHasher::new();
+                self.ip_hash_salt.hash(&mut hasher);
+                addr.hash(&mut hasher);
+                format!("{:08x}", hasher.finish() as u32)
+            }
+        }
+    }
+
+    /// Label value for a slug: itself when allow-listed, `other` otherwise.
+    pub fn slug_label(&self, slug: &str) -> String {
+        if self.slug_allowlist.contains(slug) {
+            slug.to_string()
+        } else {
+            OTHER_LABEL.to_string()
+        }
+    }
+}
+
+/// Install the label configuration from the environment. Call once at
+/// startup; without it the defaults apply.
+pub fn init_metrics_labels_from_env() -> AppResult<()> {
+    let config = MetricsLabelConfig::from_env()?;
+    LABEL_CONFIG
+        .set(config)
+        .map_err(|_| AppError::Config("metrics labels already initialized".into()))
+}
+
+/// The active label configuration.
+pub fn metrics_labels() -> &'static MetricsLabelConfig {
+    LABEL_CONFIG.get_or_init(MetricsLabelConfig::default)
+}
+
 /// Build a Prometheus metric layer and handle with common ignore patterns.
 pub fn prometheus_layer_with_ignores<'a>(
     ignore_patterns: &'a [&'a str],
@@ -35,12 +160,13 @@ pub fn prometheus_layer_default<'a>() -> (
 /// Middleware that records per-IP request counters.
 pub async fn record_ip_metrics(req: Request<Body>, next: Next) -> Response<Body> {
     let method = req.method().as_str().to_owned();
-    let path = req.uri().path().to_owned();
-    if should_ignore(&path) {
+    if should_ignore(req.uri().path()) {
         return next.run(req).await;
     }
-    let client_ip = extract_ip(&req);
-    let user_agent = extract_user_agent(&req);
+    let path = route_label(&req);
+    let client_ip =
+        client_ip(&req).map_or_else(|| "unknown".to_string(), |ip| metrics_labels().ip_label(ip));
+    let user_agent = user_agent_class(extract_user_agent(&req)).to_string();
     let start = Instant::now();
 
     let response = next.run(req).await;
@@ -96,34 +222,34 @@ fn should_ignore(path: &str) -> bool {
     DEFAULT_IGNORE_PATHS.iter().any(|p| path.starts_with(p))
 }
 
-fn extract_ip<B>(req: &Request<B>) -> String {
-    if let Some(fwd) = req
-        .headers()
-        .get("x-forwarded-for")
-        .and_then(|h| h.to_str().ok())
-        && let Some(first) = fwd.split(',').next()
-    {
-        let ip = first.trim();
-        if !ip.is_empty() {
-            return ip.to_string();
-        }
-    }
-
-    if let Some(ConnectInfo(addr)) = req.extensions().get::<ConnectInfo<SocketAddr>>() {
-        return addr.ip().to_string();
-    }
-
-    "unknown".to_string()
+/// Route template (e.g. `/{slug}`) rather than the raw path; requests that
+/// matched no route share one label.
+fn route_label<B>(req: &Request<B>) -> String {
+    req.extensions()
+        .get::<MatchedPath>()
+        .map(|p| p.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string())
 }
 
-fn extract_user_agent<B>(req: &Request<B>) -> String {
+fn extract_user_agent<B>(req: &Request<B>) -> Option<&str> {
     req.headers()
         .get("user-agent")
         .and_then(|h| h.to_str().ok())
-        .map(str::trim)
-        .filter(|ua| !ua.is_empty())
-        .map(|ua| ua.to_string())
-        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Coarse user-agent class used as a label: `bot`, `cli`, `browser`,
+/// `other` or `unknown` (no header).
+fn user_agent_class(user_agent: Option<&str>) -> &'static str {
+    match UserAgentClass::classify(user_agent) {
+        UserAgentClass::Unknown => "unknown",
+        UserAgentClass::Bot => "bot",
+        UserAgentClass::Cli => "cli",
+        UserAgentClass::Ios
+        | UserAgentClass::Android
+        | UserAgentClass::Mobile
+        | UserAgentClass::Desktop => "browser",
+        UserAgentClass::Other => "other",
+    }
 }
 
 fn extract_response_size(res: &Response<Body>) -> Option<u64> {
@@ -167,3 +293,96 @@ fn status_class(status: StatusCode) -> &'static str {
         _ => "other",
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Router, middleware, routing::get};
+    use tower::ServiceExt;
+
+    #[test]
+    fn ip_label_buckets_hashes_or_hides_addresses() {
+        let ip = |s: &str| s.parse::<IpAddr>().unwrap();
+        let mut config = MetricsLabelConfig::default();
+        assert_eq!(config.ip_label(ip("203.0.113.77")), "none");
+
+        config.client_ip = ClientIpLabel::Subnet;
+        assert_eq!(config.ip_label(ip("203.0.113.77")), "203.0.113.0/24");
+        assert_eq!(
+            config.ip_label(ip("2001:db8:abcd:12::1")),
+            "2001:db8:abcd::/48"
+        );
+
+        config.client_ip = ClientIpLabel::Hash;
+        let hashed = config.ip_label(ip("203.0.113.77"));
+        assert_eq!(hashed.len(), 8);
+        assert_eq!(hashed, config.ip_label(ip("203.0.113.77")));
+        assert_ne!(hashed, config.ip_label(ip("203.0.113.78")));
+
+        config.client_ip = ClientIpLabel::Raw;
+        assert_eq!(config.ip_label(ip("203.0.113.77")), "203.0.113.77");
+    }
+
+    #[test]
+    fn slug_label_collapses_unlisted_slugs() {
+        let config = MetricsLabelConfig {
+            slug_allowlist: HashSet::from(["promo".to_string()]),
+            ..MetricsLabelConfig::default()
+        };
+        assert_eq!(config.slug_label("promo"), "promo");
+        assert_eq!(config.slug_label("x7Gq2"), OTHER_LABEL);
+    }
+
+    #[test]
+    fn parses_client_ip_modes() {
+        assert_eq!(
+            "hash".parse::<ClientIpLabel>().unwrap(),
+            ClientIpLabel::Hash
+        );
+        assert!("full".parse::<ClientIpLabel>().is_err());
+    }
+
+    #[test]
+    fn classifies_user_agents() {
+        assert_eq!(user_agent_class(None), "unknown");
+        assert_eq!(user_agent_class(Some("curl/8.5.0")), "cli");
+        assert_eq!(
+            user_agent_class(Some("mozilla/5.0 (compatible; googlebot/2.1)")),
+            "bot"
+        );
+        assert_eq!(
+            user_agent_class(Some("mozilla/5.0 (x11; linux x86_64) firefox/128.0")),
+            "browser"
+        );
+        assert_eq!(user_agent_class(Some("something")), "other");
+    }
+
+    #[tokio::test]
+    async fn route_label_uses_route_template() {
+        async fn capture(req: Request<Body>, next: Next) -> Response<Body> {
+            let label = route_label(&req);
+            let mut response = next.run(req).await;
+            response
+                .headers_mut()
+                .insert("x-route", label.parse().unwrap());
+            response
+        }
+
+        let router = Router::new()
+            .route("/{slug}", get(|| async { "ok" }))
+            .layer(middleware::from_fn(capture));
+
+        let response = router
+            .clone()
+            .oneshot(Request::get("/abc123").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.headers()["x-route"], "/{slug}");
+
+        let response = router
+            .oneshot(Request::get("/a/b/c").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.headers()["x-route"], "unmatched");
+    }
+}
@@ -0,0 +1,363 @@
+//! Client IP resolution behind reverse proxies.
+//!
+//! Forwarding headers are only honoured when the direct peer is a trusted
+//! proxy. `Forwarded` (RFC 7239) takes precedence over `X-Forwarded-For`;
+//! both are walked right to left, skipping trusted hops, so a client cannot
+//! spoof its address by prepending entries. `X-Real-IP` is used when neither
+//! is present.
+
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+
+use axum::{
+    body::Body,
+    extract::connect_info::ConnectInfo,
+    http::{HeaderMap, Request, Response},
+    middleware::Next,
+};
+use once_cell::sync::OnceCell;
+use tracing::Instrument;
+
+use crate::models::errors::{AppError, AppResult};
+
+static RESOLVER: OnceCell<ClientIpResolver> = OnceCell::new();
+
+/// Client address resolved for the current request, stored as a request
+/// extension by [`resolve_client_ip`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientIp(pub IpAddr);
+
+/// An IPv4 or IPv6 network in CIDR notation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpCidr {
+    network: IpAddr,
+    prefix: u8,
+}
+
+impl IpCidr {
+    pub fn contains(&self, ip: &IpAddr) -> bool {
+        match (self.network, ip.to_canonical()) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                prefix_eq(&net.octets(), &ip.octets(), self.prefix)
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                prefix_eq(&net.octets(), &ip.octets(), self.prefix)
+            }
+            _ => false,
+        }
+    }
+}
+
+fn prefix_eq(a: &[u8], b: &[u8], prefix: u8) -> bool {
+    let full = (prefix / 8) as usize;
+    let rest = prefix % 8;
+    if a[..full] != b[..full] {
+        return false;
+    }
+    rest == 0 || {
+        let mask = 0xffu8 << (8 - rest);
+        a[full] & mask == b[full] & mask
+    }
+}
+
+impl FromStr for IpCidr {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || AppError::Config(format!("invalid CIDR: {}", s));
+        let (addr, prefix) = match s.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (s, None),
+        };
+        let network: IpAddr = addr.trim().parse().map_err(|_| invalid())?;
+        let max = if network.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(p) => p.trim().parse::<u8>().map_err(|_| invalid())?,
+            None => max,
+        };
+        if prefix > max {
+            return Err(invalid());
+        }
+        Ok(Self { network, prefix })
+    }
+}
+
+/// Resolves the originating client address from the peer address and
+/// forwarding headers.
+#[derive(Debug, Clone, Default)]
+pub struct ClientIpResolver {
+    trusted_proxies: Vec<IpCidr>,
+}
+
+impl ClientIpResolver {
+    pub fn new(trusted_proxies: Vec<IpCidr>) -> Self {
+        Self { trusted_proxies }
+    }
+
+    /// Read `TRUSTED_PROXIES`: comma-separated addresses or CIDRs (e.g.
+    /// `10.0.0.0/8,127.0.0.1`). When unset, no proxy is trusted and the peer
+    /// address is always used.
+    pub fn from_env() -> AppResult<Self> {
+        let trusted_proxies = match std::env::var("TRUSTED_PROXIES") {
+            Ok(raw) => raw
+                .split(',')
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(str::parse)
+                .collect::<AppResult<Vec<_>>>()?,
+            Err(_) => Vec::new(),
+        };
+        Ok(Self::new(trusted_proxies))
+    }
+
+    pub fn is_trusted(&self, ip: &IpAddr) -> bool {
+        self.trusted_proxies.iter().any(|cidr| cidr.contains(ip))
+    }
+
+    /// Resolve the client address for a request received from `peer`.
+    pub fn resolve(&self, headers: &HeaderMap, peer: Option<IpAddr>) -> Option<IpAddr> {
+        let peer = peer?;
+        if !self.is_trusted(&peer) {
+            return Some(peer);
+        }
+
+        let hops = forwarded_for(headers).or_else(|| x_forwarded_for(headers));
+        if let Some(hops) = hops {
+            let mut client = peer;
+            for hop in hops.iter().rev() {
+                match hop {
+                    Some(ip) => {
+                        client = *ip;
+                        if !self.is_trusted(ip) {
+                            break;
+                        }
+                    }
+                    // Obfuscated or malformed hop: nothing further left can
+                    // be trusted, keep the last trusted address.
+                    None => break,
+                }
+            }
+            return Some(client);
+        }
+
+        headers
+            .get("x-real-ip")
+            .and_then(|h| h.to_str().ok())
+            .and_then(parse_node)
+            .or(Some(peer))
+    }
+}
+
+/// `for=` values of all `Forwarded` headers, in order; `None` when absent.
+fn forwarded_for(headers: &HeaderMap) -> Option<Vec<Option<IpAddr>>> {
+    let mut hops = Vec::new();
+    for value in headers.get_all("forwarded") {
+        let value = value.to_str().ok()?;
+        for element in value.split(',') {
+            let node = element.split(';').find_map(|pair| {
+                let (key, value) = pair.split_once('=')?;
+                key.trim()
+                    .eq_ignore_ascii_case("for")
+                    .then(|| value.trim().trim_matches('"'))
+            });
+            if let Some(node) = node {
+                hops.push(parse_node(node));
+            }
+        }
+    }
+    (!hops.is_empty()).then_some(hops)
+}
+
+/// Entries of all `X-Forwarded-For` headers, in order; `None` when absent.
+fn x_forwarded_for(headers: &HeaderMap) -> Option<Vec<Option<IpAddr>>> {
+    let hops: Vec<_> = headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|h| h.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(parse_node)
+        .collect();
+    (!hops.is_empty()).then_some(hops)
+}
+
+/// Parse `1.2.3.4`, `1.2.3.4:80`, `2001:db8::1` or `[2001:db8::1]:80`.
+fn parse_node(node: &str) -> Option<IpAddr> {
+    let node = node.trim();
+    if let Ok(ip) = node.parse::<IpAddr>() {
+        return Some(ip);
+    }
+    if let Some(rest) = node.strip_prefix('[') {
+        return rest.split_once(']')?.0.parse().ok();
+    }
+    node.parse::<SocketAddr>().ok().map(|addr| addr.ip())
+}
+
+/// Install the resolver configuration from the environment. Call once at
+/// startup; without it no proxy is trusted.
+pub fn init_client_ip_resolver_from_env() -> AppResult<()> {
+    let resolver = ClientIpResolver::from_env()?;
+    RESOLVER
+        .set(resolver)
+        .map_err(|_| AppError::Config("client IP resolver already initialized".into()))
+}
+
+/// The active resolver.
+pub fn client_ip_resolver() -> &'static ClientIpResolver {
+    RESOLVER.get_or_init(ClientIpResolver::default)
+}
+
+/// Client address of a request: the [`ClientIp`] extension when set by
+/// [`resolve_client_ip`], otherwise resolved on the spot.
+pub fn client_ip<B>(req: &Request<B>) -> Option<IpAddr> {
+    if let Some(ClientIp(ip)) = req.extensions().get::<ClientIp>() {
+        return Some(*ip);
+    }
+    let peer = req
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip());
+    client_ip_resolver().resolve(req.headers(), peer)
+}
+
+/// Network a client is tracked under by per-client limits: IPv4 addresses
+/// as is, IPv6 addresses by their /64, since a single host usually
+/// controls a whole /64.
+pub fn client_network(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V4(_) => ip,
+        IpAddr::V6(v6) => IpAddr::V6((u128::from(v6) & (u128::MAX << 64)).into()),
+    }
+}
+
+/// Middleware that resolves the client address once, stores it as a
+/// [`ClientIp`] extension and runs the request inside a span carrying it, so
+/// metrics, logs and rate limiting agree on the same address.
+pub async fn resolve_client_ip(mut req: Request<Body>, next: Next) -> Response<Body> {
+    let ip = client_ip(&req);
+    let span = tracing::info_span!(
+        "request",
+        client_ip = %ip.map_or_else(|| "unknown".to_string(), |ip| ip.to_string())
+    );
+    if let Some(ip) = ip {
+        req.extensions_mut().insert(ClientIp(ip));
+    }
+    next.run(req).instrument(span).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn resolver(cidrs: &[&str]) -> ClientIpResolver {
+        ClientIpResolver::new(cidrs.iter().map(|c| c.parse().unwrap()).collect())
+    }
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.append(*name, HeaderValue::from_str(value).unwrap());
+        }
+        headers
+    }
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn groups_ipv6_clients_per_64() {
+        assert_eq!(client_network(ip("198.51.100.7")), ip("198.51.100.7"));
+        assert_eq!(
+            client_network(ip("2001:db8:1:2:ffff::1")),
+            ip("2001:db8:1:2::")
+        );
+    }
+
+    #[test]
+    fn parses_and_matches_cidrs() {
+        let net: IpCidr = "10.1.0.0/16".parse().unwrap();
+        assert!(net.contains(&ip("10.1.200.3")));
+        assert!(!net.contains(&ip("10.2.0.1")));
+        assert!(net.contains(&ip("::ffff:10.1.0.9")));
+
+        let v6: IpCidr = "2001:db8::/33".parse().unwrap();
+        assert!(v6.contains(&ip("2001:db8:7fff::1")));
+        assert!(!v6.contains(&ip("2001:db8:8000::1")));
+
+        let single: IpCidr = "127.0.0.1".parse().unwrap();
+        assert!(single.contains(&ip("127.0.0.1")));
+        assert!("10.0.0.0/33".parse::<IpCidr>().is_err());
+        assert!("nope/8".parse::<IpCidr>().is_err());
+    }
+
+    #[test]
+    fn ignores_headers_from_untrusted_peers() {
+        let resolver = resolver(&["10.0.0.0/8"]);
+        let spoofed = headers(&[("x-forwarded-for", "1.1.1.1"), ("x-real-ip", "2.2.2.2")]);
+
+        assert_eq!(
+            resolver.resolve(&spoofed, Some(ip("203.0.113.9"))),
+            Some(ip("203.0.113.9"))
+        );
+        assert_eq!(resolver.resolve(&spoofed, None), None);
+    }
+
+    #[test]
+    fn walks_x_forwarded_for_right_to_left() {
+        let resolver = resolver(&["10.0.0.0/8"]);
+        // The client prepended a fake entry; the first untrusted hop from the
+        // right is the real client.
+        let xff = headers(&[("x-forwarded-for", "6.6.6.6, 198.51.100.7, 10.0.0.2")]);
+        assert_eq!(
+            resolver.resolve(&xff, Some(ip("10.0.0.1"))),
+            Some(ip("198.51.100.7"))
+        );
+
+        let all_trusted = headers(&[("x-forwarded-for", "10.0.0.5:1234, 10.0.0.2")]);
+        assert_eq!(
+            resolver.resolve(&all_trusted, Some(ip("10.0.0.1"))),
+            Some(ip("10.0.0.5"))
+        );
+    }
+
+    #[test]
+    fn prefers_forwarded_header_and_handles_obfuscated_nodes() {
+        let resolver = resolver(&["10.0.0.0/8"]);
+        let forwarded = headers(&[
+            (
+                "forwarded",
+                r#"for=6.6.6.6, for="[2001:db8::7]:4711";proto=https"#,
+            ),
+            ("x-forwarded-for", "9.9.9.9"),
+        ]);
+        assert_eq!(
+            resolver.resolve(&forwarded, Some(ip("10.0.0.1"))),
+            Some(ip("2001:db8::7"))
+        );
+
+        let obfuscated = headers(&[("forwarded", "for=_hidden, for=10.0.0.3")]);
+        assert_eq!(
+            resolver.resolve(&obfuscated, Some(ip("10.0.0.1"))),
+            Some(ip("10.0.0.3"))
+        );
+    }
+
+    #[test]
+    fn falls_back_to_x_real_ip_then_peer() {
+        let resolver = resolver(&["10.0.0.0/8"]);
+        assert_eq!(
+            resolver.resolve(
+                &headers(&[("x-real-ip", "198.51.100.7")]),
+                Some(ip("10.0.0.1"))
+            ),
+            Some(ip("198.51.100.7"))
+        );
+        assert_eq!(
+            resolver.resolve(&HeaderMap::new(), Some(ip("10.0.0.1"))),
+            Some(ip("10.0.0.1"))
+        );
+    }
+}
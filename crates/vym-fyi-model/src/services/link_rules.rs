@@ -0,0 +1,282 @@
+//! Conditional redirect rules.
+//!
+//! A link may send visitors to different targets depending on their
+//! preferred language, their device and the time of the visit. Rules are
+//! checked in order; the first one whose conditions all hold picks the
+//! target, and the link's own target is the fallback.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::models::url_shortener::DeviceClass;
+use crate::services::user_agent::UserAgentClass;
+
+/// Most rules a single link may have.
+pub const MAX_RULES_PER_LINK: usize = 32;
+
+/// Longest language range accepted in a rule (RFC 5646 tags fit in 35).
+const MAX_LANGUAGE_LEN: usize = 35;
+
+/// Why a rule was rejected.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum LinkRuleError {
+    #[error("invalid language range '{0}'")]
+    InvalidLanguage(String),
+    #[error("not_before must be earlier than not_after")]
+    EmptyWindow,
+    #[error("a link may have at most {max} rules")]
+    TooMany { max: usize },
+}
+
+impl LinkRuleError {
+    /// Stable machine-readable code for API responses.
+    pub fn code(&self) -> &'static str {
+        match self {
+            LinkRuleError::InvalidLanguage(_) => "invalid_language",
+            LinkRuleError::EmptyWindow => "empty_window",
+            LinkRuleError::TooMany { .. } => "too_many_rules",
+        }
+    }
+}
+
+/// Conditions of a rule; empty lists and missing bounds match everything.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RuleConditions {
+    /// Language ranges such as `de` or `pt-br`, matched against the
+    /// visitor's preferred language (`de` also matches `de-at`).
+    #[serde(default)]
+    pub languages: Vec<String>,
+    #[serde(default)]
+    pub devices: Vec<DeviceClass>,
+    /// Start of the time window (inclusive).
+    #[serde(default)]
+    pub not_before: Option<DateTime<Utc>>,
+    /// End of the time window (exclusive).
+    #[serde(default)]
+    pub not_after: Option<DateTime<Utc>>,
+}
+
+impl RuleConditions {
+    /// Check the conditions and lowercase the language ranges.
+    pub fn normalized(mut self) -> Result<Self, LinkRuleError> {
+        for language in &mut self.languages {
+            let valid = !language.is_empty()
+                && language.len() <= MAX_LANGUAGE_LEN
+                && language.split('-').all(|part| {
+                    !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric())
+                });
+            if !valid {
+                return Err(LinkRuleError::InvalidLanguage(language.clone()));
+            }
+            language.make_ascii_lowercase();
+        }
+        if let (Some(start), Some(end)) = (self.not_before, self.not_after)
+            && start >= end
+        {
+            return Err(LinkRuleError::EmptyWindow);
+        }
+        Ok(self)
+    }
+
+    pub fn matches(&self, visit: &Visit) -> bool {
+        let language_matches = self.languages.is_empty()
+            || visit.language.as_deref().is_some_and(|preferred| {
+                self.languages.iter().any(|range| {
+                    preferred == range
+                        || preferred
+                            .strip_prefix(range.as_str())
+                            .is_some_and(|rest| rest.starts_with('-'))
+                })
+            });
+        language_matches
+            && (self.devices.is_empty() || self.devices.contains(&visit.device))
+            && self.not_before.is_none_or(|start| visit.at >= start)
+            && self.not_after.is_none_or(|end| visit.at < end)
+    }
+}
+
+/// A stored rule: where to send visitors matching `conditions`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkRule {
+    pub id: Uuid,
+    pub conditions: RuleConditions,
+    pub target_url: String,
+}
+
+impl LinkRule {
+    /// A new rule with a fresh id.
+    pub fn new(conditions: RuleConditions, target_url: impl Into<String>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            conditions,
+            target_url: target_url.into(),
+        }
+    }
+}
+
+/// What the rules know about a redirect request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Visit {
+    /// Visitor's most preferred language from `Accept-Language`, lowercased.
+    pub language: Option<String>,
+    pub device: DeviceClass,
+    pub at: DateTime<Utc>,
+}
+
+impl Visit {
+    pub fn new(accept_language: Option<&str>, user_agent: Option<&str>, at: DateTime<Utc>) -> Self {
+        Self {
+            language: accept_language.and_then(preferred_language),
+            device: device_class(user_agent),
+            at,
+        }
+    }
+}
+
+/// The first rule matching `visit`, if any.
+pub fn select_rule<'a>(rules: &'a [LinkRule], visit: &Visit) -> Option<&'a LinkRule> {
+    rules.iter().find(|rule| rule.conditions.matches(visit))
+}
+
+/// Language with the highest quality in an `Accept-Language` header; the
+/// first one wins ties, and `*` never does.
+fn preferred_language(header: &str) -> Option<String> {
+    let mut best: Option<(&str, f32)> = None;
+    for entry in header.split(',') {
+        let mut parts = entry.split(';').map(str::trim);
+        let tag = parts.next().unwrap_or_default();
+        let quality = parts
+            .find_map(|p| p.strip_prefix("q="))
+            .map_or(Some(1.0), |q| q.parse::<f32>().ok())
+            .unwrap_or(0.0);
+        if tag.is_empty() || tag == "*" || quality <= 0.0 {
+            continue;
+        }
+        if best.is_none_or(|(_, q)| quality > q) {
+            best = Some((tag, quality));
+        }
+    }
+    best.map(|(tag, _)| tag.to_ascii_lowercase())
+}
+
+/// Device class from a `User-Agent` (see [`UserAgentClass`]). Bots, scripts
+/// and browsers on other mobile platforms count as `other`.
+fn device_class(user_agent: Option<&str>) -> DeviceClass {
+    match UserAgentClass::classify(user_agent) {
+        UserAgentClass::Ios => DeviceClass::Ios,
+        UserAgentClass::Android => DeviceClass::Android,
+        UserAgentClass::Desktop => DeviceClass::Desktop,
+        UserAgentClass::Unknown
+        | UserAgentClass::Bot
+        | UserAgentClass::Cli
+        | UserAgentClass::Mobile
+        | UserAgentClass::Other => DeviceClass::Other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    const IPHONE: &str = "Mozilla/5.0 (iPhone; CPU iPhone OS 17_0 like Mac OS X) Mobile/15E148";
+    const PIXEL: &str = "Mozilla/5.0 (Linux; Android 14; Pixel 8) Mobile Safari/537.36";
+    const FIREFOX: &str = "Mozilla/5.0 (X11; Linux x86_64) Firefox/128.0";
+
+    fn rule(conditions: RuleConditions, target_url: &str) -> LinkRule {
+        LinkRule::new(conditions.normalized().unwrap(), target_url)
+    }
+
+    #[test]
+    fn parses_visits() {
+        let now = Utc::now();
+        let visit = Visit::new(Some("en;q=0.8, de-AT, *;q=0.1"), Some(IPHONE), now);
+        assert_eq!(visit.language.as_deref(), Some("de-at"));
+        assert_eq!(visit.device, DeviceClass::Ios);
+
+        assert_eq!(
+            Visit::new(None, Some(PIXEL), now).device,
+            DeviceClass::Android
+        );
+        assert_eq!(
+            Visit::new(None, Some(FIREFOX), now).device,
+            DeviceClass::Desktop
+        );
+        assert_eq!(
+            Visit::new(None, Some("curl/8.5.0"), now).device,
+            DeviceClass::Other
+        );
+        // Crawlers posing as phones are not sent to app stores.
+        let crawler =
+            "Mozilla/5.0 (iPhone; CPU iPhone OS 14_7 like Mac OS X) (compatible; Googlebot/2.1)";
+        assert_eq!(
+            Visit::new(None, Some(crawler), now).device,
+            DeviceClass::Other
+        );
+        assert_eq!(Visit::new(Some("*"), None, now).language, None);
+    }
+
+    #[test]
+    fn first_matching_rule_wins() {
+        let launch = Utc::now();
+        let rules = vec![
+            rule(
+                RuleConditions {
+                    devices: vec![DeviceClass::Ios],
+                    ..Default::default()
+                },
+                "https://apps.apple.com/app/id1",
+            ),
+            rule(
+                RuleConditions {
+                    languages: vec!["DE".into()],
+                    not_before: Some(launch),
+                    ..Default::default()
+                },
+                "https://example.com/de/launch",
+            ),
+        ];
+        let target = |language, ua, at| {
+            select_rule(&rules, &Visit::new(language, Some(ua), at)).map(|r| r.target_url.as_str())
+        };
+
+        assert_eq!(
+            target(Some("de"), IPHONE, launch),
+            Some("https://apps.apple.com/app/id1")
+        );
+        assert_eq!(
+            target(Some("de-CH"), FIREFOX, launch),
+            Some("https://example.com/de/launch")
+        );
+        assert_eq!(
+            target(Some("de"), FIREFOX, launch - Duration::seconds(1)),
+            None
+        );
+        assert_eq!(target(Some("den"), FIREFOX, launch), None);
+        assert_eq!(target(None, PIXEL, launch), None);
+    }
+
+    #[test]
+    fn rejects_invalid_conditions() {
+        let now = Utc::now();
+        let invalid = |conditions: RuleConditions| conditions.normalized().unwrap_err().code();
+
+        assert_eq!(
+            invalid(RuleConditions {
+                languages: vec!["en_US".into()],
+                ..Default::default()
+            }),
+            "invalid_language"
+        );
+        assert_eq!(
+            invalid(RuleConditions {
+                not_before: Some(now),
+                not_after: Some(now),
+                ..Default::default()
+            }),
+            "empty_window"
+        );
+    }
+}
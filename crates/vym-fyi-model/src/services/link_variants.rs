@@ -0,0 +1,203 @@
+//! Weighted A/B split targets.
+//!
+//! A link with variants sends each visitor to one of them, picked at random
+//! in proportion to the weights. With sticky assignment the redirect server
+//! remembers the pick in a cookie, so returning visitors keep their variant
+//! for as long as it has a weight.
+
+use rand::Rng;
+use thiserror::Error;
+
+/// Most variants a single link may have.
+pub const MAX_VARIANTS_PER_LINK: usize = 10;
+
+/// Largest weight of a single variant.
+pub const MAX_VARIANT_WEIGHT: u32 = 10_000;
+
+/// Longest variant name; names end up in cookies and analytics.
+const MAX_NAME_LEN: usize = 32;
+
+/// Why a set of variants was rejected.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum LinkVariantError {
+    #[error("invalid variant name '{0}' (use 1-32 of a-z, 0-9, '-' and '_')")]
+    InvalidName(String),
+    #[error("duplicate variant name '{0}'")]
+    DuplicateName(String),
+    #[error("variant weights must be between 0 and {max}")]
+    InvalidWeight { max: u32 },
+    #[error("at least one variant needs a weight above 0")]
+    NoWeight,
+    #[error("a link may have at most {max} variants")]
+    TooMany { max: usize },
+}
+
+impl LinkVariantError {
+    /// Stable machine-readable code for API responses.
+    pub fn code(&self) -> &'static str {
+        match self {
+            LinkVariantError::InvalidName(_) => "invalid_name",
+            LinkVariantError::DuplicateName(_) => "duplicate_name",
+            LinkVariantError::InvalidWeight { .. } => "invalid_weight",
+            LinkVariantError::NoWeight => "no_weight",
+            LinkVariantError::TooMany { .. } => "too_many_variants",
+        }
+    }
+}
+
+/// One target of a split link.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkVariant {
+    /// Short label such as `a` or `new-hero`, unique within the link.
+    pub name: String,
+    pub target_url: String,
+    /// Relative share of new visitors; `0` pauses the variant.
+    pub weight: u32,
+}
+
+/// The split of a link; no variants means the link's own target is used.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LinkVariants {
+    /// Keep visitors on the variant they were first sent to.
+    pub sticky: bool,
+    pub variants: Vec<LinkVariant>,
+}
+
+impl LinkVariants {
+    pub fn is_empty(&self) -> bool {
+        self.variants.is_empty()
+    }
+
+    /// Check names, weights and count.
+    pub fn validate(&self) -> Result<(), LinkVariantError> {
+        if self.variants.len() > MAX_VARIANTS_PER_LINK {
+            return Err(LinkVariantError::TooMany {
+                max: MAX_VARIANTS_PER_LINK,
+            });
+        }
+        for (i, variant) in self.variants.iter().enumerate() {
+            let name = &variant.name;
+            let valid = !name.is_empty()
+                && name.len() <= MAX_NAME_LEN
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+            if !valid {
+                return Err(LinkVariantError::InvalidName(name.clone()));
+            }
+            if self.variants[..i].iter().any(|v| v.name == *name) {
+                return Err(LinkVariantError::DuplicateName(name.clone()));
+            }
+            if variant.weight > MAX_VARIANT_WEIGHT {
+                return Err(LinkVariantError::InvalidWeight {
+                    max: MAX_VARIANT_WEIGHT,
+                });
+            }
+        }
+        if !self.is_empty() && self.total_weight() == 0 {
+            return Err(LinkVariantError::NoWeight);
+        }
+        Ok(())
+    }
+
+    /// Variant for a visit. `assigned` is the variant remembered for the
+    /// visitor; it is kept when sticky assignment is on and the variant
+    /// still has a weight. Otherwise a variant is drawn by weight.
+    pub fn choose(&self, assigned: Option<&str>) -> Option<&LinkVariant> {
+        let total = self.total_weight();
+        if total == 0 {
+            return None;
+        }
+        if self.sticky
+            && let Some(variant) = assigned.and_then(|name| {
+                self.variants
+                    .iter()
+                    .find(|v| v.name == name && v.weight > 0)
+            })
+        {
+            return Some(variant);
+        }
+        self.pick(rand::rng().random_range(0..total))
+    }
+
+    /// Variant whose share of `[0, total_weight)` contains `roll`.
+    fn pick(&self, mut roll: u32) -> Option<&LinkVariant> {
+        self.variants.iter().find(|v| {
+            if roll < v.weight {
+                true
+            } else {
+                roll -= v.weight;
+                false
+            }
+        })
+    }
+
+    fn total_weight(&self) -> u32 {
+        self.variants.iter().map(|v| v.weight).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variant(name: &str, weight: u32) -> LinkVariant {
+        LinkVariant {
+            name: name.into(),
+            target_url: format!("https://example.com/{name}"),
+            weight,
+        }
+    }
+
+    fn split(sticky: bool, variants: Vec<LinkVariant>) -> LinkVariants {
+        LinkVariants { sticky, variants }
+    }
+
+    #[test]
+    fn picks_variants_by_weight() {
+        let split = split(
+            false,
+            vec![variant("a", 70), variant("paused", 0), variant("b", 30)],
+        );
+        let name = |roll| split.pick(roll).map(|v| v.name.as_str());
+        assert_eq!(name(0), Some("a"));
+        assert_eq!(name(69), Some("a"));
+        assert_eq!(name(70), Some("b"));
+        assert_eq!(name(99), Some("b"));
+
+        let chosen = split.choose(None).unwrap();
+        assert_ne!(chosen.name, "paused");
+        assert_eq!(LinkVariants::default().choose(None), None);
+    }
+
+    #[test]
+    fn sticky_assignment_keeps_weighted_variants() {
+        let sticky = split(true, vec![variant("a", 0), variant("b", 1)]);
+        for _ in 0..10 {
+            assert_eq!(sticky.choose(Some("b")).unwrap().name, "b");
+            // Paused or removed variants are reassigned.
+            assert_eq!(sticky.choose(Some("a")).unwrap().name, "b");
+            assert_eq!(sticky.choose(Some("gone")).unwrap().name, "b");
+        }
+
+        let not_sticky = split(false, vec![variant("a", 1), variant("b", 0)]);
+        assert_eq!(not_sticky.choose(Some("b")).unwrap().name, "a");
+    }
+
+    #[test]
+    fn rejects_invalid_variants() {
+        let invalid = |variants| split(false, variants).validate().unwrap_err().code();
+        assert_eq!(invalid(vec![variant("A", 1)]), "invalid_name");
+        assert_eq!(
+            invalid(vec![variant("a", 1), variant("a", 2)]),
+            "duplicate_name"
+        );
+        assert_eq!(invalid(vec![variant("a", 10_001)]), "invalid_weight");
+        assert_eq!(invalid(vec![variant("a", 0)]), "no_weight");
+        assert_eq!(
+            invalid((0..11).map(|i| variant(&i.to_string(), 1)).collect()),
+            "too_many_variants"
+        );
+        assert!(LinkVariants::default().validate().is_ok());
+    }
+}
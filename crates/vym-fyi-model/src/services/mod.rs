@@ -3,8 +3,17 @@ pub mod query_adapter;
 
 pub mod logging;
 
+pub mod api_keys;
 pub mod axum_metrics;
+pub mod client_ip;
 pub mod config;
+pub mod link_rules;
+pub mod link_variants;
+pub mod param_template;
+pub mod rate_limit;
 pub mod repos;
 pub mod slug;
+pub mod slug_policy;
 pub mod static_assets;
+pub mod target_url;
+pub mod user_agent;
@@ -0,0 +1,200 @@
+//! Query parameter templates added to link targets, e.g.
+//! `utm_source={{tenant}}&utm_campaign={{slug}}`.
+//!
+//! Templates are set per tenant (tenants config) and per link. When a link
+//! resolves, the link's parameters override the tenant's, and parameters
+//! already present in the target URL override both, so a hand-written
+//! `utm_source` is never replaced.
+
+use thiserror::Error;
+use url::{Url, form_urlencoded};
+
+/// Placeholders a template value may use.
+const PLACEHOLDERS: &[&str] = &["tenant", "slug"];
+
+/// Why a parameter template was rejected.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ParamTemplateError {
+    #[error("parameter names must not be empty")]
+    EmptyName,
+    #[error("unknown placeholder '{{{{{0}}}}}' (expected {{{{tenant}}}} or {{{{slug}}}})")]
+    UnknownPlaceholder(String),
+    #[error("unterminated placeholder in '{0}'")]
+    Unterminated(String),
+}
+
+impl ParamTemplateError {
+    /// Stable machine-readable code for API responses.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ParamTemplateError::EmptyName => "empty_name",
+            ParamTemplateError::UnknownPlaceholder(_) => "unknown_placeholder",
+            ParamTemplateError::Unterminated(_) => "unterminated_placeholder",
+        }
+    }
+}
+
+/// Values substituted for the placeholders of a template.
+#[derive(Debug, Clone, Copy)]
+pub struct TemplateVars<'a> {
+    /// Name of the owning tenant; empty for links without a tenant.
+    pub tenant: &'a str,
+    pub slug: &'a str,
+}
+
+/// Parsed template: query parameters whose values may contain placeholders.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParamTemplate {
+    params: Vec<(String, String)>,
+}
+
+impl ParamTemplate {
+    /// Parse a template written like a query string (without the `?`).
+    pub fn parse(raw: &str) -> Result<Self, ParamTemplateError> {
+        let params: Vec<(String, String)> =
+            form_urlencoded::parse(raw.trim().trim_start_matches('?').as_bytes())
+                .into_owned()
+                .collect();
+        for (name, value) in &params {
+            if name.is_empty() {
+                return Err(ParamTemplateError::EmptyName);
+            }
+            check_placeholders(value)?;
+        }
+        Ok(Self { params })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.params.is_empty()
+    }
+
+    /// This template with the parameters of `overrides` replacing the
+    /// same-named ones.
+    pub fn merged(&self, overrides: &ParamTemplate) -> ParamTemplate {
+        let mut params: Vec<(String, String)> = self
+            .params
+            .iter()
+            .filter(|(name, _)| !overrides.params.iter().any(|(n, _)| n == name))
+            .cloned()
+            .collect();
+        params.extend(overrides.params.iter().cloned());
+        ParamTemplate { params }
+    }
+
+    /// Add the rendered parameters to `target_url`, skipping names the
+    /// target already has. Targets that are not absolute URLs are returned
+    /// unchanged.
+    pub fn apply(&self, target_url: &str, vars: TemplateVars<'_>) -> String {
+        if self.is_empty() {
+            return target_url.to_string();
+        }
+        let Ok(mut url) = Url::parse(target_url) else {
+            return target_url.to_string();
+        };
+        let existing: Vec<String> = url
+            .query_pairs()
+            .map(|(name, _)| name.into_owned())
+            .collect();
+        let missing: Vec<(&str, String)> = self
+            .params
+            .iter()
+            .filter(|(name, _)| !existing.contains(name))
+            .map(|(name, value)| (name.as_str(), render(value, vars)))
+            .collect();
+        if !missing.is_empty() {
+            url.query_pairs_mut().extend_pairs(missing);
+        }
+        url.into()
+    }
+}
+
+fn check_placeholders(value: &str) -> Result<(), ParamTemplateError> {
+    let mut rest = value;
+    while let Some(start) = rest.find("{{") {
+        let after = &rest[start + 2..];
+        let end = after
+            .find("}}")
+            .ok_or_else(|| ParamTemplateError::Unterminated(value.to_string()))?;
+        let name = after[..end].trim();
+        if !PLACEHOLDERS.contains(&name) {
+            return Err(ParamTemplateError::UnknownPlaceholder(name.to_string()));
+        }
+        rest = &after[end + 2..];
+    }
+    Ok(())
+}
+
+fn render(value: &str, vars: TemplateVars<'_>) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            break;
+        };
+        match after[..end].trim() {
+            "tenant" => out.push_str(vars.tenant),
+            "slug" => out.push_str(vars.slug),
+            _ => {}
+        }
+        rest = &after[end + 2..];
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VARS: TemplateVars<'static> = TemplateVars {
+        tenant: "acme",
+        slug: "spring-sale",
+    };
+
+    #[test]
+    fn renders_placeholders_without_overriding_target_params() {
+        let template =
+            ParamTemplate::parse("utm_source={{tenant}}&utm_campaign={{ slug }}&utm_medium=link")
+                .unwrap();
+        assert_eq!(
+            template.apply("https://example.com/shop?utm_medium=email", VARS),
+            "https://example.com/shop?utm_medium=email&utm_source=acme&utm_campaign=spring-sale"
+        );
+        assert_eq!(
+            ParamTemplate::default().apply("https://example.com/a b", VARS),
+            "https://example.com/a b"
+        );
+    }
+
+    #[test]
+    fn link_template_overrides_tenant_template() {
+        let tenant = ParamTemplate::parse("utm_source={{tenant}}&utm_medium=link").unwrap();
+        let link = ParamTemplate::parse("utm_medium=qr&ref={{slug}}").unwrap();
+        assert_eq!(
+            tenant.merged(&link).apply("https://example.com/", VARS),
+            "https://example.com/?utm_source=acme&utm_medium=qr&ref=spring-sale"
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_or_unterminated_placeholders() {
+        assert_eq!(
+            ParamTemplate::parse("utm_source={{user}}")
+                .unwrap_err()
+                .code(),
+            "unknown_placeholder"
+        );
+        assert_eq!(
+            ParamTemplate::parse("utm_source={{tenant")
+                .unwrap_err()
+                .code(),
+            "unterminated_placeholder"
+        );
+        assert_eq!(
+            ParamTemplate::parse("=x").unwrap_err(),
+            ParamTemplateError::EmptyName
+        );
+    }
+}
@@ -0,0 +1,374 @@
+//! Token-bucket rate limiting as a tower layer.
+//!
+//! The layer is keyed by a caller-supplied function, so each server decides
+//! what a "client" is (API key binding, client IP, ...). Allowed responses
+//! carry `RateLimit-Limit`, `RateLimit-Remaining` and `RateLimit-Reset`;
+//! rejected requests get `429 Too Many Requests` with `Retry-After`.
+
+use std::future::Future;
+use std::num::NonZeroUsize;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Instant;
+
+use axum::{
+    body::Body,
+    http::{HeaderMap, HeaderValue, Request, Response, StatusCode, header::RETRY_AFTER},
+};
+use lru::LruCache;
+use tower::{Layer, Service};
+
+use crate::models::errors::{AppError, AppResult};
+use crate::models::url_shortener::RateLimitPolicy;
+use crate::services::client_ip::{client_ip, client_network};
+
+/// Number of tracked keys above which the least recently used buckets are
+/// evicted.
+const DEFAULT_MAX_KEYS: usize = 100_000;
+
+impl RateLimitPolicy {
+    /// Read `RATE_LIMIT_BURST` and `RATE_LIMIT_PER_SECOND`, falling back to
+    /// `default`. Returns `None` (no limit) when the rate is `0`.
+    pub fn from_env(default: RateLimitPolicy) -> AppResult<Option<Self>> {
+        fn var<T: std::str::FromStr>(name: &str, default: T) -> AppResult<T> {
+            match std::env::var(name) {
+                Ok(raw) => raw
+                    .trim()
+                    .parse()
+                    .map_err(|_| AppError::Config(format!("invalid {}: {}", name, raw))),
+                Err(_) => Ok(default),
+            }
+        }
+
+        let policy = Self {
+            burst: var("RATE_LIMIT_BURST", default.burst)?,
+            per_second: var("RATE_LIMIT_PER_SECOND", default.per_second)?,
+        };
+        if policy.per_second < 0.0 || !policy.per_second.is_finite() {
+            return Err(AppError::Config(format!(
+                "invalid RATE_LIMIT_PER_SECOND: {}",
+                policy.per_second
+            )));
+        }
+        Ok(policy.is_enabled().then_some(policy))
+    }
+
+    /// A zero burst or rate means "no limit".
+    pub fn is_enabled(&self) -> bool {
+        self.burst > 0 && self.per_second > 0.0
+    }
+}
+
+/// What the layer limits a request by.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateLimitKey {
+    pub key: String,
+    /// Low-cardinality kind of key, used as a metrics label.
+    pub kind: &'static str,
+    pub policy: RateLimitPolicy,
+}
+
+impl RateLimitKey {
+    /// Key a request by its client IP (see [`client_ip`]); IPv6 clients
+    /// share a bucket per /64.
+    pub fn client_ip<B>(req: &Request<B>, policy: RateLimitPolicy) -> Option<Self> {
+        client_ip(req).map(|ip| Self {
+            key: format!("ip:{}", client_network(ip)),
+            kind: "ip",
+            policy,
+        })
+    }
+}
+
+/// Outcome of taking a token.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimitDecision {
+    pub allowed: bool,
+    pub limit: u32,
+    pub remaining: u32,
+    /// Seconds until the bucket is full again.
+    pub reset_secs: u64,
+    /// Seconds until the next token is available (when rejected).
+    pub retry_after_secs: u64,
+}
+
+impl RateLimitDecision {
+    /// `429 Too Many Requests` response for a rejected request.
+    pub fn rejection(&self, kind: &'static str) -> Response<Body> {
+        metrics::counter!("rate_limited_requests_total", "kind" => kind).increment(1);
+        let mut response = Response::new(Body::from("rate limit exceeded"));
+        *response.status_mut() = StatusCode::TOO_MANY_REQUESTS;
+        self.apply_headers(response.headers_mut());
+        response
+    }
+
+    fn apply_headers(&self, headers: &mut HeaderMap) {
+        headers.insert("ratelimit-limit", HeaderValue::from(self.limit));
+        headers.insert("ratelimit-remaining", HeaderValue::from(self.remaining));
+        headers.insert("ratelimit-reset", HeaderValue::from(self.reset_secs));
+        if !self.allowed {
+            headers.insert(RETRY_AFTER, HeaderValue::from(self.retry_after_secs));
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// In-memory token buckets, one per key. At most `max_keys` buckets are
+/// kept; beyond that the least recently used ones are dropped, so a flood
+/// of new keys cannot reset the buckets of active clients.
+pub struct RateLimiter {
+    buckets: Mutex<LruCache<String, Bucket>>,
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_KEYS)
+    }
+}
+
+impl RateLimiter {
+    pub fn new(max_keys: usize) -> Self {
+        Self {
+            buckets: Mutex::new(LruCache::new(
+                NonZeroUsize::new(max_keys).unwrap_or(NonZeroUsize::MIN),
+            )),
+        }
+    }
+
+    /// Take one token for `key` as of `now`.
+    pub fn check(&self, key: &str, policy: &RateLimitPolicy, now: Instant) -> RateLimitDecision {
+        let mut buckets = self
+            .buckets
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let bucket = buckets.get_or_insert_mut(key.to_owned(), || Bucket {
+            tokens: f64::from(policy.burst),
+            updated: now,
+        });
+        bucket.tokens = refill(bucket, policy, now);
+        bucket.updated = now;
+
+        let allowed = bucket.tokens >= 1.0;
+        if allowed {
+            bucket.tokens -= 1.0;
+        }
+        decision(bucket, policy, allowed)
+    }
+
+    /// Whether `key` has a token left as of `now`, without taking it.
+    pub fn peek(&self, key: &str, policy: &RateLimitPolicy, now: Instant) -> RateLimitDecision {
+        let buckets = self
+            .buckets
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let bucket = buckets.peek(key).map_or(
+            Bucket {
+                tokens: f64::from(policy.burst),
+                updated: now,
+            },
+            |bucket| Bucket {
+                tokens: refill(bucket, policy, now),
+                updated: now,
+            },
+        );
+        decision(&bucket, policy, bucket.tokens >= 1.0)
+    }
+}
+
+fn decision(bucket: &Bucket, policy: &RateLimitPolicy, allowed: bool) -> RateLimitDecision {
+    let secs = |tokens: f64| (tokens.max(0.0) / policy.per_second).ceil() as u64;
+    RateLimitDecision {
+        allowed,
+        limit: policy.burst,
+        remaining: bucket.tokens.floor() as u32,
+        reset_secs: secs(f64::from(policy.burst) - bucket.tokens),
+        retry_after_secs: secs(1.0 - bucket.tokens).max(1),
+    }
+}
+
+fn refill(bucket: &Bucket, policy: &RateLimitPolicy, now: Instant) -> f64 {
+    let elapsed = now.saturating_duration_since(bucket.updated);
+    (bucket.tokens + elapsed.as_secs_f64() * policy.per_second).min(f64::from(policy.burst))
+}
+
+type BoxFuture<T> = Pin<Box<dyn Future<Output = T> + Send>>;
+
+type KeyFn = dyn Fn(&Request<Body>) -> Option<RateLimitKey> + Send + Sync;
+
+/// Tower layer applying token-bucket limits keyed by `key_fn`. Requests for
+/// which `key_fn` returns `None`, or whose policy is disabled, are not
+/// limited.
+#[derive(Clone)]
+pub struct RateLimitLayer {
+    limiter: Arc<RateLimiter>,
+    key_fn: Arc<KeyFn>,
+}
+
+impl RateLimitLayer {
+    pub fn new<F>(limiter: Arc<RateLimiter>, key_fn: F) -> Self
+    where
+        F: Fn(&Request<Body>) -> Option<RateLimitKey> + Send + Sync + 'static,
+    {
+        Self {
+            limiter,
+            key_fn: Arc::new(key_fn),
+        }
+    }
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimitService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimitService {
+            inner,
+            limiter: self.limiter.clone(),
+            key_fn: self.key_fn.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct RateLimitService<S> {
+    inner: S,
+    limiter: Arc<RateLimiter>,
+    key_fn: Arc<KeyFn>,
+}
+
+impl<S> Service<Request<Body>> for RateLimitService<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response<Body>;
+    type Error = S::Error;
+    type Future = BoxFuture<Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        let Some(key) = (self.key_fn)(&req).filter(|key| key.policy.is_enabled()) else {
+            return Box::pin(self.inner.call(req));
+        };
+
+        let decision = self.limiter.check(&key.key, &key.policy, Instant::now());
+        if !decision.allowed {
+            let response = decision.rejection(key.kind);
+            return Box::pin(async move { Ok(response) });
+        }
+
+        let future = self.inner.call(req);
+        Box::pin(async move {
+            let mut response = future.await?;
+            decision.apply_headers(response.headers_mut());
+            Ok(response)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Router, routing::get};
+    use std::time::Duration;
+    use tower::ServiceExt;
+
+    const POLICY: RateLimitPolicy = RateLimitPolicy {
+        burst: 2,
+        per_second: 1.0,
+    };
+
+    #[test]
+    fn bucket_allows_burst_then_refills() {
+        let limiter = RateLimiter::default();
+        let start = Instant::now();
+
+        let first = limiter.check("k", &POLICY, start);
+        assert!(first.allowed);
+        assert_eq!(first.remaining, 1);
+        assert!(limiter.check("k", &POLICY, start).allowed);
+
+        let rejected = limiter.check("k", &POLICY, start);
+        assert!(!rejected.allowed);
+        assert_eq!(rejected.remaining, 0);
+        assert_eq!(rejected.retry_after_secs, 1);
+        assert_eq!(rejected.reset_secs, 2);
+
+        // Other keys have their own bucket.
+        assert!(limiter.check("other", &POLICY, start).allowed);
+
+        let later = start + Duration::from_millis(1500);
+        assert!(limiter.check("k", &POLICY, later).allowed);
+        assert!(!limiter.check("k", &POLICY, later).allowed);
+    }
+
+    #[test]
+    fn evicts_least_recently_used_buckets_when_full() {
+        let limiter = RateLimiter::new(2);
+        let start = Instant::now();
+        limiter.check("a", &POLICY, start);
+        limiter.check("a", &POLICY, start);
+        limiter.check("b", &POLICY, start);
+
+        // A new key only evicts "b"; "a" keeps its empty bucket.
+        limiter.check("a", &POLICY, start);
+        assert!(limiter.check("c", &POLICY, start).allowed);
+        assert_eq!(limiter.buckets.lock().unwrap().len(), 2);
+        assert!(!limiter.check("a", &POLICY, start).allowed);
+        assert!(limiter.check("b", &POLICY, start).allowed);
+    }
+
+    #[test]
+    fn peek_does_not_take_tokens() {
+        let limiter = RateLimiter::default();
+        let start = Instant::now();
+        assert!(limiter.peek("k", &POLICY, start).allowed);
+        limiter.check("k", &POLICY, start);
+        assert!(limiter.peek("k", &POLICY, start).allowed);
+        limiter.check("k", &POLICY, start);
+        assert!(!limiter.peek("k", &POLICY, start).allowed);
+        assert!(
+            limiter
+                .peek("k", &POLICY, start + Duration::from_secs(1))
+                .allowed
+        );
+    }
+
+    #[tokio::test]
+    async fn layer_sets_headers_and_rejects_with_429() {
+        let limiter = Arc::new(RateLimiter::default());
+        let router = Router::new()
+            .route("/", get(|| async { "ok" }))
+            .route("/free", get(|| async { "ok" }))
+            .layer(RateLimitLayer::new(limiter, |req| {
+                (req.uri().path() != "/free").then(|| RateLimitKey {
+                    key: "client".into(),
+                    kind: "test",
+                    policy: POLICY,
+                })
+            }));
+        let get = |path: &str| Request::get(path).body(Body::empty()).unwrap();
+
+        let ok = router.clone().oneshot(get("/")).await.unwrap();
+        assert_eq!(ok.status(), StatusCode::OK);
+        assert_eq!(ok.headers()["ratelimit-limit"], "2");
+        assert_eq!(ok.headers()["ratelimit-remaining"], "1");
+
+        router.clone().oneshot(get("/")).await.unwrap();
+        let limited = router.clone().oneshot(get("/")).await.unwrap();
+        assert_eq!(limited.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(limited.headers()[RETRY_AFTER], "1");
+
+        let free = router.oneshot(get("/free")).await.unwrap();
+        assert_eq!(free.status(), StatusCode::OK);
+        assert!(free.headers().get("ratelimit-limit").is_none());
+    }
+}
@@ -1,7 +1,18 @@
-use sqlx::{Pool, Postgres, Row};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use sqlx::postgres::PgRow;
+use sqlx::types::Json;
+use sqlx::{Pool, Postgres, QueryBuilder, Row};
 use uuid::Uuid;
 
 use crate::models::errors::{AppError, AppResult};
+use crate::models::url_shortener::{
+    DeviceClass, QueryPassthrough, RedirectType, Role, StatsGranularity, TenantStatus,
+};
+use crate::services::link_rules::{LinkRule, RuleConditions};
+use crate::services::link_variants::{LinkVariant, LinkVariants};
+use crate::services::param_template::{ParamTemplate, TemplateVars};
+use crate::services::slug::SlugGenerator;
 
 /// Repository for tenant-related database operations.
 #[derive(Clone)]
@@ -30,32 +41,268 @@ impl TenantRepository {
             .collect())
     }
 
-    /// Create a new tenant with the given name and return its id.
-    pub async fn create(&self, name: &str) -> AppResult<Uuid> {
+    /// List all tenants as (id, name, number of short links owned).
+    pub async fn list_with_link_counts(&self) -> AppResult<Vec<(Uuid, String, i64)>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT t.id, t.name, COUNT(s.slug) AS link_count
+            FROM tenants t
+            LEFT JOIN short_links s ON s.tenant_id = t.id
+            GROUP BY t.id, t.name
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| (row.get("id"), row.get("name"), row.get("link_count")))
+            .collect())
+    }
+
+    /// Look up a tenant id by name.
+    pub async fn find_by_name(&self, name: &str) -> AppResult<Option<Uuid>> {
+        let row = sqlx::query("SELECT id FROM tenants WHERE name = $1")
+            .bind(name)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(row.map(|row| row.get("id")))
+    }
+
+    /// Create a new tenant with the given name and status and return its id.
+    pub async fn create(&self, name: &str, status: TenantStatus) -> AppResult<Uuid> {
         let id = Uuid::new_v4();
         sqlx::query(
             r#"
             INSERT INTO tenants (id, name, status)
-            VALUES ($1, $2, 'active')
+            VALUES ($1, $2, $3)
             "#,
         )
         .bind(id)
         .bind(name)
+        .bind(status.as_str())
         .execute(&self.pool)
         .await?;
         Ok(id)
     }
 
-    /// Delete a tenant by name.
+    /// Set the status of a tenant by name. Returns `false` if no such tenant exists.
+    pub async fn set_status(&self, name: &str, status: TenantStatus) -> AppResult<bool> {
+        let result = sqlx::query("UPDATE tenants SET status = $2 WHERE name = $1")
+            .bind(name)
+            .bind(status.as_str())
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Set the parameter template of a tenant by name. Returns `true` if it
+    /// changed; unchanged templates are not written, so the redirect cache
+    /// is only flushed on real changes.
+    pub async fn set_param_template(&self, name: &str, template: Option<&str>) -> AppResult<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE tenants SET param_template = $2
+            WHERE name = $1 AND param_template IS DISTINCT FROM $2
+            "#,
+        )
+        .bind(name)
+        .bind(template)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Delete a tenant together with its short links (`short_links` does not
+    /// cascade); API keys are removed by the `api_keys` foreign key.
     pub async fn delete_by_name(&self, name: &str) -> AppResult<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            "DELETE FROM short_links WHERE tenant_id IN (SELECT id FROM tenants WHERE name = $1)",
+        )
+        .bind(name)
+        .execute(&mut *tx)
+        .await?;
         sqlx::query("DELETE FROM tenants WHERE name = $1")
             .bind(name)
-            .execute(&self.pool)
+            .execute(&mut *tx)
             .await?;
+        tx.commit().await?;
         Ok(())
     }
 }
 
+/// Columns of `short_links` returned by the link management queries.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShortLinkRecord {
+    pub slug: String,
+    pub target_url: String,
+    pub is_active: bool,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub redirect_type: RedirectType,
+    pub query_passthrough: QueryPassthrough,
+    pub path_passthrough: bool,
+    /// The link's own parameter template.
+    pub param_template: Option<String>,
+    /// Where the link currently redirects: the target with the tenant's and
+    /// the link's parameter templates applied.
+    pub resolved_url: String,
+    /// Changes on every write; see [`ShortLinkRecord::etag`].
+    pub version: i64,
+}
+
+impl ShortLinkRecord {
+    /// Column list matching `from_row`, for use in SELECT/RETURNING clauses.
+    pub const COLUMNS: &'static str = "slug, target_url, is_active, expires_at, redirect_type, \
+         query_passthrough, path_passthrough, param_template, version, \
+         (SELECT t.name FROM tenants t WHERE t.id = short_links.tenant_id) AS tenant_name, \
+         (SELECT t.param_template FROM tenants t WHERE t.id = short_links.tenant_id) \
+         AS tenant_param_template";
+
+    pub fn from_row(row: &PgRow) -> Self {
+        let slug: String = row.get("slug");
+        let target_url: String = row.get("target_url");
+        let param_template: Option<String> = row.get("param_template");
+        let tenant_name: Option<String> = row.get("tenant_name");
+        let resolved_url = param_template_from_columns(
+            row.get("tenant_param_template"),
+            param_template.as_deref(),
+        )
+        .apply(
+            &target_url,
+            TemplateVars {
+                tenant: tenant_name.as_deref().unwrap_or_default(),
+                slug: &slug,
+            },
+        );
+        Self {
+            slug,
+            target_url,
+            is_active: row.get("is_active"),
+            expires_at: row.get("expires_at"),
+            redirect_type: redirect_type_from_column(row.get("redirect_type")),
+            query_passthrough: query_passthrough_from_column(row.get("query_passthrough")),
+            path_passthrough: row.get("path_passthrough"),
+            param_template,
+            resolved_url,
+            version: row.get("version"),
+        }
+    }
+
+    /// Strong entity tag of this version of the link, e.g. `"42"`.
+    pub fn etag(&self) -> String {
+        format!("\"{}\"", self.version)
+    }
+}
+
+/// The column is constrained to supported codes; anything else falls back
+/// to the default.
+fn redirect_type_from_column(code: i16) -> RedirectType {
+    u16::try_from(code)
+        .ok()
+        .and_then(|code| RedirectType::try_from(code).ok())
+        .unwrap_or_default()
+}
+
+/// Like [`redirect_type_from_column`], for the constrained
+/// `query_passthrough` column.
+fn query_passthrough_from_column(value: &str) -> QueryPassthrough {
+    value.parse().unwrap_or_default()
+}
+
+/// Merge the stored tenant and link templates. Both are validated before
+/// they are stored; one that no longer parses is ignored.
+fn param_template_from_columns(tenant: Option<&str>, link: Option<&str>) -> ParamTemplate {
+    let parse = |raw: Option<&str>| {
+        raw.and_then(|raw| ParamTemplate::parse(raw).ok())
+            .unwrap_or_default()
+    };
+    parse(tenant).merged(&parse(link))
+}
+
+/// Fields written when a link is created or replaced.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinkFields {
+    pub target_url: String,
+    pub is_active: bool,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub redirect_type: RedirectType,
+    pub query_passthrough: QueryPassthrough,
+    /// Prefix link: `/{slug}/{rest}` redirects to the target with `rest`
+    /// appended to its path.
+    pub path_passthrough: bool,
+    /// Query parameters added to the target on redirect; see
+    /// [`crate::services::param_template`].
+    pub param_template: Option<String>,
+}
+
+impl LinkFields {
+    /// An active link to `target_url` with default settings.
+    pub fn new(target_url: impl Into<String>) -> Self {
+        Self {
+            target_url: target_url.into(),
+            is_active: true,
+            expires_at: None,
+            redirect_type: RedirectType::default(),
+            query_passthrough: QueryPassthrough::default(),
+            path_passthrough: false,
+            param_template: None,
+        }
+    }
+}
+
+/// Partial update of a link; `None` leaves a column untouched. For
+/// `expires_at` and `param_template`, `Some(None)` clears the value.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LinkUpdate {
+    pub target_url: Option<String>,
+    pub is_active: Option<bool>,
+    pub expires_at: Option<Option<DateTime<Utc>>>,
+    pub redirect_type: Option<RedirectType>,
+    pub query_passthrough: Option<QueryPassthrough>,
+    pub path_passthrough: Option<bool>,
+    pub param_template: Option<Option<String>>,
+}
+
+impl From<LinkFields> for LinkUpdate {
+    fn from(fields: LinkFields) -> Self {
+        Self {
+            target_url: Some(fields.target_url),
+            is_active: Some(fields.is_active),
+            expires_at: Some(fields.expires_at),
+            redirect_type: Some(fields.redirect_type),
+            query_passthrough: Some(fields.query_passthrough),
+            path_passthrough: Some(fields.path_passthrough),
+            param_template: Some(fields.param_template),
+        }
+    }
+}
+
+/// Postgres channel notified (by triggers on `short_links` and `tenants`)
+/// whenever a link may resolve differently. The payload is the slug, or empty
+/// when every cached resolution should be dropped.
+pub const SHORT_LINK_CHANGES_CHANNEL: &str = "short_link_changes";
+
+/// Outcome of resolving a slug on the redirect path.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedLink {
+    pub target_url: String,
+    /// Status of the owning tenant; links without a tenant count as active.
+    pub tenant_status: TenantStatus,
+    pub tenant_id: Option<Uuid>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub redirect_type: RedirectType,
+    pub query_passthrough: QueryPassthrough,
+    pub path_passthrough: bool,
+    /// The tenant's and the link's parameter templates, merged.
+    pub param_template: ParamTemplate,
+    pub tenant_name: Option<String>,
+    /// Conditional redirect rules in evaluation order.
+    pub rules: Vec<LinkRule>,
+    /// A/B split targets used when no rule matches.
+    pub variants: LinkVariants,
+}
+
 /// Repository for short-link operations.
 #[derive(Clone)]
 pub struct ShortLinkRepository {
@@ -67,159 +314,323 @@ impl ShortLinkRepository {
         Self { pool }
     }
 
-    /// Upsert a short link by slug, target_url, and tenant_id.
-    /// Returns (slug, target_url, is_active).
-    pub async fn upsert(
+    /// Insert a new short link. Fails with [`AppError::Conflict`] when the
+    /// slug is already taken, by this tenant or any other.
+    pub async fn create(
         &self,
         slug: &str,
-        target_url: &str,
+        fields: &LinkFields,
         tenant_id: Uuid,
-    ) -> AppResult<(String, String, bool)> {
-        let row = sqlx::query(
+    ) -> AppResult<ShortLinkRecord> {
+        let row = self
+            .insert(slug, fields, tenant_id, "ON CONFLICT (slug) DO NOTHING")
+            .await?;
+
+        row.as_ref()
+            .map(ShortLinkRecord::from_row)
+            .ok_or_else(|| AppError::Conflict(format!("slug {} already exists", slug)))
+    }
+
+    /// Create the link, or replace every field of the tenant's existing link
+    /// with that slug. Returns the link and whether it was created. Fails
+    /// with [`AppError::Conflict`] when another tenant owns the slug.
+    pub async fn replace(
+        &self,
+        slug: &str,
+        fields: &LinkFields,
+        tenant_id: Uuid,
+    ) -> AppResult<(ShortLinkRecord, bool)> {
+        let row = self
+            .insert(
+                slug,
+                fields,
+                tenant_id,
+                r#"
+                ON CONFLICT (slug) DO UPDATE
+                    SET target_url = EXCLUDED.target_url,
+                        is_active = EXCLUDED.is_active,
+                        expires_at = EXCLUDED.expires_at,
+                        redirect_type = EXCLUDED.redirect_type,
+                        query_passthrough = EXCLUDED.query_passthrough,
+                        path_passthrough = EXCLUDED.path_passthrough,
+                        param_template = EXCLUDED.param_template,
+                        version = nextval('short_link_version_seq')
+                    WHERE short_links.tenant_id = EXCLUDED.tenant_id
+                "#,
+            )
+            .await?;
+
+        match row {
+            Some(row) => Ok((ShortLinkRecord::from_row(&row), row.get("inserted"))),
+            None => Err(AppError::Conflict(
+                "slug already exists for a different tenant".into(),
+            )),
+        }
+    }
+
+    /// Insert `fields` as `slug`, resolving slug conflicts with
+    /// `on_conflict`. Returns the stored row (with an `inserted` flag that
+    /// is false when an existing row was updated), or `None` when the
+    /// conflict clause skipped the row.
+    async fn insert(
+        &self,
+        slug: &str,
+        fields: &LinkFields,
+        tenant_id: Uuid,
+        on_conflict: &str,
+    ) -> AppResult<Option<PgRow>> {
+        let row = sqlx::query(&format!(
             r#"
-            INSERT INTO short_links (slug, target_url, is_active, tenant_id)
-            VALUES ($1, $2, TRUE, $3)
-            ON CONFLICT (slug) DO UPDATE
-                SET target_url = EXCLUDED.target_url,
-                    is_active = TRUE
-                WHERE short_links.tenant_id = EXCLUDED.tenant_id
-            RETURNING slug, target_url, is_active
+            INSERT INTO short_links
+                (slug, target_url, is_active, expires_at, redirect_type,
+                 query_passthrough, path_passthrough, param_template, tenant_id)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            {}
+            RETURNING {}, (xmax = 0) AS inserted
             "#,
-        )
+            on_conflict,
+            ShortLinkRecord::COLUMNS
+        ))
         .bind(slug)
-        .bind(target_url)
+        .bind(&fields.target_url)
+        .bind(fields.is_active)
+        .bind(fields.expires_at)
+        .bind(fields.redirect_type.status_code() as i16)
+        .bind(fields.query_passthrough.as_str())
+        .bind(fields.path_passthrough)
+        .bind(fields.param_template.as_deref())
         .bind(tenant_id)
         .fetch_optional(&self.pool)
         .await?;
 
-        if let Some(row) = row {
-            Ok((
-                row.get("slug"),
-                row.get("target_url"),
-                row.get::<bool, _>("is_active"),
-            ))
-        } else {
-            Err(AppError::Conflict(
-                "slug already exists for a different tenant".into(),
-            ))
-        }
+        Ok(row)
     }
 
-    /// List short links for a single tenant as (slug, target_url, is_active)
-    /// with pagination support.
+    /// List short links for a single tenant with pagination support.
     pub async fn list_by_tenant(
         &self,
         tenant_id: Uuid,
         limit: i64,
         offset: i64,
-    ) -> AppResult<Vec<(String, String, bool)>> {
-        let rows = sqlx::query(
+    ) -> AppResult<Vec<ShortLinkRecord>> {
+        let rows = sqlx::query(&format!(
             r#"
-            SELECT slug, target_url, is_active
+            SELECT {}
             FROM short_links
             WHERE tenant_id = $1
             ORDER BY created_at DESC
             LIMIT $2 OFFSET $3
             "#,
-        )
+            ShortLinkRecord::COLUMNS
+        ))
         .bind(tenant_id)
         .bind(limit)
         .bind(offset)
         .fetch_all(&self.pool)
         .await?;
 
-        Ok(rows
-            .into_iter()
-            .map(|row| {
-                (
-                    row.get("slug"),
-                    row.get("target_url"),
-                    row.get::<bool, _>("is_active"),
-                )
-            })
-            .collect())
+        Ok(rows.iter().map(ShortLinkRecord::from_row).collect())
     }
 
     /// List all short links (any tenant) with pagination.
-    pub async fn list_paginated(
-        &self,
-        limit: i64,
-        offset: i64,
-    ) -> AppResult<Vec<(String, String, bool)>> {
-        let rows = sqlx::query(
+    pub async fn list_paginated(&self, limit: i64, offset: i64) -> AppResult<Vec<ShortLinkRecord>> {
+        let rows = sqlx::query(&format!(
             r#"
-            SELECT slug, target_url, is_active
+            SELECT {}
             FROM short_links
             ORDER BY created_at DESC
             LIMIT $1 OFFSET $2
             "#,
-        )
+            ShortLinkRecord::COLUMNS
+        ))
         .bind(limit)
         .bind(offset)
         .fetch_all(&self.pool)
         .await?;
 
-        Ok(rows
-            .into_iter()
-            .map(|row| {
-                (
-                    row.get("slug"),
-                    row.get("target_url"),
-                    row.get::<bool, _>("is_active"),
-                )
-            })
-            .collect())
+        Ok(rows.iter().map(ShortLinkRecord::from_row).collect())
     }
 
-    /// Resolve a slug to a target URL, if any.
-    pub async fn resolve(&self, slug: &str) -> AppResult<Option<String>> {
-        let row = sqlx::query(
+    /// Fetch a single short link by slug.
+    /// When `tenant_id` is `Some`, only links owned by that tenant are visible.
+    pub async fn get(
+        &self,
+        slug: &str,
+        tenant_id: Option<Uuid>,
+    ) -> AppResult<Option<ShortLinkRecord>> {
+        let row = sqlx::query(&format!(
             r#"
-            SELECT target_url
+            SELECT {}
             FROM short_links
             WHERE slug = $1
-              AND is_active = TRUE
-              AND (expires_at IS NULL OR expires_at > NOW())
+              AND ($2::uuid IS NULL OR tenant_id = $2)
+            "#,
+            ShortLinkRecord::COLUMNS
+        ))
+        .bind(slug)
+        .bind(tenant_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.as_ref().map(ShortLinkRecord::from_row))
+    }
+
+    /// Partially update a short link (see [`LinkUpdate`]). With
+    /// `expected_versions`, the link is only updated while its version is
+    /// one of them. Returns the updated link, or `None` when the slug does
+    /// not exist (or is not visible to `tenant_id`) or its version did not
+    /// match.
+    pub async fn update(
+        &self,
+        slug: &str,
+        tenant_id: Option<Uuid>,
+        changes: &LinkUpdate,
+        expected_versions: Option<&[i64]>,
+    ) -> AppResult<Option<ShortLinkRecord>> {
+        let row = sqlx::query(&format!(
+            r#"
+            UPDATE short_links
+            SET target_url = COALESCE($3, target_url),
+                is_active = COALESCE($4, is_active),
+                expires_at = CASE WHEN $5 THEN $6 ELSE expires_at END,
+                redirect_type = COALESCE($7, redirect_type),
+                query_passthrough = COALESCE($8, query_passthrough),
+                path_passthrough = COALESCE($9, path_passthrough),
+                param_template = CASE WHEN $10 THEN $11 ELSE param_template END,
+                version = nextval('short_link_version_seq')
+            WHERE slug = $1
+              AND ($2::uuid IS NULL OR tenant_id = $2)
+              AND ($12::bigint[] IS NULL OR version = ANY($12))
+            RETURNING {}
+            "#,
+            ShortLinkRecord::COLUMNS
+        ))
+        .bind(slug)
+        .bind(tenant_id)
+        .bind(changes.target_url.as_deref())
+        .bind(changes.is_active)
+        .bind(changes.expires_at.is_some())
+        .bind(changes.expires_at.flatten())
+        .bind(changes.redirect_type.map(|t| t.status_code() as i16))
+        .bind(changes.query_passthrough.map(|q| q.as_str()))
+        .bind(changes.path_passthrough)
+        .bind(changes.param_template.is_some())
+        .bind(changes.param_template.as_ref().and_then(Option::as_deref))
+        .bind(expected_versions)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.as_ref().map(ShortLinkRecord::from_row))
+    }
+
+    /// Delete a short link by slug. Returns `true` if a row was removed.
+    pub async fn delete(&self, slug: &str, tenant_id: Option<Uuid>) -> AppResult<bool> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM short_links
+            WHERE slug = $1
+              AND ($2::uuid IS NULL OR tenant_id = $2)
+            "#,
+        )
+        .bind(slug)
+        .bind(tenant_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Resolve an active, unexpired slug to its target, rules, variants and
+    /// tenant status in one round trip.
+    pub async fn resolve(&self, slug: &str) -> AppResult<Option<ResolvedLink>> {
+        let row = sqlx::query(
+            r#"
+            SELECT s.target_url, s.expires_at, s.tenant_id, s.redirect_type,
+                   s.query_passthrough, s.path_passthrough, s.param_template,
+                   s.sticky_variants,
+                   t.name AS tenant_name, t.param_template AS tenant_param_template,
+                   COALESCE(t.status, 'active') AS tenant_status,
+                   r.rules, v.variants
+            FROM short_links s
+            LEFT JOIN tenants t ON t.id = s.tenant_id
+            CROSS JOIN LATERAL (
+                SELECT COALESCE(json_agg(json_build_object(
+                           'id', id, 'languages', languages, 'devices', devices,
+                           'not_before', not_before, 'not_after', not_after,
+                           'target_url', target_url
+                       ) ORDER BY position, id), '[]') AS rules
+                FROM link_rules
+                WHERE slug = s.slug
+            ) r
+            CROSS JOIN LATERAL (
+                SELECT COALESCE(json_agg(json_build_object(
+                           'name', name, 'weight', weight, 'target_url', target_url
+                       ) ORDER BY position, name), '[]') AS variants
+                FROM link_variants
+                WHERE slug = s.slug
+            ) v
+            WHERE s.slug = $1
+              AND s.is_active = TRUE
+              AND (s.expires_at IS NULL OR s.expires_at > NOW())
             "#,
         )
         .bind(slug)
         .fetch_optional(&self.pool)
         .await?;
+        let Some(r) = row else {
+            return Ok(None);
+        };
+        let Json(rules) = r.get::<Json<Vec<LinkRuleRow>>, _>("rules");
+        let Json(variants) = r.get::<Json<Vec<LinkVariantRow>>, _>("variants");
 
-        Ok(row.map(|r| r.get("target_url")))
+        Ok(Some(ResolvedLink {
+            target_url: r.get("target_url"),
+            tenant_status: r.get::<String, _>("tenant_status").parse()?,
+            tenant_id: r.get("tenant_id"),
+            expires_at: r.get("expires_at"),
+            redirect_type: redirect_type_from_column(r.get("redirect_type")),
+            query_passthrough: query_passthrough_from_column(r.get("query_passthrough")),
+            path_passthrough: r.get("path_passthrough"),
+            param_template: param_template_from_columns(
+                r.get("tenant_param_template"),
+                r.get("param_template"),
+            ),
+            tenant_name: r.get("tenant_name"),
+            rules: rules.into_iter().map(LinkRule::from).collect(),
+            variants: LinkVariants {
+                sticky: r.get("sticky_variants"),
+                variants: variants.into_iter().map(LinkVariant::from).collect(),
+            },
+        }))
     }
 
-    /// Create a short link with a generated slug (at least `min_len` characters).
-    /// On rare collisions, this will retry a few times before failing.
+    /// Create a short link with a slug from `generator` (at least `min_len`
+    /// characters). On rare collisions, this will retry a few times before
+    /// failing.
     pub async fn create_with_generated_slug(
         &self,
-        target_url: &str,
+        fields: &LinkFields,
+        generator: &dyn SlugGenerator,
         min_len: usize,
         tenant_id: Uuid,
-    ) -> AppResult<(String, String, bool)> {
+    ) -> AppResult<ShortLinkRecord> {
         const MAX_ATTEMPTS: usize = 5;
         for _ in 0..MAX_ATTEMPTS {
-            let slug = crate::services::slug::generate_slug(min_len);
-            let row = sqlx::query(
-                r#"
-                INSERT INTO short_links (slug, target_url, is_active, tenant_id)
-                VALUES ($1, $2, TRUE, $3)
-                ON CONFLICT (slug) DO NOTHING
-                RETURNING slug, target_url, is_active
-                "#,
-            )
-            .bind(&slug)
-            .bind(target_url)
-            .bind(tenant_id)
-            .fetch_optional(&self.pool)
-            .await?;
+            let sequence = if generator.needs_sequence() {
+                let value: i64 = sqlx::query_scalar("SELECT nextval('short_link_slug_seq')")
+                    .fetch_one(&self.pool)
+                    .await?;
+                Some(value as u64)
+            } else {
+                None
+            };
+            let slug = generator.generate(min_len, sequence);
+            let row = self
+                .insert(&slug, fields, tenant_id, "ON CONFLICT (slug) DO NOTHING")
+                .await?;
 
             if let Some(row) = row {
-                return Ok((
-                    row.get("slug"),
-                    row.get("target_url"),
-                    row.get::<bool, _>("is_active"),
-                ));
+                return Ok(ShortLinkRecord::from_row(&row));
             }
         }
 
@@ -229,10 +640,587 @@ impl ShortLinkRepository {
     }
 }
 
+/// Row of `api_keys`, joined with the owning tenant's name.
+#[derive(Debug, Clone)]
+pub struct ApiKeyRecord {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub tenant_name: String,
+    pub tenant_status: TenantStatus,
+    pub name: String,
+    /// Lookup digest (see `ApiKeyLookup`).
+    pub key_lookup: String,
+    pub key_hash: String,
+    pub role: Role,
+    pub created_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl ApiKeyRecord {
+    const SELECT: &'static str = r#"
+        SELECT k.id, k.tenant_id, t.name AS tenant_name, t.status AS tenant_status,
+               k.name, k.key_lookup, k.key_hash, k.role, k.created_at, k.revoked_at
+        FROM api_keys k
+        JOIN tenants t ON t.id = k.tenant_id
+    "#;
+
+    fn from_row(row: &PgRow) -> AppResult<Self> {
+        Ok(Self {
+            id: row.get("id"),
+            tenant_id: row.get("tenant_id"),
+            tenant_name: row.get("tenant_name"),
+            tenant_status: row.get::<String, _>("tenant_status").parse()?,
+            name: row.get("name"),
+            key_lookup: row.get("key_lookup"),
+            key_hash: row.get("key_hash"),
+            role: row.get::<String, _>("role").parse()?,
+            created_at: row.get("created_at"),
+            revoked_at: row.get("revoked_at"),
+        })
+    }
+}
+
+/// Repository for hashed API keys.
+#[derive(Clone)]
+pub struct ApiKeyRepository {
+    pool: Pool<Postgres>,
+}
+
+impl ApiKeyRepository {
+    pub fn new(pool: Pool<Postgres>) -> Self {
+        Self { pool }
+    }
+
+    /// Non-revoked keys whose lookup digest matches; callers must still
+    /// verify the full key against `key_hash`.
+    pub async fn find_active_by_lookup(&self, key_lookup: &str) -> AppResult<Vec<ApiKeyRecord>> {
+        let rows = sqlx::query(&format!(
+            "{} WHERE k.key_lookup = $1 AND k.revoked_at IS NULL ORDER BY k.created_at",
+            ApiKeyRecord::SELECT
+        ))
+        .bind(key_lookup)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(ApiKeyRecord::from_row).collect()
+    }
+
+    /// Non-revoked keys of a tenant with the given name.
+    pub async fn list_active_by_name(
+        &self,
+        tenant_id: Uuid,
+        name: &str,
+    ) -> AppResult<Vec<ApiKeyRecord>> {
+        let rows = sqlx::query(&format!(
+            "{} WHERE k.tenant_id = $1 AND k.name = $2 AND k.revoked_at IS NULL",
+            ApiKeyRecord::SELECT
+        ))
+        .bind(tenant_id)
+        .bind(name)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(ApiKeyRecord::from_row).collect()
+    }
+
+    /// All keys of a tenant (including revoked ones), newest first.
+    pub async fn list_by_tenant(&self, tenant_id: Uuid) -> AppResult<Vec<ApiKeyRecord>> {
+        let rows = sqlx::query(&format!(
+            "{} WHERE k.tenant_id = $1 ORDER BY k.created_at DESC",
+            ApiKeyRecord::SELECT
+        ))
+        .bind(tenant_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(ApiKeyRecord::from_row).collect()
+    }
+
+    /// Store a new hashed key and return the stored row.
+    pub async fn create(
+        &self,
+        tenant_id: Uuid,
+        name: &str,
+        key_lookup: &str,
+        key_hash: &str,
+        role: &Role,
+    ) -> AppResult<ApiKeyRecord> {
+        let row = sqlx::query(
+            r#"
+            INSERT INTO api_keys (id, tenant_id, name, key_lookup, key_hash, role)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING id, tenant_id,
+                      (SELECT name FROM tenants WHERE id = $2) AS tenant_name,
+                      (SELECT status FROM tenants WHERE id = $2) AS tenant_status,
+                      name, key_lookup, key_hash, role, created_at, revoked_at
+            "#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(tenant_id)
+        .bind(name)
+        .bind(key_lookup)
+        .bind(key_hash)
+        .bind(role.as_str())
+        .fetch_one(&self.pool)
+        .await?;
+
+        ApiKeyRecord::from_row(&row)
+    }
+
+    /// Mark a tenant's key as revoked. Returns `false` if it was unknown to
+    /// that tenant or already revoked.
+    pub async fn revoke(&self, tenant_id: Uuid, id: Uuid) -> AppResult<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE api_keys
+            SET revoked_at = NOW()
+            WHERE id = $1 AND tenant_id = $2 AND revoked_at IS NULL
+            "#,
+        )
+        .bind(id)
+        .bind(tenant_id)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+/// A single redirect, as recorded in `link_clicks`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClickEvent {
+    pub slug: String,
+    pub tenant_id: Option<Uuid>,
+    pub clicked_at: DateTime<Utc>,
+    /// Host of the `Referer` header, if any.
+    pub referrer: Option<String>,
+    /// Coarse user-agent class (`bot`, `mobile`, `desktop`, `other`).
+    pub user_agent_class: String,
+    /// Reserved for GeoIP lookups; not populated yet.
+    pub country: Option<String>,
+    /// Name of the A/B variant the visitor was sent to.
+    pub variant: Option<String>,
+}
+
+/// Repository for conditional redirect rules (`link_rules`).
+#[derive(Clone)]
+pub struct LinkRuleRepository {
+    pool: Pool<Postgres>,
+}
+
+impl LinkRuleRepository {
+    pub fn new(pool: Pool<Postgres>) -> Self {
+        Self { pool }
+    }
+
+    /// Rules of a link in evaluation order.
+    pub async fn list(&self, slug: &str) -> AppResult<Vec<LinkRule>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, languages, devices, not_before, not_after, target_url
+            FROM link_rules
+            WHERE slug = $1
+            ORDER BY position, id
+            "#,
+        )
+        .bind(slug)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(link_rule_from_row).collect())
+    }
+
+    /// Replace all rules of a link with `rules`, in that order.
+    pub async fn replace(&self, slug: &str, rules: &[LinkRule]) -> AppResult<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM link_rules WHERE slug = $1")
+            .bind(slug)
+            .execute(&mut *tx)
+            .await?;
+
+        if !rules.is_empty() {
+            let mut qb = QueryBuilder::<Postgres>::new(
+                "INSERT INTO link_rules \
+                 (id, slug, position, languages, devices, not_before, not_after, target_url) ",
+            );
+            qb.push_values(rules.iter().enumerate(), |mut row, (position, rule)| {
+                row.push_bind(rule.id)
+                    .push_bind(slug)
+                    .push_bind(position as i32)
+                    .push_bind(&rule.conditions.languages)
+                    .push_bind(device_columns(&rule.conditions.devices))
+                    .push_bind(rule.conditions.not_before)
+                    .push_bind(rule.conditions.not_after)
+                    .push_bind(&rule.target_url);
+            });
+            qb.build().execute(&mut *tx).await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Add a rule after the link's existing ones.
+    pub async fn append(&self, slug: &str, rule: &LinkRule) -> AppResult<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO link_rules
+                (id, slug, position, languages, devices, not_before, not_after, target_url)
+            VALUES ($1, $2,
+                    (SELECT COALESCE(MAX(position) + 1, 0) FROM link_rules WHERE slug = $2),
+                    $3, $4, $5, $6, $7)
+            "#,
+        )
+        .bind(rule.id)
+        .bind(slug)
+        .bind(&rule.conditions.languages)
+        .bind(device_columns(&rule.conditions.devices))
+        .bind(rule.conditions.not_before)
+        .bind(rule.conditions.not_after)
+        .bind(&rule.target_url)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Delete one rule of a link. Returns `true` if a row was removed.
+    pub async fn delete(&self, slug: &str, id: Uuid) -> AppResult<bool> {
+        let result = sqlx::query("DELETE FROM link_rules WHERE slug = $1 AND id = $2")
+            .bind(slug)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+fn device_columns(devices: &[DeviceClass]) -> Vec<&'static str> {
+    devices.iter().map(DeviceClass::as_str).collect()
+}
+
+/// A `link_rules` row, read directly or aggregated as JSON by
+/// [`ShortLinkRepository::resolve`].
+#[derive(Deserialize)]
+struct LinkRuleRow {
+    id: Uuid,
+    languages: Vec<String>,
+    devices: Vec<String>,
+    not_before: Option<DateTime<Utc>>,
+    not_after: Option<DateTime<Utc>>,
+    target_url: String,
+}
+
+/// Devices are validated on write; unknown values are skipped.
+impl From<LinkRuleRow> for LinkRule {
+    fn from(row: LinkRuleRow) -> Self {
+        LinkRule {
+            id: row.id,
+            conditions: RuleConditions {
+                languages: row.languages,
+                devices: row.devices.iter().filter_map(|d| d.parse().ok()).collect(),
+                not_before: row.not_before,
+                not_after: row.not_after,
+            },
+            target_url: row.target_url,
+        }
+    }
+}
+
+fn link_rule_from_row(row: &PgRow) -> LinkRule {
+    LinkRuleRow {
+        id: row.get("id"),
+        languages: row.get("languages"),
+        devices: row.get("devices"),
+        not_before: row.get("not_before"),
+        not_after: row.get("not_after"),
+        target_url: row.get("target_url"),
+    }
+    .into()
+}
+
+/// A `link_variants` row, read directly or aggregated as JSON by
+/// [`ShortLinkRepository::resolve`].
+#[derive(Deserialize)]
+struct LinkVariantRow {
+    name: String,
+    weight: i32,
+    target_url: String,
+}
+
+impl From<LinkVariantRow> for LinkVariant {
+    fn from(row: LinkVariantRow) -> Self {
+        LinkVariant {
+            name: row.name,
+            target_url: row.target_url,
+            weight: row.weight.max(0) as u32,
+        }
+    }
+}
+
+/// Repository for A/B split targets (`link_variants`).
+#[derive(Clone)]
+pub struct LinkVariantRepository {
+    pool: Pool<Postgres>,
+}
+
+impl LinkVariantRepository {
+    pub fn new(pool: Pool<Postgres>) -> Self {
+        Self { pool }
+    }
+
+    /// Variants of a link in the order they were given.
+    pub async fn list(&self, slug: &str) -> AppResult<Vec<LinkVariant>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT name, weight, target_url
+            FROM link_variants
+            WHERE slug = $1
+            ORDER BY position, name
+            "#,
+        )
+        .bind(slug)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|r| {
+                LinkVariantRow {
+                    name: r.get("name"),
+                    weight: r.get("weight"),
+                    target_url: r.get("target_url"),
+                }
+                .into()
+            })
+            .collect())
+    }
+
+    /// The link's variants and sticky setting.
+    pub async fn get(&self, slug: &str) -> AppResult<LinkVariants> {
+        let sticky = sqlx::query_scalar("SELECT sticky_variants FROM short_links WHERE slug = $1")
+            .bind(slug)
+            .fetch_optional(&self.pool)
+            .await?
+            .unwrap_or(false);
+        Ok(LinkVariants {
+            sticky,
+            variants: self.list(slug).await?,
+        })
+    }
+
+    /// Replace the link's variants and sticky setting.
+    pub async fn replace(&self, slug: &str, split: &LinkVariants) -> AppResult<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            "UPDATE short_links SET sticky_variants = $2 \
+             WHERE slug = $1 AND sticky_variants IS DISTINCT FROM $2",
+        )
+        .bind(slug)
+        .bind(split.sticky)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query("DELETE FROM link_variants WHERE slug = $1")
+            .bind(slug)
+            .execute(&mut *tx)
+            .await?;
+
+        if !split.is_empty() {
+            let mut qb = QueryBuilder::<Postgres>::new(
+                "INSERT INTO link_variants (slug, name, position, weight, target_url) ",
+            );
+            qb.push_values(
+                split.variants.iter().enumerate(),
+                |mut row, (position, variant)| {
+                    row.push_bind(slug)
+                        .push_bind(&variant.name)
+                        .push_bind(position as i32)
+                        .push_bind(variant.weight as i32)
+                        .push_bind(&variant.target_url);
+                },
+            );
+            qb.build().execute(&mut *tx).await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
+/// Repository for click analytics.
+#[derive(Clone)]
+pub struct ClickRepository {
+    pool: Pool<Postgres>,
+}
+
+impl ClickRepository {
+    pub fn new(pool: Pool<Postgres>) -> Self {
+        Self { pool }
+    }
+
+    /// Insert a batch of click events in a single statement.
+    pub async fn insert_batch(&self, events: &[ClickEvent]) -> AppResult<()> {
+        if events.is_empty() {
+            return Ok(());
+        }
+
+        let mut qb = QueryBuilder::<Postgres>::new(
+            "INSERT INTO link_clicks \
+             (slug, tenant_id, clicked_at, referrer, user_agent_class, country, variant) ",
+        );
+        qb.push_values(events, |mut row, event| {
+            row.push_bind(&event.slug)
+                .push_bind(event.tenant_id)
+                .push_bind(event.clicked_at)
+                .push_bind(&event.referrer)
+                .push_bind(&event.user_agent_class)
+                .push_bind(&event.country)
+                .push_bind(&event.variant);
+        });
+        qb.build().execute(&self.pool).await?;
+        Ok(())
+    }
+}
+
+/// Aggregated clicks of one link over a time window.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClickStats {
+    pub total: i64,
+    /// (bucket start, clicks), one entry per bucket including empty ones.
+    pub series: Vec<(DateTime<Utc>, i64)>,
+    /// (referrer host, clicks); `None` counts clicks without a referrer.
+    pub top_referrers: Vec<(Option<String>, i64)>,
+    /// (user-agent class, clicks).
+    pub user_agents: Vec<(String, i64)>,
+    /// (A/B variant, clicks); `None` counts clicks without a variant.
+    pub variants: Vec<(Option<String>, i64)>,
+}
+
+impl ClickRepository {
+    /// Aggregate the clicks of `slug` in `[from, to)`. When `tenant_id` is
+    /// `Some`, only clicks recorded for that tenant are counted.
+    pub async fn stats(
+        &self,
+        slug: &str,
+        tenant_id: Option<Uuid>,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        granularity: StatsGranularity,
+        top_referrers: i64,
+    ) -> AppResult<ClickStats> {
+        const FILTER: &str = r#"
+            c.slug = $1
+              AND ($2::uuid IS NULL OR c.tenant_id = $2)
+              AND c.clicked_at >= $3
+              AND c.clicked_at < $4
+        "#;
+
+        let series = sqlx::query(&format!(
+            r#"
+            SELECT b.bucket, COUNT(c.id) AS clicks
+            FROM generate_series(
+                date_trunc($5, $3, 'UTC'),
+                $4 - interval '1 microsecond',
+                ('1 ' || $5)::interval
+            ) AS b(bucket)
+            LEFT JOIN link_clicks c
+                ON {} AND date_trunc($5, c.clicked_at, 'UTC') = b.bucket
+            GROUP BY b.bucket
+            ORDER BY b.bucket
+            "#,
+            FILTER
+        ))
+        .bind(slug)
+        .bind(tenant_id)
+        .bind(from)
+        .bind(to)
+        .bind(granularity.as_str())
+        .fetch_all(&self.pool)
+        .await?;
+
+        let referrers = sqlx::query(&format!(
+            r#"
+            SELECT c.referrer, COUNT(*) AS clicks
+            FROM link_clicks c
+            WHERE {}
+            GROUP BY c.referrer
+            ORDER BY clicks DESC, c.referrer
+            LIMIT $5
+            "#,
+            FILTER
+        ))
+        .bind(slug)
+        .bind(tenant_id)
+        .bind(from)
+        .bind(to)
+        .bind(top_referrers)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let user_agents = sqlx::query(&format!(
+            r#"
+            SELECT c.user_agent_class, COUNT(*) AS clicks
+            FROM link_clicks c
+            WHERE {}
+            GROUP BY c.user_agent_class
+            ORDER BY clicks DESC, c.user_agent_class
+            "#,
+            FILTER
+        ))
+        .bind(slug)
+        .bind(tenant_id)
+        .bind(from)
+        .bind(to)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let variants = sqlx::query(&format!(
+            r#"
+            SELECT c.variant, COUNT(*) AS clicks
+            FROM link_clicks c
+            WHERE {}
+            GROUP BY c.variant
+            ORDER BY clicks DESC, c.variant
+            "#,
+            FILTER
+        ))
+        .bind(slug)
+        .bind(tenant_id)
+        .bind(from)
+        .bind(to)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let series: Vec<(DateTime<Utc>, i64)> = series
+            .into_iter()
+            .map(|r| (r.get("bucket"), r.get("clicks")))
+            .collect();
+
+        Ok(ClickStats {
+            total: series.iter().map(|(_, clicks)| clicks).sum(),
+            series,
+            top_referrers: referrers
+                .into_iter()
+                .map(|r| (r.get("referrer"), r.get("clicks")))
+                .collect(),
+            user_agents: user_agents
+                .into_iter()
+                .map(|r| (r.get("user_agent_class"), r.get("clicks")))
+                .collect(),
+            variants: variants
+                .into_iter()
+                .map(|r| (r.get("variant"), r.get("clicks")))
+                .collect(),
+        })
+    }
+}
+
 /// Abstract factory for repositories.
 pub trait RepositoryFactory: Send + Sync {
     fn tenant_repo(&self) -> TenantRepository;
     fn short_link_repo(&self) -> ShortLinkRepository;
+    fn api_key_repo(&self) -> ApiKeyRepository;
+    fn click_repo(&self) -> ClickRepository;
+    fn link_rule_repo(&self) -> LinkRuleRepository;
+    fn link_variant_repo(&self) -> LinkVariantRepository;
 }
 
 /// Concrete factory for Postgres-backed repositories.
@@ -255,4 +1243,20 @@ impl RepositoryFactory for PgRepositoryFactory {
     fn short_link_repo(&self) -> ShortLinkRepository {
         ShortLinkRepository::new(self.pool.clone())
     }
+
+    fn api_key_repo(&self) -> ApiKeyRepository {
+        ApiKeyRepository::new(self.pool.clone())
+    }
+
+    fn click_repo(&self) -> ClickRepository {
+        ClickRepository::new(self.pool.clone())
+    }
+
+    fn link_rule_repo(&self) -> LinkRuleRepository {
+        LinkRuleRepository::new(self.pool.clone())
+    }
+
+    fn link_variant_repo(&self) -> LinkVariantRepository {
+        LinkVariantRepository::new(self.pool.clone())
+    }
 }
@@ -1,10 +1,17 @@
 use rand::Rng;
+use rand::distr::Alphanumeric;
+use rand::seq::IndexedRandom;
+
+use crate::models::url_shortener::SlugStyle;
+
+/// Shortest slug any generator produces, whatever length is requested.
+pub const MIN_GENERATED_SLUG_LEN: usize = 6;
 
 /// Generate a random, URL-safe slug with at least `min_len` characters.
 ///
 /// The slug is hexadecimal; for example "a3f9b2".
 pub fn generate_slug(min_len: usize) -> String {
-    let min_len = min_len.max(6);
+    let min_len = min_len.max(MIN_GENERATED_SLUG_LEN);
     let bytes_len = min_len.div_ceil(2);
     let mut buf = vec![0u8; bytes_len];
     rand::rng().fill(buf.as_mut_slice());
@@ -15,3 +22,330 @@ pub fn generate_slug(min_len: usize) -> String {
     }
     slug
 }
+
+/// Produces candidate slugs for links created without one. Collisions are
+/// handled by the caller, which retries with a new candidate.
+pub trait SlugGenerator: Send + Sync {
+    /// Whether [`SlugGenerator::generate`] expects a fresh value from the
+    /// `short_link_slug_seq` database sequence.
+    fn needs_sequence(&self) -> bool {
+        false
+    }
+
+    /// Generate a slug of at least `min_len` characters.
+    fn generate(&self, min_len: usize, sequence: Option<u64>) -> String;
+}
+
+/// Random hex slugs (`a3f9b2`), see [`generate_slug`].
+pub struct HexSlugGenerator;
+
+impl SlugGenerator for HexSlugGenerator {
+    fn generate(&self, min_len: usize, _sequence: Option<u64>) -> String {
+        generate_slug(min_len)
+    }
+}
+
+/// Random base62 slugs (`q7XbK2`): about 6 bits per character instead of 4.
+pub struct Base62SlugGenerator;
+
+impl SlugGenerator for Base62SlugGenerator {
+    fn generate(&self, min_len: usize, _sequence: Option<u64>) -> String {
+        rand::rng()
+            .sample_iter(&Alphanumeric)
+            .take(min_len.max(MIN_GENERATED_SLUG_LEN))
+            .map(char::from)
+            .collect()
+    }
+}
+
+const ADJECTIVES: &[&str] = &[
+    "amber", "bold", "brave", "bright", "calm", "clever", "cosmic", "crisp", "curious", "daring",
+    "eager", "early", "fancy", "fierce", "gentle", "glad", "golden", "grand", "happy", "hidden",
+    "humble", "jolly", "keen", "kind", "lively", "lucky", "mellow", "merry", "mighty", "misty",
+    "noble", "proud", "quick", "quiet", "rapid", "rosy", "rustic", "shiny", "silent", "silver",
+    "sleek", "snowy", "solar", "steady", "sunny", "swift", "tidy", "vivid", "warm", "wild", "wise",
+    "witty", "young", "zesty",
+];
+
+const NOUNS: &[&str] = &[
+    "badger", "beacon", "bison", "brook", "cedar", "comet", "coral", "crane", "delta", "dune",
+    "eagle", "ember", "falcon", "fern", "fjord", "forest", "fox", "glacier", "harbor", "hawk",
+    "heron", "island", "lagoon", "lark", "lynx", "maple", "meadow", "mesa", "moose", "nebula",
+    "orca", "otter", "owl", "panda", "pebble", "pine", "prairie", "raven", "reef", "ridge",
+    "river", "robin", "sparrow", "spruce", "summit", "tiger", "tundra", "valley", "walrus",
+    "willow", "wolf", "yak",
+];
+
+/// Human-readable slugs such as `brave-otter-42`. Easy to read out loud, but
+/// with far fewer combinations than random slugs, so not for private links.
+pub struct WordsSlugGenerator;
+
+impl SlugGenerator for WordsSlugGenerator {
+    fn generate(&self, min_len: usize, _sequence: Option<u64>) -> String {
+        let mut rng = rand::rng();
+        let pick = |words: &[&'static str], rng: &mut rand::rngs::ThreadRng| {
+            *words.choose(rng).expect("word lists are not empty")
+        };
+
+        let mut slug = format!(
+            "{}-{}-{}",
+            pick(ADJECTIVES, &mut rng),
+            pick(NOUNS, &mut rng),
+            rng.random_range(10..100)
+        );
+        while slug.len() < min_len {
+            slug.push('-');
+            slug.push_str(pick(NOUNS, &mut rng));
+        }
+        slug
+    }
+}
+
+/// Shortest accepted Hashids salt. With a short or empty salt the alphabet
+/// permutation is guessable and sequence values can be decoded.
+pub const MIN_HASHID_SALT_LEN: usize = 16;
+
+const BASE62_ALPHABET: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+
+/// Hashids-style encoding of a database sequence value: short, unique and
+/// non-sequential looking (`1` and `2` map to unrelated slugs), with the
+/// alphabet shuffled by a secret salt.
+///
+/// A few characters of the shuffled alphabet are reserved as guards and
+/// never appear in the encoded value. Short encodings are padded as
+/// `[filler] guard value [guard] [filler]`, so the value can always be told
+/// apart from its padding and distinct values never share a slug.
+pub struct HashidSlugGenerator {
+    alphabet: Vec<u8>,
+    guards: Vec<u8>,
+    salt: Vec<u8>,
+}
+
+/// Guard characters taken from the alphabet (one in twelve, as Hashids does).
+const HASHID_GUARD_COUNT: usize = BASE62_ALPHABET.len().div_ceil(12);
+
+impl HashidSlugGenerator {
+    pub fn new(salt: &str) -> Self {
+        let mut alphabet = BASE62_ALPHABET.to_vec();
+        consistent_shuffle(&mut alphabet, salt.as_bytes());
+        let guards = alphabet.drain(..HASHID_GUARD_COUNT).collect();
+        Self {
+            alphabet,
+            guards,
+            salt: salt.as_bytes().to_vec(),
+        }
+    }
+
+    /// Encode `value` into at least `min_len` characters.
+    pub fn encode(&self, value: u64, min_len: usize) -> String {
+        let mut alphabet = self.alphabet.clone();
+        let lottery = alphabet[(value % alphabet.len() as u64) as usize];
+
+        let mut key = vec![lottery];
+        key.extend_from_slice(&self.salt);
+        key.extend_from_slice(&alphabet);
+        key.truncate(alphabet.len());
+        consistent_shuffle(&mut alphabet, &key);
+
+        let mut out = vec![lottery];
+        out.extend(to_base(value, &alphabet));
+
+        // Mark where the value starts and ends.
+        let guard = |c: u8| {
+            let n = self.guards.len() as u64;
+            self.guards[((value % n + u64::from(c)) % n) as usize]
+        };
+        if out.len() < min_len {
+            out.insert(0, guard(out[0]));
+        }
+        if out.len() < min_len {
+            out.push(guard(out[2]));
+        }
+
+        // Fill the rest with non-guard characters around the guards.
+        while out.len() < min_len {
+            let key = alphabet.clone();
+            consistent_shuffle(&mut alphabet, &key);
+            let half = alphabet.len() / 2;
+            let missing = min_len - out.len();
+            let left = (missing / 2).min(alphabet.len() - half);
+            let right = (missing - left).min(half);
+            out = [
+                &alphabet[alphabet.len() - left..],
+                out.as_slice(),
+                &alphabet[..right],
+            ]
+            .concat();
+        }
+
+        String::from_utf8(out).expect("alphabet is ASCII")
+    }
+}
+
+impl SlugGenerator for HashidSlugGenerator {
+    fn needs_sequence(&self) -> bool {
+        true
+    }
+
+    fn generate(&self, min_len: usize, sequence: Option<u64>) -> String {
+        let value = sequence.unwrap_or_else(|| rand::rng().random());
+        self.encode(value, min_len.max(MIN_GENERATED_SLUG_LEN))
+    }
+}
+
+/// Deterministic, salt-driven permutation (the Hashids shuffle).
+fn consistent_shuffle(alphabet: &mut [u8], salt: &[u8]) {
+    if salt.is_empty() {
+        return;
+    }
+    let (mut v, mut p) = (0usize, 0usize);
+    for i in (1..alphabet.len()).rev() {
+        v %= salt.len();
+        let n = salt[v] as usize;
+        p += n;
+        let j = (n + v + p) % i;
+        alphabet.swap(i, j);
+        v += 1;
+    }
+}
+
+fn to_base(mut value: u64, alphabet: &[u8]) -> Vec<u8> {
+    let base = alphabet.len() as u64;
+    let mut digits = Vec::new();
+    loop {
+        digits.push(alphabet[(value % base) as usize]);
+        value /= base;
+        if value == 0 {
+            break;
+        }
+    }
+    digits.reverse();
+    digits
+}
+
+/// One generator per [`SlugStyle`], built once at startup.
+pub struct SlugGenerators {
+    hex: HexSlugGenerator,
+    base62: Base62SlugGenerator,
+    words: WordsSlugGenerator,
+    hashid: Option<HashidSlugGenerator>,
+}
+
+impl SlugGenerators {
+    /// `hashid_salt` keys the Hashids alphabet; keep it stable, or newly
+    /// generated slugs may collide with existing ones (which costs a retry).
+    /// Without a salt the `hashid` style is unavailable.
+    pub fn new(hashid_salt: Option<&str>) -> Self {
+        Self {
+            hex: HexSlugGenerator,
+            base62: Base62SlugGenerator,
+            words: WordsSlugGenerator,
+            hashid: hashid_salt.map(HashidSlugGenerator::new),
+        }
+    }
+
+    /// Generator for `style`, or `None` if the style is not configured.
+    pub fn get(&self, style: SlugStyle) -> Option<&dyn SlugGenerator> {
+        match style {
+            SlugStyle::Hex => Some(&self.hex),
+            SlugStyle::Base62 => Some(&self.base62),
+            SlugStyle::Words => Some(&self.words),
+            SlugStyle::Hashid => self
+                .hashid
+                .as_ref()
+                .map(|hashid| hashid as &dyn SlugGenerator),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn hex_and_base62_respect_length_and_charset() {
+        let hex = HexSlugGenerator.generate(8, None);
+        assert_eq!(hex.len(), 8);
+        assert!(hex.chars().all(|c| c.is_ascii_hexdigit()));
+
+        let base62 = Base62SlugGenerator.generate(3, None);
+        assert_eq!(base62.len(), MIN_GENERATED_SLUG_LEN);
+        assert!(base62.chars().all(|c| c.is_ascii_alphanumeric()));
+    }
+
+    #[test]
+    fn words_are_readable_and_padded_to_length() {
+        let slug = WordsSlugGenerator.generate(6, None);
+        let parts: Vec<&str> = slug.split('-').collect();
+        assert_eq!(parts.len(), 3);
+        assert!(ADJECTIVES.contains(&parts[0]));
+        assert!(NOUNS.contains(&parts[1]));
+        assert!(
+            parts[2]
+                .parse::<u32>()
+                .is_ok_and(|n| (10..100).contains(&n))
+        );
+
+        assert!(WordsSlugGenerator.generate(40, None).len() >= 40);
+    }
+
+    #[test]
+    fn hashids_are_deterministic_unique_and_salted() {
+        let generator = HashidSlugGenerator::new("pepper");
+        assert!(generator.needs_sequence());
+        assert_eq!(generator.encode(42, 6), generator.encode(42, 6));
+        assert_ne!(
+            generator.encode(42, 6),
+            HashidSlugGenerator::new("salt").encode(42, 6)
+        );
+
+        let slugs: HashSet<String> = (1..2_000).map(|n| generator.encode(n, 6)).collect();
+        assert_eq!(slugs.len(), 1_999);
+        assert!(slugs.iter().all(|s| s.len() == 6));
+        assert!(generator.encode(u64::MAX, 6).len() > 6);
+    }
+
+    #[test]
+    fn padded_hashids_never_collide_with_longer_values() {
+        let generator = HashidSlugGenerator::new("pepper");
+        // Values around 56^2 and 56^3 cross from padded to unpadded
+        // encodings at these lengths.
+        let values: Vec<u64> = (0..20_000).chain(170_000..180_000).collect();
+        for min_len in [1, 3, 4, 5, 6, 8] {
+            let slugs: HashSet<String> = values
+                .iter()
+                .map(|&n| {
+                    let slug = generator.encode(n, min_len);
+                    assert!(slug.len() >= min_len, "{} at {}: {}", n, min_len, slug);
+                    slug
+                })
+                .collect();
+            assert_eq!(
+                slugs.len(),
+                values.len(),
+                "collision at min_len {}",
+                min_len
+            );
+        }
+    }
+
+    #[test]
+    fn generators_cover_every_style() {
+        let generators = SlugGenerators::new(Some("pepper-pepper-pepper"));
+        for style in [
+            SlugStyle::Hex,
+            SlugStyle::Base62,
+            SlugStyle::Words,
+            SlugStyle::Hashid,
+        ] {
+            let slug = generators.get(style).unwrap().generate(6, Some(7));
+            assert!(slug.len() >= 6, "{:?} produced {}", style, slug);
+        }
+
+        // Unsalted hashids would be decodable, so the style is left out.
+        let unsalted = SlugGenerators::new(None);
+        assert!(unsalted.get(SlugStyle::Hashid).is_none());
+        assert!(unsalted.get(SlugStyle::Hex).is_some());
+    }
+}
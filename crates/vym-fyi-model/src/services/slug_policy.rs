@@ -0,0 +1,240 @@
+//! Rules for caller-chosen slugs.
+//!
+//! Every provided slug must be 3–64 ASCII letters, digits, `-` or `_`,
+//! start with a letter or digit, and not be a reserved name. Tenants can add
+//! a required prefix and/or a pattern on top (see [`VanitySlugPolicy`]).
+
+use regex::Regex;
+use thiserror::Error;
+
+use crate::models::errors::{AppError, AppResult};
+use crate::models::url_shortener::VanitySlugPolicy;
+
+pub const MIN_SLUG_LEN: usize = 3;
+pub const MAX_SLUG_LEN: usize = 64;
+
+/// Names served by the redirect server itself, or kept for future routes.
+/// Compared case-insensitively.
+pub const RESERVED_SLUGS: &[&str] = &[
+    ".well-known",
+    "admin",
+    "api",
+    "favicon.ico",
+    "health",
+    "index.html",
+    "metrics",
+    "robots.txt",
+    "sitemap.xml",
+    "static",
+];
+
+/// Why a slug was rejected.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum SlugError {
+    #[error("slug must be at least {min} characters")]
+    TooShort { min: usize },
+    #[error("slug must be at most {max} characters")]
+    TooLong { max: usize },
+    #[error("slug may only contain ASCII letters, digits, '-' and '_' (found {0:?})")]
+    InvalidCharacter(char),
+    #[error("slug must start with a letter or digit")]
+    InvalidStart,
+    #[error("slug '{0}' is reserved")]
+    Reserved(String),
+    #[error("slug must start with '{0}'")]
+    MissingPrefix(String),
+    #[error("slug must match the pattern {0}")]
+    PatternMismatch(String),
+}
+
+impl SlugError {
+    /// Stable machine-readable code for API responses.
+    pub fn code(&self) -> &'static str {
+        match self {
+            SlugError::TooShort { .. } => "too_short",
+            SlugError::TooLong { .. } => "too_long",
+            SlugError::InvalidCharacter(_) => "invalid_character",
+            SlugError::InvalidStart => "invalid_start",
+            SlugError::Reserved(_) => "reserved",
+            SlugError::MissingPrefix(_) => "missing_prefix",
+            SlugError::PatternMismatch(_) => "pattern_mismatch",
+        }
+    }
+}
+
+/// Whether `slug` is a reserved name.
+pub fn is_reserved_slug(slug: &str) -> bool {
+    RESERVED_SLUGS
+        .iter()
+        .any(|reserved| reserved.eq_ignore_ascii_case(slug))
+}
+
+/// Check `slug` against the global rules.
+pub fn validate_slug(slug: &str) -> Result<(), SlugError> {
+    if is_reserved_slug(slug) {
+        return Err(SlugError::Reserved(slug.to_string()));
+    }
+    if let Some(c) = slug
+        .chars()
+        .find(|c| !(c.is_ascii_alphanumeric() || *c == '-' || *c == '_'))
+    {
+        return Err(SlugError::InvalidCharacter(c));
+    }
+    if slug.len() < MIN_SLUG_LEN {
+        return Err(SlugError::TooShort { min: MIN_SLUG_LEN });
+    }
+    if slug.len() > MAX_SLUG_LEN {
+        return Err(SlugError::TooLong { max: MAX_SLUG_LEN });
+    }
+    if !slug.starts_with(|c: char| c.is_ascii_alphanumeric()) {
+        return Err(SlugError::InvalidStart);
+    }
+    Ok(())
+}
+
+/// Compiled slug rules of one tenant.
+#[derive(Debug, Clone, Default)]
+pub struct SlugPolicy {
+    prefix: Option<String>,
+    pattern: Option<Regex>,
+}
+
+impl PartialEq for SlugPolicy {
+    fn eq(&self, other: &Self) -> bool {
+        self.prefix == other.prefix
+            && self.pattern.as_ref().map(Regex::as_str) == other.pattern.as_ref().map(Regex::as_str)
+    }
+}
+
+impl SlugPolicy {
+    /// Compile a tenant's vanity policy. The pattern is anchored, so it has
+    /// to match the whole slug.
+    pub fn new(vanity: &VanitySlugPolicy) -> AppResult<Self> {
+        if let Some(prefix) = &vanity.prefix {
+            validate_prefix(prefix)?;
+        }
+        let pattern = vanity
+            .pattern
+            .as_deref()
+            .map(|pattern| {
+                Regex::new(&format!("^(?:{})$", pattern)).map_err(|e| {
+                    AppError::Config(format!("invalid slug pattern {}: {}", pattern, e))
+                })
+            })
+            .transpose()?;
+
+        Ok(Self {
+            prefix: vanity.prefix.clone(),
+            pattern,
+        })
+    }
+
+    /// Check `slug` against the global rules and this tenant's rules.
+    pub fn validate(&self, slug: &str) -> Result<(), SlugError> {
+        validate_slug(slug)?;
+        if let Some(prefix) = &self.prefix
+            && !slug.starts_with(prefix.as_str())
+        {
+            return Err(SlugError::MissingPrefix(prefix.clone()));
+        }
+        if let Some(pattern) = &self.pattern
+            && !pattern.is_match(slug)
+        {
+            return Err(SlugError::PatternMismatch(pattern.as_str().to_string()));
+        }
+        Ok(())
+    }
+}
+
+fn validate_prefix(prefix: &str) -> AppResult<()> {
+    match prefix
+        .chars()
+        .find(|c| !(c.is_ascii_alphanumeric() || *c == '-' || *c == '_'))
+    {
+        Some(c) => Err(AppError::Config(format!(
+            "invalid character {:?} in slug prefix {}",
+            c, prefix
+        ))),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_plain_slugs() {
+        assert_eq!(validate_slug("promo-2025"), Ok(()));
+        assert_eq!(validate_slug("a_b"), Ok(()));
+    }
+
+    #[test]
+    fn rejects_unsafe_or_reserved_slugs() {
+        assert_eq!(validate_slug("a/b"), Err(SlugError::InvalidCharacter('/')));
+        assert_eq!(
+            validate_slug("promo 1"),
+            Err(SlugError::InvalidCharacter(' '))
+        );
+        // Cyrillic "а" looks like a Latin "a".
+        assert_eq!(
+            validate_slug("pаypal"),
+            Err(SlugError::InvalidCharacter('а'))
+        );
+        assert_eq!(validate_slug("ab"), Err(SlugError::TooShort { min: 3 }));
+        assert_eq!(
+            validate_slug(&"a".repeat(65)),
+            Err(SlugError::TooLong { max: 64 })
+        );
+        assert_eq!(validate_slug("-promo"), Err(SlugError::InvalidStart));
+        assert_eq!(
+            validate_slug("Health"),
+            Err(SlugError::Reserved("Health".into()))
+        );
+        assert!(matches!(
+            validate_slug("favicon.ico"),
+            Err(SlugError::Reserved(_))
+        ));
+    }
+
+    #[test]
+    fn tenant_policy_adds_prefix_and_pattern() {
+        let policy = SlugPolicy::new(&VanitySlugPolicy {
+            prefix: Some("acme-".into()),
+            pattern: Some("[a-z0-9-]+".into()),
+        })
+        .unwrap();
+
+        assert_eq!(policy.validate("acme-sale"), Ok(()));
+        assert_eq!(
+            policy.validate("sale"),
+            Err(SlugError::MissingPrefix("acme-".into()))
+        );
+        assert_eq!(
+            policy.validate("acme-Sale").unwrap_err().code(),
+            "pattern_mismatch"
+        );
+        assert_eq!(
+            policy.validate("acme/x").unwrap_err().code(),
+            "invalid_character"
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_tenant_policies() {
+        let bad_pattern = VanitySlugPolicy {
+            prefix: None,
+            pattern: Some("(".into()),
+        };
+        assert!(matches!(
+            SlugPolicy::new(&bad_pattern),
+            Err(AppError::Config(_))
+        ));
+
+        let bad_prefix = VanitySlugPolicy {
+            prefix: Some("a/".into()),
+            pattern: None,
+        };
+        assert!(SlugPolicy::new(&bad_prefix).is_err());
+    }
+}
@@ -29,6 +29,11 @@ pub async fn not_found() -> Response {
     error_page("404.html", StatusCode::NOT_FOUND, "404 – Not Found").await
 }
 
+/// Render the page shown for links of a suspended tenant.
+pub async fn suspended() -> Response {
+    error_page("suspended.html", StatusCode::FORBIDDEN, "Link unavailable").await
+}
+
 /// Render the shared 500 page as an Axum response.
 pub async fn internal_error() -> Response {
     error_page(
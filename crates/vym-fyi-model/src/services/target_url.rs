@@ -0,0 +1,292 @@
+//! Parsing, normalization and blocklisting of link target URLs.
+//!
+//! Targets are parsed as absolute URLs, internationalized host names are
+//! converted to punycode, and the normalized form is what gets stored. Hosts
+//! can be blocked by domain (including subdomains) or by address range;
+//! private, loopback and link-local ranges are blocked by default.
+
+use std::net::IpAddr;
+use std::str::FromStr;
+
+use thiserror::Error;
+use url::{Host, Url};
+
+use crate::models::errors::{AppError, AppResult};
+use crate::services::client_ip::IpCidr;
+
+pub const DEFAULT_MAX_TARGET_URL_LEN: usize = 2048;
+
+/// Ranges no public link should point to: loopback, private, CGNAT,
+/// link-local (cloud metadata endpoints) and unique-local addresses.
+const PRIVATE_RANGES: &[&str] = &[
+    "0.0.0.0/8",
+    "10.0.0.0/8",
+    "100.64.0.0/10",
+    "127.0.0.0/8",
+    "169.254.0.0/16",
+    "172.16.0.0/12",
+    "192.168.0.0/16",
+    "::/128",
+    "::1/128",
+    "fc00::/7",
+    "fe80::/10",
+];
+
+/// Why a target URL was rejected.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum TargetUrlError {
+    #[error("target_url is not a valid absolute URL: {0}")]
+    Invalid(String),
+    #[error("target_url must be at most {max} characters")]
+    TooLong { max: usize },
+    #[error("scheme '{0}' is not allowed")]
+    SchemeNotAllowed(String),
+    #[error("target_url has no host")]
+    MissingHost,
+    #[error("target_url must not contain credentials")]
+    Credentials,
+    #[error("host '{0}' is blocked")]
+    BlockedHost(String),
+}
+
+impl TargetUrlError {
+    /// Stable machine-readable code for API responses.
+    pub fn code(&self) -> &'static str {
+        match self {
+            TargetUrlError::Invalid(_) => "invalid_url",
+            TargetUrlError::TooLong { .. } => "too_long",
+            TargetUrlError::SchemeNotAllowed(_) => "scheme_not_allowed",
+            TargetUrlError::MissingHost => "missing_host",
+            TargetUrlError::Credentials => "credentials_not_allowed",
+            TargetUrlError::BlockedHost(_) => "blocked_host",
+        }
+    }
+}
+
+/// Blocked domains (each also blocks its subdomains) and address ranges.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HostBlocklist {
+    domains: Vec<String>,
+    networks: Vec<IpCidr>,
+}
+
+impl HostBlocklist {
+    /// Parse entries that are either CIDRs/addresses or domain names.
+    pub fn parse<S: AsRef<str>>(entries: &[S]) -> AppResult<Self> {
+        let mut blocklist = Self::default();
+        for entry in entries.iter().map(|e| e.as_ref().trim()) {
+            if entry.is_empty() {
+                continue;
+            }
+            if let Ok(network) = entry.parse::<IpCidr>() {
+                blocklist.networks.push(network);
+                continue;
+            }
+            match Host::parse(entry.trim_start_matches("*.")) {
+                Ok(Host::Domain(domain)) => blocklist.domains.push(domain),
+                _ => {
+                    return Err(AppError::Config(format!(
+                        "invalid blocklist entry: {}",
+                        entry
+                    )));
+                }
+            }
+        }
+        Ok(blocklist)
+    }
+
+    /// Private, loopback and link-local ranges, plus `localhost`.
+    pub fn private_ranges() -> Self {
+        let mut blocklist = Self::parse(PRIVATE_RANGES).expect("built-in ranges are valid");
+        blocklist.domains.push("localhost".into());
+        blocklist
+    }
+
+    pub fn extend(&mut self, other: HostBlocklist) {
+        self.domains.extend(other.domains);
+        self.networks.extend(other.networks);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.domains.is_empty() && self.networks.is_empty()
+    }
+
+    /// Whether `host` (as parsed by [`Url`]) is blocked.
+    pub fn blocks(&self, host: &Host<&str>) -> bool {
+        match host {
+            Host::Domain(domain) => {
+                let domain = domain.trim_end_matches('.');
+                self.domains.iter().any(|blocked| {
+                    domain == blocked
+                        || domain
+                            .strip_suffix(blocked.as_str())
+                            .is_some_and(|rest| rest.ends_with('.'))
+                })
+            }
+            Host::Ipv4(ip) => self.blocks_ip(IpAddr::V4(*ip)),
+            Host::Ipv6(ip) => self.blocks_ip(IpAddr::V6(*ip)),
+        }
+    }
+
+    fn blocks_ip(&self, ip: IpAddr) -> bool {
+        self.networks.iter().any(|network| network.contains(&ip))
+    }
+}
+
+/// Deployment-wide rules for link targets.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TargetUrlPolicy {
+    pub allowed_schemes: Vec<String>,
+    pub max_len: usize,
+    pub blocklist: HostBlocklist,
+}
+
+impl Default for TargetUrlPolicy {
+    fn default() -> Self {
+        Self {
+            allowed_schemes: vec!["http".into(), "https".into()],
+            max_len: DEFAULT_MAX_TARGET_URL_LEN,
+            blocklist: HostBlocklist::private_ranges(),
+        }
+    }
+}
+
+impl TargetUrlPolicy {
+    /// Read `TARGET_URL_SCHEMES`, `TARGET_URL_MAX_LEN`,
+    /// `TARGET_URL_BLOCKLIST` (comma-separated domains and CIDRs, added to
+    /// the built-in private ranges) and `TARGET_URL_ALLOW_PRIVATE` (drops the
+    /// built-in ranges).
+    pub fn from_env() -> AppResult<Self> {
+        let list = |name: &str| -> Option<Vec<String>> {
+            std::env::var(name).ok().map(|raw| {
+                raw.split(',')
+                    .map(|s| s.trim().to_ascii_lowercase())
+                    .filter(|s| !s.is_empty())
+                    .collect()
+            })
+        };
+
+        let mut policy = Self::default();
+        if let Some(schemes) = list("TARGET_URL_SCHEMES") {
+            policy.allowed_schemes = schemes;
+        }
+        if let Ok(raw) = std::env::var("TARGET_URL_MAX_LEN") {
+            policy.max_len = raw
+                .trim()
+                .parse()
+                .map_err(|_| AppError::Config(format!("invalid TARGET_URL_MAX_LEN: {}", raw)))?;
+        }
+        if let Ok(raw) = std::env::var("TARGET_URL_ALLOW_PRIVATE") {
+            let allow_private = bool::from_str(raw.trim()).map_err(|_| {
+                AppError::Config(format!("invalid TARGET_URL_ALLOW_PRIVATE: {}", raw))
+            })?;
+            if allow_private {
+                policy.blocklist = HostBlocklist::default();
+            }
+        }
+        if let Some(entries) = list("TARGET_URL_BLOCKLIST") {
+            policy.blocklist.extend(HostBlocklist::parse(&entries)?);
+        }
+        Ok(policy)
+    }
+
+    /// Parse and check `raw`, returning the normalized URL to store.
+    /// `extra` holds additional blocked hosts, e.g. the tenant's own list.
+    pub fn normalize(&self, raw: &str, extra: &HostBlocklist) -> Result<String, TargetUrlError> {
+        let raw = raw.trim();
+        if raw.len() > self.max_len {
+            return Err(TargetUrlError::TooLong { max: self.max_len });
+        }
+
+        let url = Url::parse(raw).map_err(|e| TargetUrlError::Invalid(e.to_string()))?;
+        if !self.allowed_schemes.iter().any(|s| s == url.scheme()) {
+            return Err(TargetUrlError::SchemeNotAllowed(url.scheme().to_string()));
+        }
+        if !url.username().is_empty() || url.password().is_some() {
+            return Err(TargetUrlError::Credentials);
+        }
+        let host = url.host().ok_or(TargetUrlError::MissingHost)?;
+        if self.blocklist.blocks(&host) || extra.blocks(&host) {
+            return Err(TargetUrlError::BlockedHost(host.to_string()));
+        }
+
+        // Punycode and percent-encoding can make the normalized form longer.
+        let normalized = String::from(url);
+        if normalized.len() > self.max_len {
+            return Err(TargetUrlError::TooLong { max: self.max_len });
+        }
+        Ok(normalized)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normalize(raw: &str) -> Result<String, TargetUrlError> {
+        TargetUrlPolicy::default().normalize(raw, &HostBlocklist::default())
+    }
+
+    #[test]
+    fn normalizes_valid_urls() {
+        assert_eq!(
+            normalize(" HTTPS://Example.COM:443/a b?q=1 ").as_deref(),
+            Ok("https://example.com/a%20b?q=1")
+        );
+        assert_eq!(
+            normalize("https://bücher.example/").as_deref(),
+            Ok("https://xn--bcher-kva.example/")
+        );
+    }
+
+    #[test]
+    fn rejects_unsafe_schemes_and_relative_urls() {
+        assert_eq!(
+            normalize("javascript:alert(1)"),
+            Err(TargetUrlError::SchemeNotAllowed("javascript".into()))
+        );
+        assert_eq!(normalize("/relative").unwrap_err().code(), "invalid_url");
+        assert_eq!(
+            normalize("https://user:pw@example.com/"),
+            Err(TargetUrlError::Credentials)
+        );
+        assert_eq!(
+            normalize(&format!("https://example.com/{}", "a".repeat(2048))),
+            Err(TargetUrlError::TooLong { max: 2048 })
+        );
+    }
+
+    #[test]
+    fn blocks_internal_addresses_in_any_notation() {
+        for raw in [
+            "http://169.254.169.254/latest/meta-data",
+            "http://2852039166/",
+            "http://0xa9fea9fe/",
+            "http://127.1/",
+            "http://[::1]/",
+            "http://[::ffff:169.254.169.254]/",
+            "http://localhost:8080/",
+            "http://api.localhost/",
+        ] {
+            assert_eq!(
+                normalize(raw).unwrap_err().code(),
+                "blocked_host",
+                "{}",
+                raw
+            );
+        }
+        assert!(normalize("http://8.8.8.8/").is_ok());
+    }
+
+    #[test]
+    fn extra_blocklist_covers_subdomains_only() {
+        let extra = HostBlocklist::parse(&["evil.example", "203.0.113.0/24"]).unwrap();
+        let policy = TargetUrlPolicy::default();
+
+        assert!(policy.normalize("https://evil.example/", &extra).is_err());
+        assert!(policy.normalize("https://a.evil.example/", &extra).is_err());
+        assert!(policy.normalize("https://notevil.example/", &extra).is_ok());
+        assert!(policy.normalize("http://203.0.113.9/", &extra).is_err());
+        assert!(HostBlocklist::parse(&["not a host"]).is_err());
+    }
+}
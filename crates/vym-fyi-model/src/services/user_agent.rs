@@ -0,0 +1,102 @@
+//! Coarse `User-Agent` classification shared by metrics, click analytics and
+//! device-based redirect rules, so they all agree on what a bot or a phone is.
+
+/// Kind of client behind a `User-Agent` header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UserAgentClass {
+    /// No (or an empty) header.
+    Unknown,
+    /// Crawlers and link previewers.
+    Bot,
+    /// Command-line tools and HTTP libraries.
+    Cli,
+    /// Browser on an iPhone, iPad or iPod. iPads that identify as desktop
+    /// Safari count as [`UserAgentClass::Desktop`].
+    Ios,
+    /// Browser on Android.
+    Android,
+    /// Browser on another mobile platform.
+    Mobile,
+    /// Desktop browser.
+    Desktop,
+    /// Anything else, e.g. native apps.
+    Other,
+}
+
+const BOT_MARKERS: &[&str] = &["bot", "crawl", "spider", "slurp", "preview"];
+const CLI_MARKERS: &[&str] = &[
+    "curl",
+    "wget",
+    "httpie",
+    "python",
+    "go-http-client",
+    "okhttp",
+    "java/",
+    "node",
+    "reqwest",
+];
+
+impl UserAgentClass {
+    pub fn classify(user_agent: Option<&str>) -> Self {
+        let Some(ua) = user_agent
+            .map(str::trim)
+            .filter(|ua| !ua.is_empty())
+            .map(str::to_ascii_lowercase)
+        else {
+            return UserAgentClass::Unknown;
+        };
+
+        if BOT_MARKERS.iter().any(|m| ua.contains(m)) {
+            UserAgentClass::Bot
+        } else if CLI_MARKERS.iter().any(|m| ua.contains(m)) {
+            UserAgentClass::Cli
+        } else if !(ua.starts_with("mozilla/") || ua.starts_with("opera/")) {
+            UserAgentClass::Other
+        } else if ["iphone", "ipad", "ipod"].iter().any(|m| ua.contains(m)) {
+            UserAgentClass::Ios
+        } else if ua.contains("android") {
+            UserAgentClass::Android
+        } else if ua.contains("mobile") {
+            UserAgentClass::Mobile
+        } else {
+            UserAgentClass::Desktop
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_user_agents() {
+        let class = |ua| UserAgentClass::classify(Some(ua));
+        assert_eq!(UserAgentClass::classify(None), UserAgentClass::Unknown);
+        assert_eq!(class("  "), UserAgentClass::Unknown);
+        assert_eq!(
+            class("Mozilla/5.0 (compatible; Googlebot/2.1)"),
+            UserAgentClass::Bot
+        );
+        assert_eq!(class("curl/8.5.0"), UserAgentClass::Cli);
+        assert_eq!(
+            class("Mozilla/5.0 (iPhone; CPU iPhone OS 17_0 like Mac OS X) Mobile/15E148"),
+            UserAgentClass::Ios
+        );
+        assert_eq!(
+            class("Mozilla/5.0 (Linux; Android 14; Pixel 8) Mobile Safari/537.36"),
+            UserAgentClass::Android
+        );
+        assert_eq!(
+            class("Mozilla/5.0 (Mobile; rv:48.0) Gecko/48.0 Firefox/48.0 KAIOS/2.5"),
+            UserAgentClass::Mobile
+        );
+        assert_eq!(
+            class("Mozilla/5.0 (X11; Linux x86_64) Firefox/128.0"),
+            UserAgentClass::Desktop
+        );
+        assert_eq!(
+            class("Dalvik/2.1.0 (Linux; Android 14)"),
+            UserAgentClass::Other
+        );
+    }
+}
@@ -27,6 +27,27 @@ impl CrudOptions {
 pub struct CreateLinkInput {
     pub slug: Option<String>,
     pub target_url: String,
+    /// Absolute expiry (RFC3339).
+    pub expires_at: Option<String>,
+    /// Relative expiry such as "12h" or "7d"; mutually exclusive with `expires_at`.
+    pub ttl: Option<String>,
+    /// Style of the generated slug: "hex", "base62", "words" or "hashid".
+    pub slug_style: Option<String>,
+    /// Minimum length of the generated slug.
+    pub slug_length: Option<u32>,
+    /// Redirect status: 301, 302, 307 (default) or 308.
+    pub redirect_type: Option<u32>,
+    /// Merge the visitor's query string into the target: "off" (default),
+    /// "target_wins", "request_wins" or "append".
+    pub query_passthrough: Option<String>,
+    /// Redirect `/{slug}/rest` to the target with `rest` appended.
+    pub path_passthrough: Option<bool>,
+    /// Query parameters added to the target on redirect, e.g.
+    /// "utm_campaign={{slug}}".
+    pub param_template: Option<String>,
+    /// Replace the link if `slug` already exists instead of failing with 409.
+    /// Requires `slug`.
+    pub overwrite: Option<bool>,
     pub use_master: Option<bool>,
 }
 
@@ -67,6 +88,59 @@ pub struct LinkResponse {
     pub slug: String,
     pub target_url: String,
     pub active: bool,
+    pub expires_at: Option<String>,
+    pub redirect_type: Option<u32>,
+    pub query_passthrough: Option<String>,
+    pub path_passthrough: Option<bool>,
+    pub param_template: Option<String>,
+    /// Target with the tenant's and the link's parameter templates applied.
+    pub resolved_url: Option<String>,
+}
+
+#[napi(object)]
+pub struct LinkStatsInput {
+    pub slug: String,
+    /// Start of the window (RFC3339, inclusive).
+    pub from: Option<String>,
+    /// End of the window (RFC3339, exclusive).
+    pub to: Option<String>,
+    /// "hour" or "day".
+    pub granularity: Option<String>,
+    pub use_master: Option<bool>,
+}
+
+#[napi(object)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatsBucket {
+    pub start: String,
+    pub clicks: i64,
+}
+
+#[napi(object)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReferrerCount {
+    pub referrer: Option<String>,
+    pub clicks: i64,
+}
+
+#[napi(object)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserAgentCount {
+    pub class: String,
+    pub clicks: i64,
+}
+
+#[napi(object)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinkStatsResponse {
+    pub slug: String,
+    pub from: String,
+    pub to: String,
+    pub granularity: String,
+    pub total: i64,
+    pub series: Vec<StatsBucket>,
+    pub top_referrers: Vec<ReferrerCount>,
+    pub user_agents: Vec<UserAgentCount>,
 }
 
 #[napi]
@@ -79,6 +153,9 @@ pub async fn ping(options: CrudOptions, use_master: Option<bool>) -> Result<()>
 #[napi]
 pub async fn create_link(options: CrudOptions, input: CreateLinkInput) -> Result<LinkResponse> {
     let use_master = input.use_master.unwrap_or(false);
+    if input.overwrite.unwrap_or(false) && input.slug.is_none() {
+        return Err(Error::from_reason("overwrite requires a slug"));
+    }
     perform_create_link(&options, &input, use_master)
         .await
         .map_err(to_napi_err)
@@ -92,6 +169,14 @@ pub async fn list_links(options: CrudOptions, input: ListLinksInput) -> Result<V
         .map_err(to_napi_err)
 }
 
+#[napi]
+pub async fn link_stats(options: CrudOptions, input: LinkStatsInput) -> Result<LinkStatsResponse> {
+    let use_master = input.use_master.unwrap_or(false);
+    perform_link_stats(&options, &input, use_master)
+        .await
+        .map_err(to_napi_err)
+}
+
 async fn perform_ping(opts: &CrudOptions, use_master: bool) -> AppResult<()> {
     let client = HttpClient::global()?;
     let url = format!("{}/health", opts.base_url.trim_end_matches('/'));
@@ -114,19 +199,59 @@ async fn perform_create_link(
     use_master: bool,
 ) -> AppResult<LinkResponse> {
     let client = HttpClient::global()?;
-    let url = format!("{}/api/links", opts.base_url.trim_end_matches('/'));
+    let base = opts.base_url.trim_end_matches('/');
+
+    // Create is strict (409 on an existing slug); replacing goes through PUT.
+    let request = match (&input.slug, input.overwrite.unwrap_or(false)) {
+        (Some(slug), true) => client.client().put(format!("{}/api/links/{}", base, slug)),
+        _ => client.client().post(format!("{}/api/links", base)),
+    };
 
     let mut body = serde_json::json!({
         "target_url": input.target_url,
     });
 
-    if let Some(slug) = &input.slug {
+    if let Some(slug) = input
+        .slug
+        .as_ref()
+        .filter(|_| !input.overwrite.unwrap_or(false))
+    {
         body["slug"] = serde_json::Value::String(slug.clone());
     }
 
-    let response = client
-        .client()
-        .post(&url)
+    if let Some(expires_at) = &input.expires_at {
+        body["expires_at"] = serde_json::Value::String(expires_at.clone());
+    }
+
+    if let Some(ttl) = &input.ttl {
+        body["ttl"] = serde_json::Value::String(ttl.clone());
+    }
+
+    if let Some(slug_style) = &input.slug_style {
+        body["slug_style"] = serde_json::Value::String(slug_style.clone());
+    }
+
+    if let Some(slug_length) = input.slug_length {
+        body["slug_length"] = serde_json::Value::from(slug_length);
+    }
+
+    if let Some(redirect_type) = input.redirect_type {
+        body["redirect_type"] = serde_json::Value::from(redirect_type);
+    }
+
+    if let Some(query_passthrough) = &input.query_passthrough {
+        body["query_passthrough"] = serde_json::Value::String(query_passthrough.clone());
+    }
+
+    if let Some(path_passthrough) = input.path_passthrough {
+        body["path_passthrough"] = serde_json::Value::Bool(path_passthrough);
+    }
+
+    if let Some(param_template) = &input.param_template {
+        body["param_template"] = serde_json::Value::String(param_template.clone());
+    }
+
+    let response = request
         .header("X-API-Key", opts.api_key(use_master))
         .header("X-Client-Id", &opts.client_id)
         .json(&body)
@@ -159,6 +284,37 @@ async fn perform_list_links(
     Ok(response.json::<Vec<LinkResponse>>().await?)
 }
 
+async fn perform_link_stats(
+    opts: &CrudOptions,
+    input: &LinkStatsInput,
+    use_master: bool,
+) -> AppResult<LinkStatsResponse> {
+    let client = HttpClient::global()?;
+    let url = format!(
+        "{}/api/links/{}/stats",
+        opts.base_url.trim_end_matches('/'),
+        input.slug
+    );
+
+    let mut params = QueryParamsBuilder::new();
+    params
+        .push_trimmed("from", &input.from)
+        .push_trimmed("to", &input.to)
+        .push_trimmed("granularity", &input.granularity);
+
+    let response = client
+        .client()
+        .get(&url)
+        .header("X-API-Key", opts.api_key(use_master))
+        .header("X-Client-Id", &opts.client_id)
+        .query(&params.into_vec())
+        .send()
+        .await?
+        .error_for_status()?;
+
+    Ok(response.json::<LinkStatsResponse>().await?)
+}
+
 fn to_napi_err<E: std::fmt::Display>(err: E) -> Error {
     Error::from_reason(err.to_string())
 }
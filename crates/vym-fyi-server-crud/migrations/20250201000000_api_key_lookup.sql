@@ -0,0 +1,9 @@
+-- Lookup digest for hashed API keys: a truncated HMAC of the whole key,
+-- keyed with a server-side secret, narrows candidates before verifying the
+-- Argon2 hash. No part of the key is stored in clear.
+
+ALTER TABLE api_keys ADD COLUMN IF NOT EXISTS key_lookup text NOT NULL DEFAULT '';
+
+CREATE INDEX IF NOT EXISTS api_keys_active_lookup_idx
+    ON api_keys (key_lookup)
+    WHERE revoked_at IS NULL;
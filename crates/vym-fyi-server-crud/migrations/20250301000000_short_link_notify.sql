@@ -0,0 +1,32 @@
+-- Notify listeners (the redirect server's slug cache) whenever a link changes.
+-- The payload is the affected slug; an empty payload means "anything may have
+-- changed", sent when a tenant's status changes or a tenant is deleted.
+
+CREATE OR REPLACE FUNCTION notify_short_link_change() RETURNS trigger AS $$
+BEGIN
+    IF TG_OP IN ('UPDATE', 'DELETE') THEN
+        PERFORM pg_notify('short_link_changes', OLD.slug);
+    END IF;
+    IF TG_OP IN ('INSERT', 'UPDATE') AND (TG_OP = 'INSERT' OR NEW.slug <> OLD.slug) THEN
+        PERFORM pg_notify('short_link_changes', NEW.slug);
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS short_links_notify ON short_links;
+CREATE TRIGGER short_links_notify
+    AFTER INSERT OR UPDATE OR DELETE ON short_links
+    FOR EACH ROW EXECUTE FUNCTION notify_short_link_change();
+
+CREATE OR REPLACE FUNCTION notify_tenant_change() RETURNS trigger AS $$
+BEGIN
+    PERFORM pg_notify('short_link_changes', '');
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS tenants_notify ON tenants;
+CREATE TRIGGER tenants_notify
+    AFTER UPDATE OF status OR DELETE ON tenants
+    FOR EACH ROW EXECUTE FUNCTION notify_tenant_change();
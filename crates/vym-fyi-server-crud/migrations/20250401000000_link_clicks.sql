@@ -0,0 +1,15 @@
+-- Click events recorded by the redirect server, one row per redirect.
+-- No foreign keys: analytics are append-only and outlive deleted links.
+
+CREATE TABLE IF NOT EXISTS link_clicks (
+    id bigserial PRIMARY KEY,
+    slug text NOT NULL,
+    tenant_id uuid,
+    clicked_at timestamptz NOT NULL,
+    referrer text,
+    user_agent_class text NOT NULL,
+    country text
+);
+
+CREATE INDEX IF NOT EXISTS link_clicks_slug_clicked_at_idx
+    ON link_clicks (slug, clicked_at);
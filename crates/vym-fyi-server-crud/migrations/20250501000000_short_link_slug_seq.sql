@@ -0,0 +1,2 @@
+-- Source of the values encoded by the `hashid` slug style.
+CREATE SEQUENCE IF NOT EXISTS short_link_slug_seq;
@@ -0,0 +1,7 @@
+-- Version of a link, bumped on every change and exposed as its ETag.
+-- Values come from a sequence so a deleted and re-created slug never
+-- reuses an old version.
+CREATE SEQUENCE IF NOT EXISTS short_link_version_seq;
+
+ALTER TABLE short_links
+    ADD COLUMN IF NOT EXISTS version bigint NOT NULL DEFAULT nextval('short_link_version_seq');
@@ -0,0 +1,4 @@
+-- HTTP status each link redirects with.
+ALTER TABLE short_links
+    ADD COLUMN IF NOT EXISTS redirect_type smallint NOT NULL DEFAULT 307
+        CHECK (redirect_type IN (301, 302, 307, 308));
@@ -0,0 +1,5 @@
+-- Forwarding of the redirect request's query string and path suffix.
+ALTER TABLE short_links
+    ADD COLUMN IF NOT EXISTS query_passthrough text NOT NULL DEFAULT 'off'
+        CHECK (query_passthrough IN ('off', 'target_wins', 'request_wins', 'append')),
+    ADD COLUMN IF NOT EXISTS path_passthrough boolean NOT NULL DEFAULT false;
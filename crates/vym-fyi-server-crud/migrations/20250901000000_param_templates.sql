@@ -0,0 +1,10 @@
+-- Query parameter templates (e.g. UTM tags) applied to targets on redirect:
+-- one per tenant, synced from the tenants config, and one per link.
+ALTER TABLE tenants ADD COLUMN IF NOT EXISTS param_template text;
+ALTER TABLE short_links ADD COLUMN IF NOT EXISTS param_template text;
+
+-- Changing a tenant's template changes where all of its links resolve.
+DROP TRIGGER IF EXISTS tenants_notify ON tenants;
+CREATE TRIGGER tenants_notify
+    AFTER UPDATE OF status, param_template OR DELETE ON tenants
+    FOR EACH ROW EXECUTE FUNCTION notify_tenant_change();
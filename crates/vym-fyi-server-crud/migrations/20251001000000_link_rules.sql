@@ -0,0 +1,36 @@
+-- Conditional redirect rules, checked in `position` order; the first rule
+-- whose conditions all hold picks the target. Empty arrays and NULL bounds
+-- match every visit.
+
+CREATE TABLE IF NOT EXISTS link_rules (
+    id uuid PRIMARY KEY,
+    slug text NOT NULL REFERENCES short_links(slug) ON DELETE CASCADE,
+    position integer NOT NULL,
+    languages text[] NOT NULL DEFAULT '{}',
+    devices text[] NOT NULL DEFAULT '{}',
+    not_before timestamptz,
+    not_after timestamptz,
+    target_url text NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS link_rules_slug_position_idx
+    ON link_rules (slug, position);
+
+-- Rules are cached with their link on the redirect server. The function
+-- only needs the row's slug, so every table keyed by slug can reuse it.
+CREATE OR REPLACE FUNCTION notify_slug_row_change() RETURNS trigger AS $$
+BEGIN
+    IF TG_OP IN ('UPDATE', 'DELETE') THEN
+        PERFORM pg_notify('short_link_changes', OLD.slug);
+    END IF;
+    IF TG_OP IN ('INSERT', 'UPDATE') THEN
+        PERFORM pg_notify('short_link_changes', NEW.slug);
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS link_rules_notify ON link_rules;
+CREATE TRIGGER link_rules_notify
+    AFTER INSERT OR UPDATE OR DELETE ON link_rules
+    FOR EACH ROW EXECUTE FUNCTION notify_slug_row_change();
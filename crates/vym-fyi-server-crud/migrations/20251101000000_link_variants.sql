@@ -0,0 +1,24 @@
+-- Weighted A/B split targets. A link with variants sends each visitor to
+-- one of them in proportion to `weight`; `sticky_variants` keeps returning
+-- visitors on their first variant (remembered in a cookie).
+
+ALTER TABLE short_links
+    ADD COLUMN IF NOT EXISTS sticky_variants boolean NOT NULL DEFAULT FALSE;
+
+CREATE TABLE IF NOT EXISTS link_variants (
+    slug text NOT NULL REFERENCES short_links(slug) ON DELETE CASCADE,
+    name text NOT NULL,
+    position integer NOT NULL,
+    weight integer NOT NULL CHECK (weight >= 0),
+    target_url text NOT NULL,
+    PRIMARY KEY (slug, name)
+);
+
+-- Variants are cached with their link on the redirect server.
+DROP TRIGGER IF EXISTS link_variants_notify ON link_variants;
+CREATE TRIGGER link_variants_notify
+    AFTER INSERT OR UPDATE OR DELETE ON link_variants
+    FOR EACH ROW EXECUTE FUNCTION notify_slug_row_change();
+
+-- The variant a click was sent to, for comparing variants.
+ALTER TABLE link_clicks ADD COLUMN IF NOT EXISTS variant text;
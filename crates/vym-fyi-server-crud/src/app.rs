@@ -1,72 +1,247 @@
-use std::collections::HashSet;
-use std::path::Path;
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
 
 use sqlx::{Pool, Postgres, postgres::PgPoolOptions};
 use tracing::{info, warn};
 use uuid::Uuid;
-use vym_fyi_model::models::errors::AppResult;
-use vym_fyi_model::models::url_shortener::ClientConfig;
+use vym_fyi_model::models::errors::{AppError, AppResult};
+use vym_fyi_model::models::url_shortener::{
+    ClientConfig, ClientEntry, RateLimitPolicy, Role, Scope, SlugStyle, TenantStatus,
+};
+use vym_fyi_model::services::api_keys::{ApiKeyLookup, hash_api_key, verify_api_key};
 use vym_fyi_model::services::config::{load_client_config, resolve_env_placeholders};
+use vym_fyi_model::services::param_template::ParamTemplate;
 use vym_fyi_model::services::repos::{
+    ApiKeyRecord, ApiKeyRepository, ClickRepository, LinkRuleRepository, LinkVariantRepository,
     PgRepositoryFactory, RepositoryFactory, ShortLinkRepository, TenantRepository,
 };
+use vym_fyi_model::services::slug::{MIN_HASHID_SALT_LEN, SlugGenerator, SlugGenerators};
+use vym_fyi_model::services::slug_policy::SlugPolicy;
+use vym_fyi_model::services::target_url::{HostBlocklist, TargetUrlPolicy};
 
-/// Simple in-memory representation of an API key binding derived from the tenants config.
-#[derive(Clone)]
+use crate::tenant_sync::{TenantSyncOptions, sync_tenants, tenant_ids_by_name};
+
+/// Identity resolved from an API key: either the master key or a tenant key
+/// stored (hashed) in `api_keys`.
+#[derive(Clone, Debug)]
 pub struct ApiKeyBinding {
-    pub key: String,
-    pub client_id: Option<String>,
+    /// Id of the `api_keys` row; `None` for the master key.
+    pub api_key_id: Option<Uuid>,
     pub tenant_id: Option<Uuid>,
+    /// Status of the owning tenant; `None` for the master key.
+    pub tenant_status: Option<TenantStatus>,
+    pub scopes: Vec<Scope>,
     pub is_master: bool,
+    /// Settings of the owning tenant; the server defaults for the master key.
+    pub settings: TenantSettings,
 }
 
-/// Delegates API key lookup and comparison (constant-time).
+/// Name given to `api_keys` rows seeded from the tenants config.
+pub const CONFIG_KEY_NAME: &str = "config";
+
+/// Default token-bucket limit per API key, overridable with
+/// `RATE_LIMIT_BURST` / `RATE_LIMIT_PER_SECOND`.
+pub const DEFAULT_RATE_LIMIT: RateLimitPolicy = RateLimitPolicy {
+    burst: 60,
+    per_second: 10.0,
+};
+
+/// Minimum length of server-generated slugs unless a tenant asks for more.
+pub const DEFAULT_MIN_SLUG_LEN: usize = 6;
+
+/// Longest slug the server generates, and upper bound for `min_slug_len`.
+pub const MAX_GENERATED_SLUG_LEN: usize = 64;
+
+/// Per-tenant settings from the tenants config, merged over the server-wide
+/// defaults.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TenantSettings {
+    /// Limit applied to requests made with the tenant's keys; `None` when
+    /// unlimited.
+    pub rate_limit: Option<RateLimitPolicy>,
+    /// Minimum length of slugs generated for the tenant.
+    pub min_slug_len: usize,
+    /// Style of slugs generated for the tenant.
+    pub slug_style: SlugStyle,
+    /// Rules for slugs chosen by the tenant's callers.
+    pub slug_policy: SlugPolicy,
+    /// Hosts the tenant's links may not point to, checked in addition to
+    /// the server-wide [`TargetUrlPolicy`].
+    pub target_blocklist: HostBlocklist,
+}
+
+impl Default for TenantSettings {
+    fn default() -> Self {
+        Self {
+            rate_limit: None,
+            min_slug_len: DEFAULT_MIN_SLUG_LEN,
+            slug_style: SlugStyle::default(),
+            slug_policy: SlugPolicy::default(),
+            target_blocklist: HostBlocklist::default(),
+        }
+    }
+}
+
+impl TenantSettings {
+    /// These settings with the overrides of a tenants config entry applied.
+    fn merged(&self, entry: &ClientEntry) -> AppResult<Self> {
+        Ok(Self {
+            rate_limit: entry.rate_limit.or(self.rate_limit),
+            min_slug_len: entry.min_slug_len.unwrap_or(self.min_slug_len),
+            slug_style: entry.slug_style.unwrap_or(self.slug_style),
+            slug_policy: match &entry.vanity_slugs {
+                Some(vanity) => SlugPolicy::new(vanity)?,
+                None => self.slug_policy.clone(),
+            },
+            target_blocklist: match &entry.blocked_targets {
+                Some(entries) => HostBlocklist::parse(entries)?,
+                None => self.target_blocklist.clone(),
+            },
+        })
+    }
+}
+
+/// Authenticates API keys: the master key from config (constant-time
+/// comparison) or tenant keys looked up by their lookup digest and verified
+/// against their Argon2 hash.
 #[derive(Clone)]
 pub struct ApiKeyStore {
-    bindings: Vec<ApiKeyBinding>,
+    master_key: Option<String>,
+    repo: ApiKeyRepository,
+    lookup: ApiKeyLookup,
+    /// Server-wide settings, used for the master key and tenants missing
+    /// from the config.
+    default_settings: TenantSettings,
+    /// Settings of the tenants in the config, keyed by tenant name.
+    tenant_settings: HashMap<String, TenantSettings>,
 }
 
 impl ApiKeyStore {
-    pub fn new(bindings: Vec<ApiKeyBinding>) -> Self {
-        Self { bindings }
-    }
-
-    pub fn authenticate(&self, api_key: &str, client_id: Option<&str>) -> Option<ApiKeyBinding> {
-        fn constant_time_eq(a: &str, b: &str) -> bool {
-            let ab = a.as_bytes();
-            let bb = b.as_bytes();
-            let max = ab.len().max(bb.len());
-            let mut diff: u8 = (ab.len() ^ bb.len()) as u8;
-            for i in 0..max {
-                let av = *ab.get(i).unwrap_or(&0);
-                let bv = *bb.get(i).unwrap_or(&0);
-                diff |= av ^ bv;
-            }
-            diff == 0
+    pub fn new(
+        master_key: Option<String>,
+        repo: ApiKeyRepository,
+        lookup: ApiKeyLookup,
+        default_settings: TenantSettings,
+    ) -> Self {
+        Self {
+            master_key,
+            repo,
+            lookup,
+            default_settings,
+            tenant_settings: HashMap::new(),
         }
+    }
 
-        // Master key takes precedence, global scope.
-        if let Some(binding) = self
-            .bindings
+    /// Apply the per-tenant overrides of the clients in `config`.
+    pub fn with_tenant_settings(mut self, config: &ClientConfig) -> AppResult<Self> {
+        self.tenant_settings = config
+            .clients
             .iter()
-            .find(|b| b.is_master && constant_time_eq(&b.key, api_key))
+            .map(|(client_id, entry)| Ok((client_id.clone(), self.default_settings.merged(entry)?)))
+            .collect::<AppResult<_>>()?;
+        Ok(self)
+    }
+
+    /// Resolve an API key to its binding. Tenant keys additionally need a
+    /// `client_id` naming the tenant that owns the key; only the master key
+    /// works without one.
+    pub async fn authenticate(
+        &self,
+        api_key: &str,
+        client_id: Option<&str>,
+    ) -> AppResult<Option<ApiKeyBinding>> {
+        // Master key takes precedence, global scope.
+        if let Some(master) = &self.master_key
+            && constant_time_eq(master, api_key)
         {
-            return Some(binding.clone());
-        }
-
-        // Otherwise require a client id match.
-        client_id.and_then(|cid| {
-            self.bindings
-                .iter()
-                .find(|b| {
-                    !b.is_master
-                        && b.client_id.as_deref() == Some(cid)
-                        && constant_time_eq(&b.key, api_key)
-                })
-                .cloned()
-        })
+            return Ok(Some(ApiKeyBinding {
+                api_key_id: None,
+                tenant_id: None,
+                tenant_status: None,
+                scopes: Scope::ALL.to_vec(),
+                is_master: true,
+                settings: self.default_settings.clone(),
+            }));
+        }
+
+        let Some(client_id) = client_id else {
+            return Ok(None);
+        };
+
+        let key_lookup = self.lookup.digest(api_key);
+        let candidates = self.repo.find_active_by_lookup(&key_lookup).await?;
+        if candidates.is_empty() {
+            return Ok(None);
+        }
+
+        // Argon2 verification is CPU-bound; keep it off the async workers.
+        let api_key = api_key.to_owned();
+        let client_id = client_id.to_owned();
+        let matched =
+            tokio::task::spawn_blocking(move || match_api_key(candidates, &api_key, &client_id))
+                .await
+                .map_err(|e| {
+                    AppError::Server(format!("API key verification task failed: {}", e))
+                })?;
+        let Some((record, binding)) = matched else {
+            return Ok(None);
+        };
+
+        Ok(Some(ApiKeyBinding {
+            settings: self
+                .tenant_settings
+                .get(&record.tenant_name)
+                .unwrap_or(&self.default_settings)
+                .clone(),
+            ..binding
+        }))
+    }
+}
+
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    let ab = a.as_bytes();
+    let bb = b.as_bytes();
+    let max = ab.len().max(bb.len());
+    let mut diff: u8 = (ab.len() ^ bb.len()) as u8;
+    for i in 0..max {
+        let av = *ab.get(i).unwrap_or(&0);
+        let bv = *bb.get(i).unwrap_or(&0);
+        diff |= av ^ bv;
     }
+    diff == 0
+}
+
+/// Find the candidate matching `api_key`, returning it and a binding with
+/// the default settings.
+fn match_api_key(
+    candidates: Vec<ApiKeyRecord>,
+    api_key: &str,
+    client_id: &str,
+) -> Option<(ApiKeyRecord, ApiKeyBinding)> {
+    candidates
+        .into_iter()
+        .filter(|record| record.tenant_name == client_id)
+        .find(|record| verify_api_key(api_key, &record.key_hash))
+        .map(|record| {
+            let binding = ApiKeyBinding {
+                api_key_id: Some(record.id),
+                tenant_id: Some(record.tenant_id),
+                tenant_status: Some(record.tenant_status),
+                scopes: record.role.scopes().to_vec(),
+                is_master: false,
+                settings: TenantSettings::default(),
+            };
+            (record, binding)
+        })
+}
+
+/// Where the tenants config lives and how it is synchronized, kept so the
+/// config can be reloaded at runtime.
+#[derive(Clone, Debug)]
+pub struct TenantsConfigSource {
+    pub path: PathBuf,
+    pub sync: TenantSyncOptions,
 }
 
 /// Facade over core CRUD server components (DB pool, repositories).
@@ -74,7 +249,30 @@ impl ApiKeyStore {
 pub struct CrudApp {
     pool: Pool<Postgres>,
     repos: Arc<dyn RepositoryFactory>,
-    pub api_keys: ApiKeyStore,
+    /// Swapped as a whole when the tenants config is reloaded.
+    api_keys: Arc<RwLock<Arc<ApiKeyStore>>>,
+    tenants_config: Option<TenantsConfigSource>,
+    rate_limit: Option<RateLimitPolicy>,
+    slug_generators: Arc<SlugGenerators>,
+    target_url_policy: Arc<TargetUrlPolicy>,
+    api_key_lookup: ApiKeyLookup,
+}
+
+/// `SLUG_HASHID_SALT`, if set. A salt shorter than [`MIN_HASHID_SALT_LEN`]
+/// is rejected; without one the `hashid` slug style is disabled.
+fn hashid_salt_from_env() -> AppResult<Option<String>> {
+    match std::env::var("SLUG_HASHID_SALT") {
+        Ok(salt) if salt.is_empty() => Ok(None),
+        Ok(salt) if salt.len() < MIN_HASHID_SALT_LEN => Err(AppError::Config(format!(
+            "SLUG_HASHID_SALT must be at least {} characters",
+            MIN_HASHID_SALT_LEN
+        ))),
+        Ok(salt) => Ok(Some(salt)),
+        Err(_) => {
+            info!("SLUG_HASHID_SALT not set; the hashid slug style is disabled");
+            Ok(None)
+        }
+    }
 }
 
 /// Builder for `CrudApp` (builder pattern).
@@ -82,20 +280,36 @@ pub struct CrudAppBuilder {
     database_url: String,
     max_connections: u32,
     tenants_config_path: Option<String>,
+    tenant_sync: TenantSyncOptions,
+    rate_limit: Option<RateLimitPolicy>,
+    /// Keys the `hashid` slug style; changing it makes new slugs collide
+    /// with old ones more often. Unset disables the style.
+    hashid_salt: Option<String>,
+    target_url_policy: TargetUrlPolicy,
+    api_key_lookup: ApiKeyLookup,
 }
 
 impl CrudAppBuilder {
     /// Construct a builder using standard environment variables.
     pub fn from_env() -> AppResult<Self> {
-        let database_url = std::env::var("DATABASE_URL").map_err(|_| {
-            vym_fyi_model::models::errors::AppError::Config("DATABASE_URL not set".into())
-        })?;
+        let database_url = std::env::var("DATABASE_URL")
+            .map_err(|_| AppError::Config("DATABASE_URL not set".into()))?;
         let tenants_config_path = std::env::var("TENANTS_CONFIG_PATH").ok();
+        let tenant_sync = TenantSyncOptions::from_env()?;
+        let rate_limit = RateLimitPolicy::from_env(DEFAULT_RATE_LIMIT)?;
+        let hashid_salt = hashid_salt_from_env()?;
+        let target_url_policy = TargetUrlPolicy::from_env()?;
+        let api_key_lookup = ApiKeyLookup::from_env()?;
 
         Ok(Self {
             database_url,
             max_connections: 5,
             tenants_config_path,
+            tenant_sync,
+            rate_limit,
+            hashid_salt,
+            target_url_policy,
+            api_key_lookup,
         })
     }
 
@@ -118,42 +332,47 @@ impl CrudAppBuilder {
 
         let repos: Arc<dyn RepositoryFactory> = Arc::new(PgRepositoryFactory::new(pool.clone()));
 
-        if let Some(path) = self.tenants_config_path {
-            let path_buf = Path::new(&path).to_path_buf();
-            let config: ClientConfig = load_client_config(&path_buf)?;
-
-            // Synchronize tenants from config.
-            {
-                let tenant_repo = repos.tenant_repo();
-                sync_tenants_with_repo(&tenant_repo, &config).await?;
-            }
-
-            // Build mapping tenant_name -> tenant_id.
-            let tenant_repo = repos.tenant_repo();
-            let existing = tenant_repo.list_all().await?;
-            let mut tenant_map = std::collections::HashMap::new();
-            for (id, name) in existing {
-                tenant_map.insert(name, id);
-            }
-
-            // Build in-memory API key bindings from config using tenant ids.
-            let api_keys = build_api_key_bindings(&config, &tenant_map)?;
+        let tenants_config = self.tenants_config_path.map(|path| TenantsConfigSource {
+            path: PathBuf::from(path),
+            sync: self.tenant_sync,
+        });
 
-            Ok(CrudApp {
-                pool,
-                repos,
-                api_keys,
-            })
+        let default_settings = TenantSettings {
+            rate_limit: self.rate_limit,
+            ..TenantSettings::default()
+        };
+        let api_keys = if let Some(source) = &tenants_config {
+            let config: ClientConfig = load_client_config(&source.path)?;
+            let master_key =
+                apply_tenants_config(repos.as_ref(), &self.api_key_lookup, &config, &source.sync)
+                    .await?;
+            ApiKeyStore::new(
+                master_key,
+                repos.api_key_repo(),
+                self.api_key_lookup.clone(),
+                default_settings,
+            )
+            .with_tenant_settings(&config)?
         } else {
-            warn!(
-                "TENANTS_CONFIG_PATH not set; skipping tenant synchronization and API key bindings"
-            );
-            Ok(CrudApp {
-                pool,
-                repos,
-                api_keys: ApiKeyStore::new(Vec::new()),
-            })
-        }
+            warn!("TENANTS_CONFIG_PATH not set; skipping tenant synchronization and key seeding");
+            ApiKeyStore::new(
+                None,
+                repos.api_key_repo(),
+                self.api_key_lookup.clone(),
+                default_settings,
+            )
+        };
+
+        Ok(CrudApp {
+            pool,
+            repos,
+            api_keys: Arc::new(RwLock::new(Arc::new(api_keys))),
+            tenants_config,
+            rate_limit: self.rate_limit,
+            slug_generators: Arc::new(SlugGenerators::new(self.hashid_salt.as_deref())),
+            target_url_policy: Arc::new(self.target_url_policy),
+            api_key_lookup: self.api_key_lookup,
+        })
     }
 }
 
@@ -162,64 +381,396 @@ impl CrudApp {
         self.repos.short_link_repo()
     }
 
+    pub fn tenant_repository(&self) -> TenantRepository {
+        self.repos.tenant_repo()
+    }
+
+    pub fn api_key_repository(&self) -> ApiKeyRepository {
+        self.repos.api_key_repo()
+    }
+
+    pub fn click_repository(&self) -> ClickRepository {
+        self.repos.click_repo()
+    }
+
+    pub fn link_rule_repository(&self) -> LinkRuleRepository {
+        self.repos.link_rule_repo()
+    }
+
+    pub fn link_variant_repository(&self) -> LinkVariantRepository {
+        self.repos.link_variant_repo()
+    }
+
     pub fn db_pool(&self) -> &Pool<Postgres> {
         &self.pool
     }
-}
 
-async fn sync_tenants_with_repo(repo: &TenantRepository, config: &ClientConfig) -> AppResult<()> {
-    let desired: HashSet<String> = config.clients.keys().cloned().collect();
-    info!("tenant sync: {} tenants defined in config", desired.len());
+    /// The API key store currently in effect.
+    pub fn api_key_store(&self) -> Arc<ApiKeyStore> {
+        self.api_keys
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
 
-    let existing = repo.list_all().await?;
-    let existing_names: HashSet<String> = existing.iter().map(|(_, name)| name.clone()).collect();
+    pub fn tenants_config(&self) -> Option<&TenantsConfigSource> {
+        self.tenants_config.as_ref()
+    }
 
-    // Create tenants in config but missing in DB.
-    for tenant_name in desired.difference(&existing_names) {
-        info!("tenant sync: creating tenant name={}", tenant_name);
-        let _ = repo.create(tenant_name).await?;
+    /// Generator for slugs of the given style, if the style is enabled.
+    pub fn slug_generator(&self, style: SlugStyle) -> Option<&dyn SlugGenerator> {
+        self.slug_generators.get(style)
     }
 
-    // Delete tenants that exist in DB but not in config.
-    for (_, name) in existing
-        .into_iter()
-        .filter(|(_, name)| !desired.contains(name))
+    /// Digests used to find API keys by their value.
+    pub fn api_key_lookup(&self) -> &ApiKeyLookup {
+        &self.api_key_lookup
+    }
+
+    /// Server-wide rules for link targets.
+    pub fn target_url_policy(&self) -> &TargetUrlPolicy {
+        &self.target_url_policy
+    }
+
+    /// Server-wide rate limit, applied by client IP to unauthenticated
+    /// requests and to keys without a tenant override.
+    pub fn rate_limit(&self) -> Option<RateLimitPolicy> {
+        self.rate_limit
+    }
+
+    /// Re-read the tenants config, synchronize tenants and keys, and swap in
+    /// a new `ApiKeyStore`. An invalid config is rejected before anything is
+    /// written, leaving the previous store in place.
+    pub async fn reload_tenants_config(&self) -> AppResult<()> {
+        let source = self
+            .tenants_config
+            .as_ref()
+            .ok_or_else(|| AppError::Config("TENANTS_CONFIG_PATH not set".into()))?;
+
+        let config = load_client_config(&source.path)?;
+        let master_key = apply_tenants_config(
+            self.repos.as_ref(),
+            &self.api_key_lookup,
+            &config,
+            &source.sync,
+        )
+        .await?;
+        let store = Arc::new(
+            ApiKeyStore::new(
+                master_key,
+                self.repos.api_key_repo(),
+                self.api_key_lookup.clone(),
+                TenantSettings {
+                    rate_limit: self.rate_limit,
+                    ..TenantSettings::default()
+                },
+            )
+            .with_tenant_settings(&config)?,
+        );
+
+        *self
+            .api_keys
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = store;
+        Ok(())
+    }
+}
+
+/// Check that every key in the config resolves to a non-empty value, so a
+/// broken config is rejected before tenants or keys are touched.
+pub fn validate_tenants_config(config: &ClientConfig) -> AppResult<()> {
+    if let Some(raw) = &config.server.master_api_key
+        && resolve_env_placeholders(raw)?.is_empty()
     {
-        info!("tenant sync: deleting tenant name={}", name);
-        repo.delete_by_name(&name).await?;
+        return Err(AppError::Config("server.master_api_key is empty".into()));
+    }
+
+    for (client_id, entry) in &config.clients {
+        if resolve_env_placeholders(&entry.api_key)?.is_empty() {
+            return Err(AppError::Config(format!(
+                "api_key for client {} is empty",
+                client_id
+            )));
+        }
+        if let Some(limit) = &entry.rate_limit
+            && !(limit.per_second >= 0.0 && limit.per_second.is_finite())
+        {
+            return Err(AppError::Config(format!(
+                "rate_limit.per_second for client {} must be a non-negative number",
+                client_id
+            )));
+        }
+        if let Some(len) = entry.min_slug_len
+            && !(DEFAULT_MIN_SLUG_LEN..=MAX_GENERATED_SLUG_LEN).contains(&len)
+        {
+            return Err(AppError::Config(format!(
+                "min_slug_len for client {} must be between {} and {}",
+                client_id, DEFAULT_MIN_SLUG_LEN, MAX_GENERATED_SLUG_LEN
+            )));
+        }
+        if let Some(vanity) = &entry.vanity_slugs {
+            SlugPolicy::new(vanity).map_err(|e| {
+                AppError::Config(format!("vanity_slugs for client {}: {}", client_id, e))
+            })?;
+        }
+        if let Some(entries) = &entry.blocked_targets {
+            HostBlocklist::parse(entries).map_err(|e| {
+                AppError::Config(format!("blocked_targets for client {}: {}", client_id, e))
+            })?;
+        }
+        if let Some(template) = &entry.param_template {
+            ParamTemplate::parse(template).map_err(|e| {
+                AppError::Config(format!("param_template for client {}: {}", client_id, e))
+            })?;
+        }
     }
 
     Ok(())
 }
 
-fn build_api_key_bindings(
+/// Validate the config, synchronize tenants, seed config keys and return the
+/// resolved master key.
+async fn apply_tenants_config(
+    repos: &dyn RepositoryFactory,
+    lookup: &ApiKeyLookup,
     config: &ClientConfig,
-    tenant_map: &std::collections::HashMap<String, Uuid>,
-) -> AppResult<ApiKeyStore> {
-    let mut bindings = Vec::new();
+    sync: &TenantSyncOptions,
+) -> AppResult<Option<String>> {
+    validate_tenants_config(config)?;
 
-    // Per-client API keys
+    // Synchronize tenants from config.
+    let tenant_repo = repos.tenant_repo();
+    sync_tenants(&tenant_repo, config, sync).await?;
+
+    // Seed hashed API keys for the clients defined in config. In
+    // dry-run mode nothing is written, keys included.
+    if sync.dry_run {
+        info!("tenant sync dry-run: skipping API key seeding");
+    } else {
+        let tenant_map = tenant_ids_by_name(&tenant_repo).await?;
+        seed_config_api_keys(&repos.api_key_repo(), lookup, config, &tenant_map).await?;
+    }
+
+    config
+        .server
+        .master_api_key
+        .as_deref()
+        .map(resolve_env_placeholders)
+        .transpose()
+}
+
+/// Ensure every client in the config has an active, hashed `api_keys` row
+/// matching its configured key and role. Rows seeded from an older key, or
+/// digested with an older lookup secret, are revoked, so rotating either
+/// takes effect on restart.
+async fn seed_config_api_keys(
+    repo: &ApiKeyRepository,
+    lookup: &ApiKeyLookup,
+    config: &ClientConfig,
+    tenant_map: &HashMap<String, Uuid>,
+) -> AppResult<()> {
     for (client_id, entry) in &config.clients {
+        let Some(tenant_id) = tenant_map.get(client_id).copied() else {
+            warn!("key seeding: no tenant for client {}; skipping", client_id);
+            continue;
+        };
+
         let resolved_key = resolve_env_placeholders(&entry.api_key)?;
-        let tenant_id = tenant_map.get(client_id).copied();
-        bindings.push(ApiKeyBinding {
-            key: resolved_key,
-            client_id: Some(client_id.clone()),
-            tenant_id,
-            is_master: false,
-        });
+        // Tenant self-administration is opt-in: without an explicit role the
+        // key only manages links, as config keys always could.
+        let role = entry.role.clone().unwrap_or(Role::Url);
+
+        let key_lookup = lookup.digest(&resolved_key);
+        let existing = repo.list_active_by_name(tenant_id, CONFIG_KEY_NAME).await?;
+        if existing.iter().any(|k| {
+            k.role == role
+                && k.key_lookup == key_lookup
+                && verify_api_key(&resolved_key, &k.key_hash)
+        }) {
+            continue;
+        }
+
+        for stale in existing {
+            info!(
+                "key seeding: revoking outdated config key id={} for {}",
+                stale.id, client_id
+            );
+            repo.revoke(tenant_id, stale.id).await?;
+        }
+
+        let key_hash = hash_api_key(&resolved_key)?;
+        let stored = repo
+            .create(tenant_id, CONFIG_KEY_NAME, &key_lookup, &key_hash, &role)
+            .await?;
+        info!(
+            "key seeding: stored config key id={} for {}",
+            stored.id, client_id
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use vym_fyi_model::models::url_shortener::{ServerSection, VanitySlugPolicy};
+    use vym_fyi_model::services::api_keys::MIN_LOOKUP_SECRET_LEN;
+
+    fn record(tenant_name: &str, api_key: &str) -> ApiKeyRecord {
+        ApiKeyRecord {
+            id: Uuid::new_v4(),
+            tenant_id: Uuid::new_v4(),
+            tenant_name: tenant_name.to_string(),
+            tenant_status: TenantStatus::Active,
+            name: CONFIG_KEY_NAME.to_string(),
+            key_lookup: ApiKeyLookup::new(&"s".repeat(MIN_LOOKUP_SECRET_LEN))
+                .unwrap()
+                .digest(api_key),
+            key_hash: hash_api_key(api_key).unwrap(),
+            role: Role::Url,
+            created_at: Utc::now(),
+            revoked_at: None,
+        }
+    }
+
+    fn config(master: Option<&str>, client_key: &str) -> ClientConfig {
+        ClientConfig {
+            server: ServerSection {
+                base_url: "http://localhost".into(),
+                master_api_key: master.map(str::to_owned),
+            },
+            clients: HashMap::from([(
+                "client-a".to_string(),
+                ClientEntry {
+                    name: "A".into(),
+                    api_key: client_key.into(),
+                    ..Default::default()
+                },
+            )]),
+        }
+    }
+
+    #[test]
+    fn validate_tenants_config_accepts_resolvable_keys() {
+        assert!(validate_tenants_config(&config(Some("master"), "key-a")).is_ok());
+        assert!(validate_tenants_config(&config(None, "key-a")).is_ok());
+    }
+
+    #[test]
+    fn validate_tenants_config_rejects_empty_or_unresolved_keys() {
+        assert!(matches!(
+            validate_tenants_config(&config(None, "")),
+            Err(AppError::Config(_))
+        ));
+        assert!(matches!(
+            validate_tenants_config(&config(Some(""), "key-a")),
+            Err(AppError::Config(_))
+        ));
+        assert!(matches!(
+            validate_tenants_config(&config(None, "$(VYM_FYI_TEST_UNSET_KEY)")),
+            Err(AppError::MissingEnvVar { .. })
+        ));
+    }
+
+    #[test]
+    fn validate_tenants_config_checks_client_settings() {
+        type Configure = fn(&mut ClientEntry);
+        let cases: [(&str, Configure, bool); 7] = [
+            ("min_slug_len in range", |e| e.min_slug_len = Some(12), true),
+            (
+                "min_slug_len too short",
+                |e| e.min_slug_len = Some(4),
+                false,
+            ),
+            (
+                "min_slug_len too long",
+                |e| e.min_slug_len = Some(65),
+                false,
+            ),
+            (
+                "invalid vanity pattern",
+                |e| {
+                    e.vanity_slugs = Some(VanitySlugPolicy {
+                        pattern: Some("[a-z".into()),
+                        ..Default::default()
+                    })
+                },
+                false,
+            ),
+            (
+                "invalid blocked target",
+                |e| e.blocked_targets = Some(vec!["example.com".into(), "10.0.0.0/33".into()]),
+                false,
+            ),
+            (
+                "valid param template",
+                |e| e.param_template = Some("utm_source={{tenant}}&utm_campaign={{slug}}".into()),
+                true,
+            ),
+            (
+                "unknown param template variable",
+                |e| e.param_template = Some("utm_source={{client}}".into()),
+                false,
+            ),
+        ];
+
+        for (case, configure, valid) in cases {
+            let mut config = config(None, "key-a");
+            configure(config.clients.get_mut("client-a").unwrap());
+            let result = validate_tenants_config(&config);
+            if valid {
+                assert!(result.is_ok(), "{}: {:?}", case, result);
+            } else {
+                assert!(
+                    matches!(result, Err(AppError::Config(_))),
+                    "{}: {:?}",
+                    case,
+                    result
+                );
+            }
+        }
     }
 
-    // Optional master API key
-    if let Some(raw_master) = &config.server.master_api_key {
-        let resolved_master = resolve_env_placeholders(raw_master)?;
-        bindings.push(ApiKeyBinding {
-            key: resolved_master,
-            client_id: None,
-            tenant_id: None,
-            is_master: true,
+    #[test]
+    fn tenant_settings_override_defaults() {
+        let defaults = TenantSettings {
+            rate_limit: Some(DEFAULT_RATE_LIMIT),
+            ..TenantSettings::default()
+        };
+        let mut entry = config(None, "key-a").clients.remove("client-a").unwrap();
+        assert_eq!(defaults.merged(&entry).unwrap(), defaults);
+
+        entry.min_slug_len = Some(10);
+        entry.rate_limit = Some(RateLimitPolicy {
+            burst: 1,
+            per_second: 0.0,
         });
+        let merged = defaults.merged(&entry).unwrap();
+        assert_eq!(merged.min_slug_len, 10);
+        assert_eq!(merged.rate_limit.map(|l| l.burst), Some(1));
+    }
+
+    #[test]
+    fn constant_time_eq_compares_full_strings() {
+        assert!(constant_time_eq("master", "master"));
+        assert!(!constant_time_eq("master", "master2"));
+        assert!(!constant_time_eq("master", "maste"));
     }
 
-    Ok(ApiKeyStore::new(bindings))
+    #[test]
+    fn match_api_key_verifies_hash_and_client_id() {
+        let candidates = vec![record("client-a", "key-a"), record("client-b", "key-b")];
+
+        let (record, binding) =
+            match_api_key(candidates.clone(), "key-b", "client-b").expect("key-b matches");
+        assert_eq!(record.tenant_name, "client-b");
+        assert_eq!(binding.tenant_id, Some(candidates[1].tenant_id));
+        assert_eq!(binding.scopes, Role::Url.scopes());
+        assert!(!binding.is_master);
+
+        assert!(match_api_key(candidates.clone(), "key-b", "client-a").is_none());
+        assert!(match_api_key(candidates, "key-c", "client-b").is_none());
+    }
 }
@@ -1,57 +1,283 @@
-use crate::app::CrudApp;
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::app::{ApiKeyBinding, CrudApp, TenantSettings};
 use axum::{
-    extract::FromRequestParts,
-    http::{StatusCode, request::Parts},
+    extract::{FromRequestParts, Request, State},
+    http::{HeaderMap, StatusCode, request::Parts},
+    middleware::Next,
+    response::Response,
 };
-use std::future::ready;
-use tracing::debug;
+use tracing::{debug, error, warn};
+use vym_fyi_model::models::url_shortener::{RateLimitPolicy, Scope, TenantStatus};
+use vym_fyi_model::services::rate_limit::{RateLimitKey, RateLimiter};
 
 /// Extracted information about the caller based on their API key.
 #[derive(Clone, Debug)]
 pub struct ApiKeyAuth {
     pub tenant_id: Option<uuid::Uuid>,
+    pub scopes: Vec<Scope>,
     pub is_master: bool,
+    pub settings: TenantSettings,
+}
+
+impl ApiKeyAuth {
+    /// Reject the request with `403` unless the key grants `scope`.
+    pub fn require(&self, scope: Scope) -> Result<(), StatusCode> {
+        if self.scopes.contains(&scope) {
+            Ok(())
+        } else {
+            warn!(
+                "API key for tenant {:?} lacks scope {}",
+                self.tenant_id,
+                scope.as_str()
+            );
+            Err(StatusCode::FORBIDDEN)
+        }
+    }
+}
+
+/// Rejection returned when a request cannot be authenticated: status plus a
+/// short plain-text reason shown to the caller.
+pub type AuthRejection = (StatusCode, &'static str);
+
+/// Outcome of resolving the request's API key, stored in the request
+/// extensions by [`authenticate`] so the key is verified only once.
+#[derive(Clone, Debug)]
+pub struct Authenticated(pub Result<ApiKeyBinding, AuthRejection>);
+
+/// Per-IP limit checked before API keys are looked up; shares its buckets
+/// with the rate-limit layer.
+#[derive(Clone)]
+pub struct PreAuthRateLimit {
+    pub limiter: Arc<RateLimiter>,
+    pub policy: Option<RateLimitPolicy>,
+}
+
+/// Middleware run before [`authenticate`]: answers `429` without looking up
+/// the API key when the client IP's bucket is already empty. The rate-limit
+/// layer charges anonymous and failed requests to that bucket, so a client
+/// guessing keys is stopped before it reaches Postgres and Argon2.
+pub async fn reject_exhausted_clients(
+    State(limit): State<PreAuthRateLimit>,
+    req: Request,
+    next: Next,
+) -> Response {
+    if req.uri().path().starts_with("/api/")
+        && let Some(key) = limit
+            .policy
+            .filter(RateLimitPolicy::is_enabled)
+            .and_then(|policy| RateLimitKey::client_ip(&req, policy))
+    {
+        let decision = limit.limiter.peek(&key.key, &key.policy, Instant::now());
+        if !decision.allowed {
+            debug!("rejecting request from exhausted client before authentication");
+            return decision.rejection(key.kind);
+        }
+    }
+    next.run(req).await
+}
+
+/// Middleware resolving the API key (if any) before rate limiting and the
+/// handlers run. Requests without a key pass through untouched.
+pub async fn authenticate(State(state): State<CrudApp>, mut req: Request, next: Next) -> Response {
+    if api_key_from_headers(req.headers()).is_some() {
+        let outcome = resolve_binding(req.headers(), &state).await;
+        req.extensions_mut().insert(Authenticated(outcome));
+    }
+    next.run(req).await
+}
+
+/// Rate-limit key for a request: its API key when authenticated (with the
+/// owning tenant's policy), otherwise its client IP. Only `/api` routes are
+/// limited.
+pub fn rate_limit_key(req: &Request, default: Option<RateLimitPolicy>) -> Option<RateLimitKey> {
+    if !req.uri().path().starts_with("/api/") {
+        return None;
+    }
+
+    match req.extensions().get::<Authenticated>() {
+        Some(Authenticated(Ok(binding))) => Some(RateLimitKey {
+            key: match binding.api_key_id {
+                Some(api_key_id) => format!("key:{}", api_key_id),
+                None => "master".to_string(),
+            },
+            kind: "api_key",
+            policy: binding.settings.rate_limit?,
+        }),
+        _ => RateLimitKey::client_ip(req, default?),
+    }
+}
+
+fn api_key_from_headers(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get("X-API-Key")
+        .and_then(|h| h.to_str().ok())
+        .or_else(|| {
+            headers
+                .get("Authorization")
+                .and_then(|h| h.to_str().ok())
+                .and_then(|h| h.strip_prefix("ApiKey "))
+        })
+}
+
+async fn resolve_binding(
+    headers: &HeaderMap,
+    state: &CrudApp,
+) -> Result<ApiKeyBinding, AuthRejection> {
+    let client_id = headers.get("X-Client-Id").and_then(|h| h.to_str().ok());
+    debug!("auth client_id={:?}", client_id);
+
+    let api_key =
+        api_key_from_headers(headers).ok_or((StatusCode::UNAUTHORIZED, "missing API key"))?;
+
+    state
+        .api_key_store()
+        .authenticate(api_key, client_id)
+        .await
+        .map_err(|e| {
+            error!("API key lookup failed: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "API key lookup failed")
+        })?
+        .ok_or((StatusCode::FORBIDDEN, "invalid API key"))
 }
 
 impl FromRequestParts<CrudApp> for ApiKeyAuth {
-    type Rejection = StatusCode;
+    type Rejection = AuthRejection;
 
-    fn from_request_parts(
+    async fn from_request_parts(
         parts: &mut Parts,
         state: &CrudApp,
-    ) -> impl std::future::Future<Output = Result<Self, Self::Rejection>> + Send {
-        let client_id = parts
-            .headers
-            .get("X-Client-Id")
-            .and_then(|h| h.to_str().ok())
-            .map(str::to_owned);
-        debug!("auth client_id={:?}", client_id);
-
-        let api_key = parts
-            .headers
-            .get("X-API-Key")
-            .and_then(|h| h.to_str().ok())
-            .map(str::to_owned)
-            .or_else(|| {
-                parts
-                    .headers
-                    .get("Authorization")
-                    .and_then(|h| h.to_str().ok())
-                    .and_then(|h| h.strip_prefix("ApiKey "))
-                    .map(str::to_owned)
-            });
-
-        let api_keys = state.api_keys.clone();
-
-        ready(match api_key {
-            Some(api_key) => api_keys
-                .authenticate(&api_key, client_id.as_deref())
-                .map(|binding| ApiKeyAuth {
-                    tenant_id: binding.tenant_id,
-                    is_master: binding.is_master,
-                })
-                .ok_or(StatusCode::FORBIDDEN),
-            None => Err(StatusCode::UNAUTHORIZED),
+    ) -> Result<Self, Self::Rejection> {
+        let binding = match parts.extensions.get::<Authenticated>() {
+            Some(Authenticated(outcome)) => outcome.clone()?,
+            None => resolve_binding(&parts.headers, state).await?,
+        };
+
+        if binding.tenant_status == Some(TenantStatus::Suspended) {
+            warn!(
+                "rejecting request for suspended tenant {:?}",
+                binding.tenant_id
+            );
+            return Err((StatusCode::FORBIDDEN, "tenant is suspended"));
+        }
+
+        Ok(ApiKeyAuth {
+            tenant_id: binding.tenant_id,
+            scopes: binding.scopes,
+            is_master: binding.is_master,
+            settings: binding.settings,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vym_fyi_model::models::url_shortener::Role;
+
+    #[test]
+    fn require_checks_granted_scopes() {
+        let auth = ApiKeyAuth {
+            tenant_id: Some(uuid::Uuid::new_v4()),
+            scopes: Role::ReadOnly.scopes().to_vec(),
+            is_master: false,
+            settings: TenantSettings::default(),
+        };
+
+        assert_eq!(auth.require(Scope::LinksRead), Ok(()));
+        assert_eq!(auth.require(Scope::LinksWrite), Err(StatusCode::FORBIDDEN));
+        assert_eq!(auth.require(Scope::LinksDelete), Err(StatusCode::FORBIDDEN));
+    }
+
+    #[test]
+    fn rate_limit_key_prefers_binding_over_client_ip() {
+        use vym_fyi_model::services::client_ip::ClientIp;
+
+        let policy = RateLimitPolicy {
+            burst: 5,
+            per_second: 1.0,
+        };
+        let api_key_id = uuid::Uuid::new_v4();
+        let request = |path: &str| {
+            let mut req = Request::get(path).body(axum::body::Body::empty()).unwrap();
+            req.extensions_mut()
+                .insert(ClientIp("203.0.113.7".parse().unwrap()));
+            req
+        };
+
+        let anonymous = rate_limit_key(&request("/api/links"), Some(policy)).unwrap();
+        assert_eq!(anonymous.key, "ip:203.0.113.7");
+        assert_eq!(anonymous.kind, "ip");
+        assert!(rate_limit_key(&request("/health"), Some(policy)).is_none());
+
+        let mut req = request("/api/links");
+        req.extensions_mut().insert(Authenticated(Ok(ApiKeyBinding {
+            api_key_id: Some(api_key_id),
+            tenant_id: Some(uuid::Uuid::new_v4()),
+            tenant_status: Some(TenantStatus::Active),
+            scopes: Role::Admin.scopes().to_vec(),
+            is_master: false,
+            settings: TenantSettings {
+                rate_limit: Some(RateLimitPolicy {
+                    burst: 100,
+                    per_second: 50.0,
+                }),
+                ..TenantSettings::default()
+            },
+        })));
+        let keyed = rate_limit_key(&req, Some(policy)).unwrap();
+        assert_eq!(keyed.key, format!("key:{}", api_key_id));
+        assert_eq!(keyed.kind, "api_key");
+        assert_eq!(keyed.policy.burst, 100);
+    }
+
+    #[tokio::test]
+    async fn exhausted_clients_are_rejected_before_authentication() {
+        use axum::{Router, body::Body, routing::get};
+        use tower::ServiceExt;
+        use vym_fyi_model::services::client_ip::ClientIp;
+
+        let policy = RateLimitPolicy {
+            burst: 1,
+            per_second: 1.0,
+        };
+        let limiter = Arc::new(RateLimiter::default());
+        let router = Router::new()
+            .route("/api/links", get(|| async { "ok" }))
+            .layer(axum::middleware::from_fn_with_state(
+                PreAuthRateLimit {
+                    limiter: limiter.clone(),
+                    policy: Some(policy),
+                },
+                reject_exhausted_clients,
+            ));
+        let request = |ip: &str| {
+            let mut req = Request::get("/api/links").body(Body::empty()).unwrap();
+            req.extensions_mut().insert(ClientIp(ip.parse().unwrap()));
+            req
+        };
+
+        // Peeking never takes a token, so a fresh client keeps passing.
+        for _ in 0..3 {
+            let ok = router
+                .clone()
+                .oneshot(request("2001:db8::1"))
+                .await
+                .unwrap();
+            assert_eq!(ok.status(), StatusCode::OK);
+        }
+
+        // Failed attempts are charged to the client's /64 by the rate-limit layer.
+        limiter.check("ip:2001:db8::", &policy, Instant::now());
+        let limited = router
+            .clone()
+            .oneshot(request("2001:db8::ffff"))
+            .await
+            .unwrap();
+        assert_eq!(limited.status(), StatusCode::TOO_MANY_REQUESTS);
+
+        let other = router.oneshot(request("203.0.113.7")).await.unwrap();
+        assert_eq!(other.status(), StatusCode::OK);
+    }
+}
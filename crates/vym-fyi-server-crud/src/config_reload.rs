@@ -0,0 +1,145 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use metrics::{counter, gauge};
+use tokio::time::{Interval, MissedTickBehavior, interval_at};
+use tracing::{error, info, warn};
+use vym_fyi_model::models::errors::{AppError, AppResult};
+
+use crate::app::CrudApp;
+
+/// Default interval for checking the tenants config file for changes.
+const DEFAULT_POLL_SECS: u64 = 10;
+
+/// What triggered a reload; used as a metrics label.
+#[derive(Debug, Clone, Copy)]
+enum ReloadTrigger {
+    FileChange,
+    Signal,
+}
+
+impl ReloadTrigger {
+    fn as_str(self) -> &'static str {
+        match self {
+            ReloadTrigger::FileChange => "file_change",
+            ReloadTrigger::Signal => "sighup",
+        }
+    }
+}
+
+/// Read `TENANTS_CONFIG_POLL_SECS`; `0` disables watching the file (SIGHUP
+/// still triggers a reload).
+fn poll_interval_from_env() -> AppResult<Option<Duration>> {
+    let secs = match std::env::var("TENANTS_CONFIG_POLL_SECS") {
+        Ok(raw) => raw.trim().parse::<u64>().map_err(|e| {
+            AppError::Config(format!("invalid TENANTS_CONFIG_POLL_SECS {}: {}", raw, e))
+        })?,
+        Err(_) => DEFAULT_POLL_SECS,
+    };
+    Ok((secs > 0).then(|| Duration::from_secs(secs)))
+}
+
+/// Spawn a background task that reloads the tenants config whenever the file
+/// content changes or the process receives SIGHUP. Does nothing when no
+/// tenants config is configured.
+pub fn spawn_tenants_config_reloader(app: CrudApp) -> AppResult<()> {
+    let Some(source) = app.tenants_config().cloned() else {
+        return Ok(());
+    };
+
+    let poll = poll_interval_from_env()?;
+    let mut ticker = poll.map(|period| {
+        let mut ticker = interval_at(tokio::time::Instant::now() + period, period);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        ticker
+    });
+
+    #[cfg(unix)]
+    let mut sighup = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup())?;
+
+    info!(
+        "watching tenants config {} (poll={:?}, SIGHUP enabled)",
+        source.path.display(),
+        poll
+    );
+
+    tokio::spawn(async move {
+        let mut last_seen = tokio::fs::read(&source.path).await.ok();
+
+        loop {
+            #[cfg(unix)]
+            let trigger = tokio::select! {
+                _ = tick(&mut ticker) => ReloadTrigger::FileChange,
+                _ = sighup.recv() => ReloadTrigger::Signal,
+            };
+            #[cfg(not(unix))]
+            let trigger = {
+                tick(&mut ticker).await;
+                ReloadTrigger::FileChange
+            };
+
+            let current = tokio::fs::read(&source.path).await.ok();
+            if matches!(trigger, ReloadTrigger::FileChange) && current == last_seen {
+                continue;
+            }
+            // Remember the content even if the reload fails, so a broken file
+            // is reported once rather than on every poll.
+            last_seen = current;
+
+            reload(&app, trigger).await;
+        }
+    });
+
+    Ok(())
+}
+
+async fn tick(ticker: &mut Option<Interval>) {
+    match ticker {
+        Some(ticker) => {
+            ticker.tick().await;
+        }
+        None => std::future::pending::<()>().await,
+    }
+}
+
+async fn reload(app: &CrudApp, trigger: ReloadTrigger) {
+    info!("reloading tenants config (trigger={})", trigger.as_str());
+
+    match app.reload_tenants_config().await {
+        Ok(()) => {
+            info!("tenants config reloaded");
+            counter!(
+                "tenants_config_reloads_total",
+                "result" => "success",
+                "trigger" => trigger.as_str()
+            )
+            .increment(1);
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs_f64())
+                .unwrap_or_default();
+            gauge!("tenants_config_last_reload_success_timestamp_seconds").set(now);
+        }
+        Err(
+            e @ (AppError::Config(_)
+            | AppError::MissingEnvVar { .. }
+            | AppError::YamlError(_)
+            | AppError::Io(_)),
+        ) => {
+            warn!("rejected tenants config, keeping previous one: {}", e);
+            record_failure(trigger);
+        }
+        Err(e) => {
+            error!("tenants config reload failed, keeping previous one: {}", e);
+            record_failure(trigger);
+        }
+    }
+}
+
+fn record_failure(trigger: ReloadTrigger) {
+    counter!(
+        "tenants_config_reloads_total",
+        "result" => "failure",
+        "trigger" => trigger.as_str()
+    )
+    .increment(1);
+}
@@ -0,0 +1,249 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, warn};
+use uuid::Uuid;
+use vym_fyi_model::models::url_shortener::{Role, Scope};
+use vym_fyi_model::services::api_keys::{generate_api_key, hash_api_key};
+use vym_fyi_model::services::repos::ApiKeyRecord;
+
+use crate::app::{CONFIG_KEY_NAME, CrudApp};
+use crate::auth::ApiKeyAuth;
+
+#[derive(Deserialize)]
+pub struct CreateApiKeyRequest {
+    /// Human-readable name for the key (e.g. "ci-deploy").
+    pub name: String,
+    /// Role for the new key. Defaults to `url`.
+    pub role: Option<Role>,
+}
+
+/// Key metadata; never includes the secret or its hash.
+#[derive(Serialize)]
+pub struct ApiKeyResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub role: Role,
+    pub created_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl From<ApiKeyRecord> for ApiKeyResponse {
+    fn from(record: ApiKeyRecord) -> Self {
+        Self {
+            id: record.id,
+            name: record.name,
+            role: record.role,
+            created_at: record.created_at,
+            revoked_at: record.revoked_at,
+        }
+    }
+}
+
+/// Response to key creation: the metadata plus the secret, shown only once.
+#[derive(Serialize)]
+pub struct CreatedApiKeyResponse {
+    #[serde(flatten)]
+    pub key: ApiKeyResponse,
+    pub api_key: String,
+}
+
+/// Decide whether the caller may administer the tenant found for the path.
+/// Tenant admins are limited to their own tenant; for them, unknown and
+/// foreign tenants are indistinguishable.
+fn authorize_tenant(auth: &ApiKeyAuth, found: Option<Uuid>) -> Result<Uuid, StatusCode> {
+    auth.require(Scope::TenantAdmin)?;
+    match found {
+        Some(id) if auth.is_master || auth.tenant_id == Some(id) => Ok(id),
+        None if auth.is_master => Err(StatusCode::NOT_FOUND),
+        _ => {
+            warn!(
+                "Tenant admin for {:?} attempted to manage another tenant",
+                auth.tenant_id
+            );
+            Err(StatusCode::FORBIDDEN)
+        }
+    }
+}
+
+async fn admin_tenant_id(
+    app: &CrudApp,
+    auth: &ApiKeyAuth,
+    tenant: &str,
+) -> Result<Uuid, StatusCode> {
+    auth.require(Scope::TenantAdmin)?;
+    let found = app
+        .tenant_repository()
+        .find_by_name(tenant)
+        .await
+        .map_err(|e| {
+            error!("Database error looking up tenant {}: {}", tenant, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    authorize_tenant(auth, found)
+}
+
+/// Mint a new API key for a tenant. The secret is only returned here.
+pub async fn create_api_key(
+    State(app): State<CrudApp>,
+    auth: ApiKeyAuth,
+    Path(tenant): Path<String>,
+    Json(req): Json<CreateApiKeyRequest>,
+) -> Result<(StatusCode, Json<CreatedApiKeyResponse>), StatusCode> {
+    let name = req.name.trim();
+    if name.is_empty() || name == CONFIG_KEY_NAME {
+        warn!("Rejected API key name '{}'", name);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let tenant_id = admin_tenant_id(&app, &auth, &tenant).await?;
+    let role = req.role.unwrap_or(Role::Url);
+    let secret = generate_api_key();
+    let key_hash = hash_api_key(&secret).map_err(|e| {
+        error!("Failed to hash new API key: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let key_lookup = app.api_key_lookup().digest(&secret);
+    let record = app
+        .api_key_repository()
+        .create(tenant_id, name, &key_lookup, &key_hash, &role)
+        .await
+        .map_err(|e| {
+            error!("Database error creating API key for {}: {}", tenant, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    info!(
+        "Created API key id={} name={} role={} tenant={}",
+        record.id,
+        record.name,
+        record.role.as_str(),
+        tenant
+    );
+
+    Ok((
+        StatusCode::CREATED,
+        Json(CreatedApiKeyResponse {
+            key: record.into(),
+            api_key: secret,
+        }),
+    ))
+}
+
+/// List key metadata for a tenant, including revoked keys.
+pub async fn list_api_keys(
+    State(app): State<CrudApp>,
+    auth: ApiKeyAuth,
+    Path(tenant): Path<String>,
+) -> Result<Json<Vec<ApiKeyResponse>>, StatusCode> {
+    let tenant_id = admin_tenant_id(&app, &auth, &tenant).await?;
+
+    let keys = app
+        .api_key_repository()
+        .list_by_tenant(tenant_id)
+        .await
+        .map_err(|e| {
+            error!("Database error listing API keys for {}: {}", tenant, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(keys.into_iter().map(ApiKeyResponse::from).collect()))
+}
+
+/// Revoke a tenant's key. Takes effect on the next request using it.
+pub async fn revoke_api_key(
+    State(app): State<CrudApp>,
+    auth: ApiKeyAuth,
+    Path((tenant, key_id)): Path<(String, Uuid)>,
+) -> Result<StatusCode, StatusCode> {
+    let tenant_id = admin_tenant_id(&app, &auth, &tenant).await?;
+
+    let revoked = app
+        .api_key_repository()
+        .revoke(tenant_id, key_id)
+        .await
+        .map_err(|e| {
+            error!("Database error revoking API key {}: {}", key_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    if revoked {
+        info!("Revoked API key id={} tenant={}", key_id, tenant);
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(StatusCode::NOT_FOUND)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::TenantSettings;
+
+    fn auth(tenant_id: Option<Uuid>, role: Option<Role>) -> ApiKeyAuth {
+        ApiKeyAuth {
+            tenant_id,
+            is_master: role.is_none(),
+            scopes: role.map_or(Scope::ALL.to_vec(), |r| r.scopes().to_vec()),
+            settings: TenantSettings::default(),
+        }
+    }
+
+    #[test]
+    fn master_may_administer_any_tenant() {
+        let tenant = Uuid::new_v4();
+        let master = auth(None, None);
+
+        assert_eq!(authorize_tenant(&master, Some(tenant)), Ok(tenant));
+        assert_eq!(authorize_tenant(&master, None), Err(StatusCode::NOT_FOUND));
+    }
+
+    #[test]
+    fn tenant_admin_is_limited_to_own_tenant() {
+        let own = Uuid::new_v4();
+        let admin = auth(Some(own), Some(Role::Admin));
+
+        assert_eq!(authorize_tenant(&admin, Some(own)), Ok(own));
+        assert_eq!(
+            authorize_tenant(&admin, Some(Uuid::new_v4())),
+            Err(StatusCode::FORBIDDEN)
+        );
+        assert_eq!(authorize_tenant(&admin, None), Err(StatusCode::FORBIDDEN));
+    }
+
+    #[test]
+    fn non_admin_roles_are_rejected() {
+        let own = Uuid::new_v4();
+        let url = auth(Some(own), Some(Role::Url));
+
+        assert_eq!(
+            authorize_tenant(&url, Some(own)),
+            Err(StatusCode::FORBIDDEN)
+        );
+    }
+
+    #[test]
+    fn created_response_flattens_metadata_and_secret() {
+        let response = CreatedApiKeyResponse {
+            key: ApiKeyResponse {
+                id: Uuid::nil(),
+                name: "ci".into(),
+                role: Role::Url,
+                created_at: Utc::now(),
+                revoked_at: None,
+            },
+            api_key: "vfyi_secret".into(),
+        };
+
+        let json = serde_json::to_value(&response).unwrap();
+        assert_eq!(json["name"], "ci");
+        assert_eq!(json["role"], "url");
+        assert_eq!(json["api_key"], "vfyi_secret");
+        assert!(json.get("key_hash").is_none());
+    }
+}
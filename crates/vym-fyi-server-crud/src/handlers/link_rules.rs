@@ -0,0 +1,224 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+use vym_fyi_model::models::url_shortener::Scope;
+use vym_fyi_model::services::link_rules::{
+    LinkRule, LinkRuleError, MAX_RULES_PER_LINK, RuleConditions,
+};
+
+use crate::app::CrudApp;
+use crate::auth::ApiKeyAuth;
+use crate::handlers::links::{LinkError, normalize_target_url, tenant_scope};
+use crate::models::ValidationError;
+
+/// A conditional redirect rule: visits matching every condition go to
+/// `target_url`.
+#[derive(Deserialize)]
+pub struct LinkRuleRequest {
+    #[serde(flatten)]
+    pub conditions: RuleConditions,
+    pub target_url: String,
+}
+
+#[derive(Serialize)]
+pub struct LinkRuleResponse {
+    pub id: Uuid,
+    #[serde(flatten)]
+    pub conditions: RuleConditions,
+    pub target_url: String,
+}
+
+impl From<LinkRule> for LinkRuleResponse {
+    fn from(rule: LinkRule) -> Self {
+        Self {
+            id: rule.id,
+            conditions: rule.conditions,
+            target_url: rule.target_url,
+        }
+    }
+}
+
+fn invalid_rule(e: LinkRuleError) -> LinkError {
+    warn!("Rejecting link rule: {}", e);
+    LinkError::Invalid(ValidationError::new("rules", e.code(), e.to_string()))
+}
+
+/// Check a rule's conditions and target the same way link targets are.
+fn validate_rule(
+    app: &CrudApp,
+    auth: &ApiKeyAuth,
+    req: LinkRuleRequest,
+) -> Result<LinkRule, LinkError> {
+    let conditions = req.conditions.normalized().map_err(invalid_rule)?;
+    let target_url =
+        normalize_target_url(app.target_url_policy(), &auth.settings, &req.target_url)?;
+    Ok(LinkRule::new(conditions, target_url))
+}
+
+/// `404` unless the link exists and is visible to the caller.
+pub(crate) async fn require_link(
+    app: &CrudApp,
+    auth: &ApiKeyAuth,
+    slug: &str,
+) -> Result<(), StatusCode> {
+    let tenant_id = tenant_scope(auth)?;
+    app.short_link_repository()
+        .get(slug, tenant_id)
+        .await
+        .map_err(|e| {
+            error!("Database error fetching short link {}: {}", slug, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .map(|_| ())
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+async fn list_rules(app: &CrudApp, slug: &str) -> Result<Vec<LinkRule>, StatusCode> {
+    app.link_rule_repository().list(slug).await.map_err(|e| {
+        error!("Database error listing rules of {}: {}", slug, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+/// List a link's rules in evaluation order.
+pub async fn list_link_rules(
+    State(app): State<CrudApp>,
+    auth: ApiKeyAuth,
+    Path(slug): Path<String>,
+) -> Result<Json<Vec<LinkRuleResponse>>, StatusCode> {
+    auth.require(Scope::LinksRead)?;
+    require_link(&app, &auth, &slug).await?;
+    debug!("List rules of slug={}", slug);
+
+    let rules = list_rules(&app, &slug).await?;
+    Ok(Json(
+        rules.into_iter().map(LinkRuleResponse::from).collect(),
+    ))
+}
+
+/// Replace all rules of a link; the order of the body is the evaluation
+/// order. An empty list removes every rule.
+pub async fn replace_link_rules(
+    State(app): State<CrudApp>,
+    auth: ApiKeyAuth,
+    Path(slug): Path<String>,
+    Json(req): Json<Vec<LinkRuleRequest>>,
+) -> Result<Json<Vec<LinkRuleResponse>>, LinkError> {
+    auth.require(Scope::LinksWrite)?;
+    require_link(&app, &auth, &slug).await?;
+    if req.len() > MAX_RULES_PER_LINK {
+        return Err(invalid_rule(LinkRuleError::TooMany {
+            max: MAX_RULES_PER_LINK,
+        }));
+    }
+    let rules = req
+        .into_iter()
+        .map(|rule| validate_rule(&app, &auth, rule))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    info!(
+        "Replace rules of slug={} with {} rule(s)",
+        slug,
+        rules.len()
+    );
+    app.link_rule_repository()
+        .replace(&slug, &rules)
+        .await
+        .map_err(|e| {
+            error!("Database error replacing rules of {}: {}", slug, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(
+        rules.into_iter().map(LinkRuleResponse::from).collect(),
+    ))
+}
+
+/// Add a rule after the link's existing ones.
+pub async fn create_link_rule(
+    State(app): State<CrudApp>,
+    auth: ApiKeyAuth,
+    Path(slug): Path<String>,
+    Json(req): Json<LinkRuleRequest>,
+) -> Result<(StatusCode, Json<LinkRuleResponse>), LinkError> {
+    auth.require(Scope::LinksWrite)?;
+    require_link(&app, &auth, &slug).await?;
+    let rule = validate_rule(&app, &auth, req)?;
+    if list_rules(&app, &slug).await?.len() >= MAX_RULES_PER_LINK {
+        return Err(invalid_rule(LinkRuleError::TooMany {
+            max: MAX_RULES_PER_LINK,
+        }));
+    }
+
+    info!(
+        "Add rule id={} to slug={} target_url={}",
+        rule.id, slug, rule.target_url
+    );
+    app.link_rule_repository()
+        .append(&slug, &rule)
+        .await
+        .map_err(|e| {
+            error!("Database error adding rule to {}: {}", slug, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok((StatusCode::CREATED, Json(LinkRuleResponse::from(rule))))
+}
+
+/// Remove a single rule.
+pub async fn delete_link_rule(
+    State(app): State<CrudApp>,
+    auth: ApiKeyAuth,
+    Path((slug, rule_id)): Path<(String, Uuid)>,
+) -> Result<StatusCode, StatusCode> {
+    auth.require(Scope::LinksWrite)?;
+    require_link(&app, &auth, &slug).await?;
+
+    let deleted = app
+        .link_rule_repository()
+        .delete(&slug, rule_id)
+        .await
+        .map_err(|e| {
+            error!(
+                "Database error deleting rule {} of {}: {}",
+                rule_id, slug, e
+            );
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    if deleted {
+        info!("Deleted rule id={} of slug={}", rule_id, slug);
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(StatusCode::NOT_FOUND)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vym_fyi_model::models::url_shortener::DeviceClass;
+
+    #[test]
+    fn rule_requests_flatten_conditions() {
+        let req: LinkRuleRequest = serde_json::from_str(
+            r#"{"devices": ["ios"], "not_before": "2030-01-01T00:00:00Z",
+                "target_url": "https://apps.apple.com/app/id1"}"#,
+        )
+        .unwrap();
+        assert_eq!(req.conditions.devices, vec![DeviceClass::Ios]);
+        assert!(req.conditions.languages.is_empty());
+        assert!(req.conditions.not_before.is_some());
+
+        let rule = LinkRule::new(req.conditions, req.target_url);
+        let json = serde_json::to_value(LinkRuleResponse::from(rule.clone())).unwrap();
+        assert_eq!(json["id"], rule.id.to_string());
+        assert_eq!(json["devices"], serde_json::json!(["ios"]));
+        assert_eq!(json["not_after"], serde_json::Value::Null);
+    }
+}
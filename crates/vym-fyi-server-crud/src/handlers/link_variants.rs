@@ -0,0 +1,162 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, error, info, warn};
+use vym_fyi_model::models::url_shortener::Scope;
+use vym_fyi_model::services::link_variants::{LinkVariant, LinkVariantError, LinkVariants};
+
+use crate::app::CrudApp;
+use crate::auth::ApiKeyAuth;
+use crate::handlers::link_rules::require_link;
+use crate::handlers::links::{LinkError, normalize_target_url};
+use crate::models::ValidationError;
+
+/// One target of an A/B split.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct LinkVariantBody {
+    pub name: String,
+    pub target_url: String,
+    pub weight: u32,
+}
+
+/// A link's A/B split, as read and written by the variants endpoints. An
+/// empty `variants` list turns the split off.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct LinkVariantsBody {
+    /// Keep returning visitors on their first variant (via a cookie).
+    #[serde(default)]
+    pub sticky: bool,
+    pub variants: Vec<LinkVariantBody>,
+}
+
+impl From<LinkVariants> for LinkVariantsBody {
+    fn from(split: LinkVariants) -> Self {
+        Self {
+            sticky: split.sticky,
+            variants: split
+                .variants
+                .into_iter()
+                .map(|v| LinkVariantBody {
+                    name: v.name,
+                    target_url: v.target_url,
+                    weight: v.weight,
+                })
+                .collect(),
+        }
+    }
+}
+
+fn invalid_variants(e: LinkVariantError) -> LinkError {
+    warn!("Rejecting link variants: {}", e);
+    LinkError::Invalid(ValidationError::new("variants", e.code(), e.to_string()))
+}
+
+/// Check the split and normalize every target the same way link targets are.
+fn validate_variants(
+    app: &CrudApp,
+    auth: &ApiKeyAuth,
+    req: LinkVariantsBody,
+) -> Result<LinkVariants, LinkError> {
+    let split = LinkVariants {
+        sticky: req.sticky,
+        variants: req
+            .variants
+            .into_iter()
+            .map(|v| {
+                Ok(LinkVariant {
+                    target_url: normalize_target_url(
+                        app.target_url_policy(),
+                        &auth.settings,
+                        &v.target_url,
+                    )?,
+                    name: v.name,
+                    weight: v.weight,
+                })
+            })
+            .collect::<Result<_, LinkError>>()?,
+    };
+    split.validate().map_err(invalid_variants)?;
+    Ok(split)
+}
+
+/// Show a link's A/B split.
+pub async fn get_link_variants(
+    State(app): State<CrudApp>,
+    auth: ApiKeyAuth,
+    Path(slug): Path<String>,
+) -> Result<Json<LinkVariantsBody>, StatusCode> {
+    auth.require(Scope::LinksRead)?;
+    require_link(&app, &auth, &slug).await?;
+    debug!("Get variants of slug={}", slug);
+
+    let split = app
+        .link_variant_repository()
+        .get(&slug)
+        .await
+        .map_err(|e| {
+            error!("Database error fetching variants of {}: {}", slug, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    Ok(Json(split.into()))
+}
+
+/// Replace a link's A/B split.
+pub async fn replace_link_variants(
+    State(app): State<CrudApp>,
+    auth: ApiKeyAuth,
+    Path(slug): Path<String>,
+    Json(req): Json<LinkVariantsBody>,
+) -> Result<Json<LinkVariantsBody>, LinkError> {
+    auth.require(Scope::LinksWrite)?;
+    require_link(&app, &auth, &slug).await?;
+    let split = validate_variants(&app, &auth, req)?;
+
+    info!(
+        "Replace variants of slug={} with {} variant(s) sticky={}",
+        slug,
+        split.variants.len(),
+        split.sticky
+    );
+    app.link_variant_repository()
+        .replace(&slug, &split)
+        .await
+        .map_err(|e| {
+            error!("Database error replacing variants of {}: {}", slug, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(split.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn variants_body_defaults_to_not_sticky() {
+        let req: LinkVariantsBody = serde_json::from_str(
+            r#"{"variants": [
+                {"name": "a", "target_url": "https://example.com/a", "weight": 70},
+                {"name": "b", "target_url": "https://example.com/b", "weight": 30}
+            ]}"#,
+        )
+        .unwrap();
+        assert!(!req.sticky);
+        assert_eq!(req.variants[1].weight, 30);
+
+        let json = serde_json::to_value(LinkVariantsBody::from(LinkVariants {
+            sticky: true,
+            variants: vec![LinkVariant {
+                name: "a".into(),
+                target_url: "https://example.com/a".into(),
+                weight: 1,
+            }],
+        }))
+        .unwrap();
+        assert_eq!(json["sticky"], true);
+        assert_eq!(json["variants"][0]["name"], "a");
+    }
+}
@@ -1,26 +1,57 @@
 use async_trait::async_trait;
 use axum::{
     Json,
-    extract::{Query, State},
-    http::StatusCode,
+    extract::{Path, Query, State},
+    http::{
+        HeaderMap, HeaderValue, StatusCode,
+        header::{ETAG, IF_MATCH, IF_NONE_MATCH},
+    },
+    response::{IntoResponse, Response},
 };
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::QueryBuilder;
-use sqlx::Row;
 use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 use vym_fyi_model::models::errors::AppError;
-use vym_fyi_model::services::repos::ShortLinkRepository;
+use vym_fyi_model::models::url_shortener::{QueryPassthrough, RedirectType, Scope, SlugStyle};
+use vym_fyi_model::services::param_template::ParamTemplate;
+use vym_fyi_model::services::repos::{
+    LinkFields, LinkUpdate, ShortLinkRecord, ShortLinkRepository,
+};
+use vym_fyi_model::services::slug::SlugGenerator;
+use vym_fyi_model::services::target_url::TargetUrlPolicy;
 
-use crate::app::CrudApp;
+use crate::app::{CrudApp, MAX_GENERATED_SLUG_LEN, TenantSettings};
 use crate::auth::ApiKeyAuth;
+use crate::models::ValidationError;
 
 #[derive(Deserialize)]
 pub struct CreateLinkRequest {
     /// Optional slug. If omitted or empty, the server will generate a random slug.
     pub slug: Option<String>,
     pub target_url: String,
+    /// Optional absolute expiry (RFC3339).
+    pub expires_at: Option<String>,
+    /// Optional relative expiry such as "30m", "12h", "7d" or "2w".
+    /// Mutually exclusive with `expires_at`.
+    pub ttl: Option<String>,
+    /// Style of the generated slug; defaults to the tenant's style.
+    pub slug_style: Option<SlugStyle>,
+    /// Minimum length of the generated slug; may not be below the tenant's
+    /// `min_slug_len`.
+    pub slug_length: Option<usize>,
+    /// Redirect status (301, 302, 307 or 308); defaults to 307.
+    pub redirect_type: Option<RedirectType>,
+    /// Merge the redirect request's query string into the target; `off` by
+    /// default.
+    pub query_passthrough: Option<QueryPassthrough>,
+    /// Redirect `/{slug}/{rest}` with `rest` appended to the target path.
+    pub path_passthrough: Option<bool>,
+    /// Query parameters added to the target on redirect, e.g.
+    /// `utm_campaign={{slug}}`; overrides same-named parameters of the
+    /// tenant's template.
+    pub param_template: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -28,18 +59,77 @@ pub struct LinkResponse {
     pub slug: String,
     pub target_url: String,
     pub active: bool,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub redirect_type: RedirectType,
+    pub query_passthrough: QueryPassthrough,
+    pub path_passthrough: bool,
+    pub param_template: Option<String>,
+    /// Where the link redirects, with the tenant's and the link's parameter
+    /// templates applied.
+    pub resolved_url: String,
 }
 
-type LinkCreationResult = vym_fyi_model::models::errors::AppResult<(String, String, bool)>;
+impl From<ShortLinkRecord> for LinkResponse {
+    fn from(record: ShortLinkRecord) -> Self {
+        Self {
+            slug: record.slug,
+            target_url: record.target_url,
+            active: record.is_active,
+            expires_at: record.expires_at,
+            redirect_type: record.redirect_type,
+            query_passthrough: record.query_passthrough,
+            path_passthrough: record.path_passthrough,
+            param_template: record.param_template,
+            resolved_url: record.resolved_url,
+        }
+    }
+}
+
+/// A link response carrying the link's `ETag`, to be sent back in
+/// `If-Match` on updates.
+pub struct TaggedLink(pub ShortLinkRecord);
+
+impl IntoResponse for TaggedLink {
+    fn into_response(self) -> Response {
+        let etag = HeaderValue::from_str(&self.0.etag()).expect("ETag is ASCII");
+        ([(ETAG, etag)], Json(LinkResponse::from(self.0))).into_response()
+    }
+}
+
+/// Error of link handlers that report invalid fields in the body.
+pub enum LinkError {
+    Status(StatusCode),
+    Invalid(ValidationError),
+}
+
+impl From<StatusCode> for LinkError {
+    fn from(status: StatusCode) -> Self {
+        LinkError::Status(status)
+    }
+}
+
+impl IntoResponse for LinkError {
+    fn into_response(self) -> Response {
+        match self {
+            LinkError::Status(status) => status.into_response(),
+            LinkError::Invalid(body) => {
+                (StatusCode::UNPROCESSABLE_ENTITY, Json(body)).into_response()
+            }
+        }
+    }
+}
+
+type LinkCreationResult = vym_fyi_model::models::errors::AppResult<ShortLinkRecord>;
 
 /// Contract for link persistence used by the creation strategies.
 #[async_trait]
 trait LinkRepository: Send + Sync {
-    async fn upsert(&self, slug: &str, target_url: &str, tenant_id: Uuid) -> LinkCreationResult;
+    async fn create(&self, slug: &str, fields: &LinkFields, tenant_id: Uuid) -> LinkCreationResult;
 
     async fn create_with_generated_slug(
         &self,
-        target_url: &str,
+        fields: &LinkFields,
+        generator: &dyn SlugGenerator,
         min_len: usize,
         tenant_id: Uuid,
     ) -> LinkCreationResult;
@@ -47,17 +137,19 @@ trait LinkRepository: Send + Sync {
 
 #[async_trait]
 impl LinkRepository for ShortLinkRepository {
-    async fn upsert(&self, slug: &str, target_url: &str, tenant_id: Uuid) -> LinkCreationResult {
-        ShortLinkRepository::upsert(self, slug, target_url, tenant_id).await
+    async fn create(&self, slug: &str, fields: &LinkFields, tenant_id: Uuid) -> LinkCreationResult {
+        ShortLinkRepository::create(self, slug, fields, tenant_id).await
     }
 
     async fn create_with_generated_slug(
         &self,
-        target_url: &str,
+        fields: &LinkFields,
+        generator: &dyn SlugGenerator,
         min_len: usize,
         tenant_id: Uuid,
     ) -> LinkCreationResult {
-        ShortLinkRepository::create_with_generated_slug(self, target_url, min_len, tenant_id).await
+        ShortLinkRepository::create_with_generated_slug(self, fields, generator, min_len, tenant_id)
+            .await
     }
 }
 
@@ -68,7 +160,7 @@ trait LinkCreationStrategy: Send + Sync {
     async fn create(
         &self,
         repo: &(dyn LinkRepository + Send + Sync),
-        target_url: &str,
+        fields: &LinkFields,
         tenant_id: Uuid,
     ) -> LinkCreationResult;
 }
@@ -92,25 +184,26 @@ impl LinkCreationStrategy for ProvidedSlugStrategy {
     async fn create(
         &self,
         repo: &(dyn LinkRepository + Send + Sync),
-        target_url: &str,
+        fields: &LinkFields,
         tenant_id: Uuid,
     ) -> LinkCreationResult {
-        repo.upsert(&self.slug, target_url, tenant_id).await
+        repo.create(&self.slug, fields, tenant_id).await
     }
 }
 
-struct GeneratedSlugStrategy {
+struct GeneratedSlugStrategy<'a> {
+    generator: &'a dyn SlugGenerator,
     min_len: usize,
 }
 
-impl GeneratedSlugStrategy {
-    fn new(min_len: usize) -> Self {
-        Self { min_len }
+impl<'a> GeneratedSlugStrategy<'a> {
+    fn new(generator: &'a dyn SlugGenerator, min_len: usize) -> Self {
+        Self { generator, min_len }
     }
 }
 
 #[async_trait]
-impl LinkCreationStrategy for GeneratedSlugStrategy {
+impl LinkCreationStrategy for GeneratedSlugStrategy<'_> {
     fn label(&self) -> &'static str {
         "generated_slug"
     }
@@ -118,14 +211,235 @@ impl LinkCreationStrategy for GeneratedSlugStrategy {
     async fn create(
         &self,
         repo: &(dyn LinkRepository + Send + Sync),
-        target_url: &str,
+        fields: &LinkFields,
         tenant_id: Uuid,
     ) -> LinkCreationResult {
-        repo.create_with_generated_slug(target_url, self.min_len, tenant_id)
+        repo.create_with_generated_slug(fields, self.generator, self.min_len, tenant_id)
             .await
     }
 }
 
+/// Partial update for a single link. Omitted fields are left untouched;
+/// `expires_at: null` clears the expiry and `param_template: null` the
+/// template.
+#[derive(Deserialize)]
+pub struct UpdateLinkRequest {
+    pub target_url: Option<String>,
+    pub is_active: Option<bool>,
+    #[serde(default, deserialize_with = "deserialize_present")]
+    pub expires_at: Option<Option<String>>,
+    pub redirect_type: Option<RedirectType>,
+    pub query_passthrough: Option<QueryPassthrough>,
+    pub path_passthrough: Option<bool>,
+    #[serde(default, deserialize_with = "deserialize_present")]
+    pub param_template: Option<Option<String>>,
+}
+
+/// Full replacement of a link via `PUT /api/links/{slug}`. Omitted optional
+/// fields are reset: no expiry, active, `307` redirect, no passthrough, no
+/// parameter template.
+#[derive(Deserialize)]
+pub struct ReplaceLinkRequest {
+    pub target_url: String,
+    /// Optional absolute expiry (RFC3339).
+    pub expires_at: Option<String>,
+    /// Optional relative expiry; mutually exclusive with `expires_at`.
+    pub ttl: Option<String>,
+    pub is_active: Option<bool>,
+    pub redirect_type: Option<RedirectType>,
+    pub query_passthrough: Option<QueryPassthrough>,
+    pub path_passthrough: Option<bool>,
+    pub param_template: Option<String>,
+}
+
+/// Conditional request headers of a write.
+#[derive(Debug, PartialEq)]
+enum Precondition {
+    None,
+    /// `If-Match: *`: the link must exist.
+    Exists,
+    /// `If-Match` with entity tags: the link's version must be one of them.
+    Versions(Vec<i64>),
+    /// `If-None-Match: *`: the link must not exist.
+    Absent,
+}
+
+impl Precondition {
+    fn from_headers(headers: &HeaderMap) -> Self {
+        let values = |name| {
+            headers
+                .get_all(name)
+                .iter()
+                .filter_map(|v| v.to_str().ok())
+                .flat_map(|v| v.split(','))
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .collect::<Vec<_>>()
+        };
+
+        let if_match = values(IF_MATCH);
+        if if_match.contains(&"*") {
+            return Precondition::Exists;
+        }
+        if !if_match.is_empty() {
+            // If-Match uses strong comparison, so weak tags never match.
+            return Precondition::Versions(
+                if_match
+                    .iter()
+                    .filter_map(|tag| tag.strip_prefix('"')?.strip_suffix('"')?.parse().ok())
+                    .collect(),
+            );
+        }
+        if values(IF_NONE_MATCH).contains(&"*") {
+            return Precondition::Absent;
+        }
+        Precondition::None
+    }
+
+    /// Versions an update of an existing link must match; `Some(empty)`
+    /// when it must not happen at all.
+    fn expected_versions(self) -> Option<Vec<i64>> {
+        match self {
+            Precondition::None | Precondition::Exists => None,
+            Precondition::Versions(versions) => Some(versions),
+            Precondition::Absent => Some(Vec::new()),
+        }
+    }
+}
+
+/// Distinguish an explicit `null` (`Some(None)`) from a missing field (`None`).
+fn deserialize_present<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+pub(crate) fn parse_rfc3339_opt(
+    label: &str,
+    value: &Option<String>,
+) -> Result<Option<DateTime<Utc>>, StatusCode> {
+    match value.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+        None => Ok(None),
+        Some(raw) => DateTime::parse_from_rfc3339(raw)
+            .map(|dt| dt.with_timezone(&Utc))
+            .map(Some)
+            .map_err(|e| {
+                error!("Invalid {} timestamp '{}': {}", label, raw, e);
+                StatusCode::BAD_REQUEST
+            }),
+    }
+}
+
+/// Parse a relative TTL such as "90s", "30m", "12h", "7d" or "2w".
+fn parse_ttl(raw: &str) -> Option<Duration> {
+    let raw = raw.trim();
+    let (split, _) = raw.char_indices().last()?;
+    let (amount, unit) = raw.split_at(split);
+    let amount: i64 = amount.parse().ok().filter(|n| *n > 0)?;
+    match unit {
+        "s" => Duration::try_seconds(amount),
+        "m" => Duration::try_minutes(amount),
+        "h" => Duration::try_hours(amount),
+        "d" => Duration::try_days(amount),
+        "w" => Duration::try_weeks(amount),
+        _ => None,
+    }
+}
+
+/// Compute the expiry for a new link from either an absolute `expires_at`
+/// or a relative `ttl`. Supplying both, or an expiry in the past, is rejected.
+fn resolve_expiry(
+    expires_at: &Option<String>,
+    ttl: &Option<String>,
+    now: DateTime<Utc>,
+) -> Result<Option<DateTime<Utc>>, StatusCode> {
+    let absolute = parse_rfc3339_opt("expires_at", expires_at)?;
+    let relative = match ttl.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+        None => None,
+        Some(raw) => {
+            let ttl = parse_ttl(raw).ok_or_else(|| {
+                error!("Invalid ttl '{}'", raw);
+                StatusCode::BAD_REQUEST
+            })?;
+            Some(now.checked_add_signed(ttl).ok_or(StatusCode::BAD_REQUEST)?)
+        }
+    };
+
+    match (absolute, relative) {
+        (Some(_), Some(_)) => {
+            error!("Both expires_at and ttl supplied");
+            Err(StatusCode::BAD_REQUEST)
+        }
+        (Some(dt), None) if dt <= now => {
+            error!("expires_at {} is in the past", dt);
+            Err(StatusCode::BAD_REQUEST)
+        }
+        (absolute, relative) => Ok(absolute.or(relative)),
+    }
+}
+
+/// Length of a generated slug: the requested `slug_length`, which may not
+/// go below the tenant's minimum, or the tenant's minimum itself.
+fn generated_slug_len(
+    requested: Option<usize>,
+    settings: &TenantSettings,
+) -> Result<usize, StatusCode> {
+    match requested {
+        None => Ok(settings.min_slug_len),
+        Some(len) if (settings.min_slug_len..=MAX_GENERATED_SLUG_LEN).contains(&len) => Ok(len),
+        Some(len) => {
+            warn!(
+                "Rejecting slug_length {} outside {}..={}",
+                len, settings.min_slug_len, MAX_GENERATED_SLUG_LEN
+            );
+            Err(StatusCode::BAD_REQUEST)
+        }
+    }
+}
+
+/// Normalize a target URL and check it against the server-wide policy and
+/// the tenant's blocklist.
+pub(crate) fn normalize_target_url(
+    policy: &TargetUrlPolicy,
+    settings: &TenantSettings,
+    raw: &str,
+) -> Result<String, LinkError> {
+    policy
+        .normalize(raw, &settings.target_blocklist)
+        .map_err(|e| {
+            warn!("Rejecting target_url {:?}: {}", raw, e);
+            LinkError::Invalid(ValidationError::new("target_url", e.code(), e.to_string()))
+        })
+}
+
+/// Check a link's parameter template; blank templates are stored as none.
+fn parse_param_template(raw: Option<&str>) -> Result<Option<String>, LinkError> {
+    let Some(raw) = raw.map(str::trim).filter(|t| !t.is_empty()) else {
+        return Ok(None);
+    };
+    ParamTemplate::parse(raw).map_err(|e| {
+        warn!("Rejecting param_template {:?}: {}", raw, e);
+        LinkError::Invalid(ValidationError::new(
+            "param_template",
+            e.code(),
+            e.to_string(),
+        ))
+    })?;
+    Ok(Some(raw.to_string()))
+}
+
+/// Tenant filter for single-link operations: `None` for the master key
+/// (all tenants), otherwise the caller's tenant.
+pub(crate) fn tenant_scope(auth: &ApiKeyAuth) -> Result<Option<Uuid>, StatusCode> {
+    if auth.is_master {
+        Ok(None)
+    } else {
+        auth.tenant_id.map(Some).ok_or(StatusCode::FORBIDDEN)
+    }
+}
+
 /// Query parameters for listing links.
 #[derive(Deserialize)]
 pub struct ListLinksQuery {
@@ -146,22 +460,7 @@ pub async fn list_links(
     auth: ApiKeyAuth,
     Query(query): Query<ListLinksQuery>,
 ) -> Result<Json<Vec<LinkResponse>>, StatusCode> {
-    fn parse_rfc3339_opt(
-        label: &str,
-        value: &Option<String>,
-    ) -> Result<Option<DateTime<Utc>>, StatusCode> {
-        match value.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
-            None => Ok(None),
-            Some(raw) => DateTime::parse_from_rfc3339(raw)
-                .map(|dt| dt.with_timezone(&Utc))
-                .map(Some)
-                .map_err(|e| {
-                    error!("Invalid {} timestamp '{}': {}", label, raw, e);
-                    StatusCode::BAD_REQUEST
-                }),
-        }
-    }
-
+    auth.require(Scope::LinksRead)?;
     let created_before = parse_rfc3339_opt("created_before", &query.created_before)?;
     let created_after = parse_rfc3339_opt("created_after", &query.created_after)?;
     let expires_before = parse_rfc3339_opt("expires_before", &query.expires_before)?;
@@ -177,9 +476,10 @@ pub async fn list_links(
         page, per_page, auth.is_master, auth.tenant_id
     );
 
-    let mut qb = QueryBuilder::<sqlx::Postgres>::new(
-        "SELECT slug, target_url, is_active FROM short_links WHERE ",
-    );
+    let mut qb = QueryBuilder::<sqlx::Postgres>::new(format!(
+        "SELECT {} FROM short_links WHERE ",
+        ShortLinkRecord::COLUMNS
+    ));
 
     if auth.is_master {
         qb.push("TRUE");
@@ -240,38 +540,290 @@ pub async fn list_links(
     })?;
 
     let links = rows
-        .into_iter()
-        .map(|row| LinkResponse {
-            slug: row.get("slug"),
-            target_url: row.get("target_url"),
-            active: row.get::<bool, _>("is_active"),
-        })
+        .iter()
+        .map(ShortLinkRecord::from_row)
+        .map(LinkResponse::from)
         .collect();
 
     Ok(Json(links))
 }
 
+/// Fetch a single short link by slug.
+pub async fn get_link(
+    State(app): State<CrudApp>,
+    auth: ApiKeyAuth,
+    Path(slug): Path<String>,
+) -> Result<TaggedLink, StatusCode> {
+    auth.require(Scope::LinksRead)?;
+    let tenant_id = tenant_scope(&auth)?;
+    debug!("Get link slug={} tenant_id={:?}", slug, tenant_id);
+
+    let record = app
+        .short_link_repository()
+        .get(&slug, tenant_id)
+        .await
+        .map_err(|e| {
+            error!("Database error fetching short link {}: {}", slug, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(TaggedLink(record))
+}
+
+/// Update the target, active flag and/or expiry of a short link. With
+/// `If-Match`, the update only applies to the given version (else `412`).
+pub async fn update_link(
+    State(app): State<CrudApp>,
+    auth: ApiKeyAuth,
+    Path(slug): Path<String>,
+    headers: HeaderMap,
+    Json(req): Json<UpdateLinkRequest>,
+) -> Result<TaggedLink, LinkError> {
+    auth.require(Scope::LinksWrite)?;
+    let tenant_id = tenant_scope(&auth)?;
+
+    let target_url = match req.target_url.as_deref().map(str::trim) {
+        Some("") => return Err(StatusCode::BAD_REQUEST.into()),
+        Some(raw) => Some(normalize_target_url(
+            app.target_url_policy(),
+            &auth.settings,
+            raw,
+        )?),
+        None => None,
+    };
+    let expires_at = match &req.expires_at {
+        None => None,
+        Some(raw) => Some(parse_rfc3339_opt("expires_at", raw)?),
+    };
+
+    let changes = LinkUpdate {
+        target_url,
+        is_active: req.is_active,
+        expires_at,
+        redirect_type: req.redirect_type,
+        query_passthrough: req.query_passthrough,
+        path_passthrough: req.path_passthrough,
+        param_template: req
+            .param_template
+            .as_ref()
+            .map(|t| parse_param_template(t.as_deref()))
+            .transpose()?,
+    };
+
+    info!(
+        "Update link slug={} tenant_id={:?} changes={:?}",
+        slug, tenant_id, changes
+    );
+
+    let expected = Precondition::from_headers(&headers).expected_versions();
+    let repo = app.short_link_repository();
+    let record = repo
+        .update(&slug, tenant_id, &changes, expected.as_deref())
+        .await
+        .map_err(|e| {
+            error!("Database error updating short link {}: {}", slug, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    match record {
+        Some(record) => Ok(TaggedLink(record)),
+        None if expected.is_some() => {
+            Err(precondition_failed(&repo, &slug, tenant_id).await.into())
+        }
+        None => Err(StatusCode::NOT_FOUND.into()),
+    }
+}
+
+/// Status for a conditional update that matched no row: `412` when the link
+/// exists (its version changed), `404` when it does not.
+async fn precondition_failed(
+    repo: &ShortLinkRepository,
+    slug: &str,
+    tenant_id: Option<Uuid>,
+) -> StatusCode {
+    match repo.get(slug, tenant_id).await {
+        Ok(Some(_)) => {
+            info!("Precondition failed updating slug={}", slug);
+            StatusCode::PRECONDITION_FAILED
+        }
+        Ok(None) => StatusCode::NOT_FOUND,
+        Err(e) => {
+            error!("Database error fetching short link {}: {}", slug, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+/// Create or fully replace the caller's link with this slug (`201` when
+/// created, `200` when replaced). `If-Match` restricts it to replacing the
+/// given version, `If-None-Match: *` to creating.
+pub async fn replace_link(
+    State(app): State<CrudApp>,
+    auth: ApiKeyAuth,
+    Path(slug): Path<String>,
+    headers: HeaderMap,
+    Json(req): Json<ReplaceLinkRequest>,
+) -> Result<(StatusCode, TaggedLink), LinkError> {
+    auth.require(Scope::LinksWrite)?;
+    let tenant_id = auth.tenant_id.ok_or(StatusCode::FORBIDDEN)?;
+    auth.settings.slug_policy.validate(&slug).map_err(|e| {
+        warn!("Rejecting slug {:?} for tenant {}: {}", slug, tenant_id, e);
+        LinkError::Invalid(ValidationError::new("slug", e.code(), e.to_string()))
+    })?;
+    let target_url =
+        normalize_target_url(app.target_url_policy(), &auth.settings, &req.target_url)?;
+    let fields = LinkFields {
+        target_url,
+        is_active: req.is_active.unwrap_or(true),
+        expires_at: resolve_expiry(&req.expires_at, &req.ttl, Utc::now())?,
+        redirect_type: req.redirect_type.unwrap_or_default(),
+        query_passthrough: req.query_passthrough.unwrap_or_default(),
+        path_passthrough: req.path_passthrough.unwrap_or(false),
+        param_template: parse_param_template(req.param_template.as_deref())?,
+    };
+    let precondition = Precondition::from_headers(&headers);
+
+    info!(
+        "Replace link slug={} tenant_id={} fields={:?} precondition={:?}",
+        slug, tenant_id, fields, precondition
+    );
+
+    // With If-None-Match, an existing slug fails the precondition instead of
+    // conflicting.
+    let conflict_status = if precondition == Precondition::Absent {
+        StatusCode::PRECONDITION_FAILED
+    } else {
+        StatusCode::CONFLICT
+    };
+    let repo = app.short_link_repository();
+    let result = match precondition {
+        Precondition::None => repo
+            .replace(&slug, &fields, tenant_id)
+            .await
+            .map(|(record, created)| Some((record, created))),
+        Precondition::Absent => repo
+            .create(&slug, &fields, tenant_id)
+            .await
+            .map(|record| Some((record, true))),
+        precondition => repo
+            .update(
+                &slug,
+                Some(tenant_id),
+                &fields.into(),
+                precondition.expected_versions().as_deref(),
+            )
+            .await
+            .map(|record| record.map(|record| (record, false))),
+    };
+
+    match result {
+        Ok(Some((record, created))) => {
+            let status = if created {
+                StatusCode::CREATED
+            } else {
+                StatusCode::OK
+            };
+            Ok((status, TaggedLink(record)))
+        }
+        // If-Match on a link that does not exist or changed.
+        Ok(None) => Err(StatusCode::PRECONDITION_FAILED.into()),
+        Err(AppError::Conflict(msg)) => {
+            warn!(
+                "Slug conflict for tenant {} replacing {}: {}",
+                tenant_id, slug, msg
+            );
+            Err(conflict_status.into())
+        }
+        Err(e) => {
+            error!("Database error replacing short link {}: {}", slug, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR.into())
+        }
+    }
+}
+
+/// Permanently delete a short link.
+pub async fn delete_link(
+    State(app): State<CrudApp>,
+    auth: ApiKeyAuth,
+    Path(slug): Path<String>,
+) -> Result<StatusCode, StatusCode> {
+    auth.require(Scope::LinksDelete)?;
+    let tenant_id = tenant_scope(&auth)?;
+    info!("Delete link slug={} tenant_id={:?}", slug, tenant_id);
+
+    let deleted = app
+        .short_link_repository()
+        .delete(&slug, tenant_id)
+        .await
+        .map_err(|e| {
+            error!("Database error deleting short link {}: {}", slug, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    if deleted {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(StatusCode::NOT_FOUND)
+    }
+}
+
 /// Create a short link (skeleton, no persistence yet).
 pub async fn create_link(
     State(app): State<CrudApp>,
     auth: ApiKeyAuth,
     Json(req): Json<CreateLinkRequest>,
-) -> Result<(StatusCode, Json<LinkResponse>), StatusCode> {
+) -> Result<(StatusCode, TaggedLink), LinkError> {
+    auth.require(Scope::LinksWrite)?;
     let tenant_id = auth.tenant_id.ok_or(StatusCode::FORBIDDEN)?;
+    let fields = LinkFields {
+        target_url: normalize_target_url(app.target_url_policy(), &auth.settings, &req.target_url)?,
+        is_active: true,
+        expires_at: resolve_expiry(&req.expires_at, &req.ttl, Utc::now())?,
+        redirect_type: req.redirect_type.unwrap_or_default(),
+        query_passthrough: req.query_passthrough.unwrap_or_default(),
+        path_passthrough: req.path_passthrough.unwrap_or(false),
+        param_template: parse_param_template(req.param_template.as_deref())?,
+    };
     let repo = app.short_link_repository();
     let strategy: Box<dyn LinkCreationStrategy> = match req.slug.as_deref().map(str::trim) {
-        Some(slug) if !slug.is_empty() => Box::new(ProvidedSlugStrategy::new(slug.to_string())),
-        _ => Box::new(GeneratedSlugStrategy::new(6)),
+        Some(slug) if !slug.is_empty() => {
+            auth.settings.slug_policy.validate(slug).map_err(|e| {
+                warn!("Rejecting slug {:?} for tenant {}: {}", slug, tenant_id, e);
+                LinkError::Invalid(ValidationError::new("slug", e.code(), e.to_string()))
+            })?;
+            Box::new(ProvidedSlugStrategy::new(slug.to_string()))
+        }
+        _ => {
+            let style = req.slug_style.unwrap_or(auth.settings.slug_style);
+            let generator = app.slug_generator(style).ok_or_else(|| {
+                warn!(
+                    "Rejecting slug style {:?}: not enabled on this server",
+                    style
+                );
+                LinkError::Invalid(ValidationError::new(
+                    "slug_style",
+                    "unavailable",
+                    "this slug style is not enabled on the server",
+                ))
+            })?;
+            let min_len = generated_slug_len(req.slug_length, &auth.settings)?;
+            Box::new(GeneratedSlugStrategy::new(generator, min_len))
+        }
     };
 
     let strategy_label = strategy.label();
     info!(
-        "Create link using strategy={} target_url={} tenant_id={}",
-        strategy_label, req.target_url, tenant_id
+        "Create link using strategy={} target_url={} expires_at={:?} redirect_type={} tenant_id={}",
+        strategy_label,
+        fields.target_url,
+        fields.expires_at,
+        fields.redirect_type.status_code(),
+        tenant_id
     );
 
     let result = strategy
-        .create(&repo, &req.target_url, tenant_id)
+        .create(&repo, &fields, tenant_id)
         .await
         .map_err(|e| match e {
             AppError::Conflict(msg) => {
@@ -290,85 +842,350 @@ pub async fn create_link(
             }
         })?;
 
-    let response = LinkResponse {
-        slug: result.0,
-        target_url: result.1,
-        active: result.2,
-    };
-
-    Ok((StatusCode::CREATED, Json(response)))
+    Ok((StatusCode::CREATED, TaggedLink(result)))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::sync::{Arc, Mutex};
+    use vym_fyi_model::services::slug::HexSlugGenerator;
+    use vym_fyi_model::services::target_url::HostBlocklist;
+
+    fn record(slug: &str, target_url: &str, expires_at: Option<DateTime<Utc>>) -> ShortLinkRecord {
+        ShortLinkRecord {
+            slug: slug.to_string(),
+            target_url: target_url.to_string(),
+            is_active: true,
+            expires_at,
+            redirect_type: RedirectType::default(),
+            query_passthrough: QueryPassthrough::default(),
+            path_passthrough: false,
+            param_template: None,
+            resolved_url: target_url.to_string(),
+            version: 1,
+        }
+    }
+
+    fn stored(slug: &str, fields: &LinkFields) -> ShortLinkRecord {
+        ShortLinkRecord {
+            redirect_type: fields.redirect_type,
+            ..record(slug, &fields.target_url, fields.expires_at)
+        }
+    }
 
     #[derive(Clone, Default)]
     struct StubRepo {
-        upsert_calls: Arc<Mutex<Vec<(String, String, Uuid)>>>,
+        create_calls: Arc<Mutex<Vec<(String, String, Uuid)>>>,
         generated_calls: Arc<Mutex<Vec<(String, usize, Uuid)>>>,
     }
 
     #[async_trait]
     impl LinkRepository for StubRepo {
-        async fn upsert(
+        async fn create(
             &self,
             slug: &str,
-            target_url: &str,
+            fields: &LinkFields,
             tenant_id: Uuid,
         ) -> LinkCreationResult {
-            self.upsert_calls.lock().unwrap().push((
+            self.create_calls.lock().unwrap().push((
                 slug.to_string(),
-                target_url.to_string(),
+                fields.target_url.clone(),
                 tenant_id,
             ));
-            Ok((slug.to_string(), target_url.to_string(), true))
+            Ok(stored(slug, fields))
         }
 
         async fn create_with_generated_slug(
             &self,
-            target_url: &str,
+            fields: &LinkFields,
+            _generator: &dyn SlugGenerator,
             min_len: usize,
             tenant_id: Uuid,
         ) -> LinkCreationResult {
-            self.generated_calls
-                .lock()
-                .unwrap()
-                .push((target_url.to_string(), min_len, tenant_id));
-            Ok(("generated".into(), target_url.to_string(), true))
+            self.generated_calls.lock().unwrap().push((
+                fields.target_url.clone(),
+                min_len,
+                tenant_id,
+            ));
+            Ok(stored("generated", fields))
         }
     }
 
     #[tokio::test]
-    async fn provided_slug_strategy_uses_upsert() {
+    async fn provided_slug_strategy_uses_create() {
         let repo = StubRepo::default();
         let strategy = ProvidedSlugStrategy::new("custom".into());
         let tenant_id = Uuid::nil();
 
+        let expires_at = Some(Utc::now() + Duration::days(1));
+        let fields = LinkFields {
+            expires_at,
+            redirect_type: RedirectType::PermanentRedirect,
+            ..LinkFields::new("https://example.com")
+        };
+
         let result = strategy
-            .create(&repo, "https://example.com", tenant_id)
+            .create(&repo, &fields, tenant_id)
             .await
             .expect("strategy should succeed");
 
-        assert_eq!(result.0, "custom");
-        assert_eq!(repo.upsert_calls.lock().unwrap().len(), 1);
+        assert_eq!(result.slug, "custom");
+        assert_eq!(result.expires_at, expires_at);
+        assert_eq!(result.redirect_type, RedirectType::PermanentRedirect);
+        assert_eq!(repo.create_calls.lock().unwrap().len(), 1);
         assert!(repo.generated_calls.lock().unwrap().is_empty());
     }
 
+    fn headers(pairs: &[(axum::http::HeaderName, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.append(name, HeaderValue::from_str(value).unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn precondition_parses_conditional_headers() {
+        assert_eq!(
+            Precondition::from_headers(&headers(&[])),
+            Precondition::None
+        );
+        assert_eq!(
+            Precondition::from_headers(&headers(&[(IF_MATCH, "*")])),
+            Precondition::Exists
+        );
+        assert_eq!(
+            Precondition::from_headers(&headers(&[
+                (IF_MATCH, "\"7\", W/\"8\""),
+                (IF_MATCH, "\"9\""),
+            ])),
+            Precondition::Versions(vec![7, 9])
+        );
+        assert_eq!(
+            Precondition::from_headers(&headers(&[(IF_NONE_MATCH, "*")])),
+            Precondition::Absent
+        );
+
+        // A tag that can never match still makes the update conditional.
+        let garbage = Precondition::from_headers(&headers(&[(IF_MATCH, "\"abc\"")]));
+        assert_eq!(garbage.expected_versions(), Some(vec![]));
+        assert_eq!(Precondition::Exists.expected_versions(), None);
+    }
+
+    #[test]
+    fn tagged_link_sets_etag() {
+        let mut link = record("promo", "https://example.com/", None);
+        link.version = 42;
+
+        let response = TaggedLink(link).into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[ETAG], "\"42\"");
+    }
+
+    #[test]
+    fn update_request_distinguishes_null_from_missing_expiry() {
+        let missing: UpdateLinkRequest = serde_json::from_str(r#"{"is_active": false}"#).unwrap();
+        assert!(missing.expires_at.is_none());
+        assert_eq!(missing.is_active, Some(false));
+
+        let cleared: UpdateLinkRequest = serde_json::from_str(r#"{"expires_at": null}"#).unwrap();
+        assert_eq!(cleared.expires_at, Some(None));
+
+        let set: UpdateLinkRequest =
+            serde_json::from_str(r#"{"expires_at": "2030-01-01T00:00:00Z"}"#).unwrap();
+        assert_eq!(
+            set.expires_at,
+            Some(Some("2030-01-01T00:00:00Z".to_string()))
+        );
+    }
+
+    #[test]
+    fn redirect_type_accepts_only_redirect_codes() {
+        let permanent: UpdateLinkRequest =
+            serde_json::from_str(r#"{"redirect_type": 301}"#).unwrap();
+        assert_eq!(
+            permanent.redirect_type,
+            Some(RedirectType::MovedPermanently)
+        );
+        assert!(serde_json::from_str::<UpdateLinkRequest>(r#"{"redirect_type": 303}"#).is_err());
+
+        let json =
+            serde_json::to_value(LinkResponse::from(record("a", "https://x/", None))).unwrap();
+        assert_eq!(json["redirect_type"], 307);
+    }
+
+    #[test]
+    fn tenant_scope_is_unrestricted_only_for_master() {
+        let tenant = Uuid::new_v4();
+        let master = ApiKeyAuth {
+            tenant_id: None,
+            scopes: Scope::ALL.to_vec(),
+            is_master: true,
+            settings: TenantSettings::default(),
+        };
+        let client = ApiKeyAuth {
+            tenant_id: Some(tenant),
+            scopes: Scope::ALL.to_vec(),
+            is_master: false,
+            settings: TenantSettings::default(),
+        };
+        let orphan = ApiKeyAuth {
+            tenant_id: None,
+            scopes: Scope::ALL.to_vec(),
+            is_master: false,
+            settings: TenantSettings::default(),
+        };
+
+        assert_eq!(tenant_scope(&master), Ok(None));
+        assert_eq!(tenant_scope(&client), Ok(Some(tenant)));
+        assert_eq!(tenant_scope(&orphan), Err(StatusCode::FORBIDDEN));
+    }
+
     #[tokio::test]
     async fn generated_slug_strategy_requests_generation() {
         let repo = StubRepo::default();
-        let strategy = GeneratedSlugStrategy::new(8);
+        let strategy = GeneratedSlugStrategy::new(&HexSlugGenerator, 8);
         let tenant_id = Uuid::nil();
 
         let result = strategy
-            .create(&repo, "https://example.com", tenant_id)
+            .create(&repo, &LinkFields::new("https://example.com"), tenant_id)
             .await
             .expect("strategy should succeed");
 
-        assert_eq!(result.0, "generated");
+        assert_eq!(result.slug, "generated");
+        assert!(result.expires_at.is_none());
         assert_eq!(repo.generated_calls.lock().unwrap().len(), 1);
-        assert!(repo.upsert_calls.lock().unwrap().is_empty());
+        assert!(repo.create_calls.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn generated_slug_len_respects_tenant_minimum() {
+        let settings = TenantSettings {
+            min_slug_len: 10,
+            ..TenantSettings::default()
+        };
+
+        assert_eq!(generated_slug_len(None, &settings), Ok(10));
+        assert_eq!(generated_slug_len(Some(16), &settings), Ok(16));
+        assert_eq!(
+            generated_slug_len(Some(8), &settings),
+            Err(StatusCode::BAD_REQUEST)
+        );
+        assert_eq!(
+            generated_slug_len(Some(MAX_GENERATED_SLUG_LEN + 1), &settings),
+            Err(StatusCode::BAD_REQUEST)
+        );
+    }
+
+    #[test]
+    fn target_url_checks_tenant_blocklist() {
+        let policy = TargetUrlPolicy::default();
+        let settings = TenantSettings {
+            target_blocklist: HostBlocklist::parse(&["competitor.example"]).unwrap(),
+            ..TenantSettings::default()
+        };
+
+        assert_eq!(
+            normalize_target_url(&policy, &settings, "HTTPS://Example.com").ok(),
+            Some("https://example.com/".to_string())
+        );
+        assert!(
+            normalize_target_url(
+                &policy,
+                &TenantSettings::default(),
+                "https://competitor.example"
+            )
+            .is_ok()
+        );
+        match normalize_target_url(&policy, &settings, "https://www.competitor.example/") {
+            Err(LinkError::Invalid(err)) => {
+                assert_eq!((err.field, err.code), ("target_url", "blocked_host"))
+            }
+            _ => panic!("expected a validation error"),
+        }
+    }
+
+    #[test]
+    fn invalid_fields_map_to_422() {
+        let invalid = LinkError::Invalid(ValidationError::new("slug", "reserved", "reserved"));
+        assert_eq!(
+            invalid.into_response().status(),
+            StatusCode::UNPROCESSABLE_ENTITY
+        );
+        assert_eq!(
+            LinkError::from(StatusCode::CONFLICT)
+                .into_response()
+                .status(),
+            StatusCode::CONFLICT
+        );
+    }
+
+    #[test]
+    fn param_template_is_validated_and_blank_clears_it() {
+        assert_eq!(
+            parse_param_template(Some(" utm_campaign={{slug}} ")).ok(),
+            Some(Some("utm_campaign={{slug}}".to_string()))
+        );
+        assert_eq!(parse_param_template(Some("  ")).ok(), Some(None));
+        assert!(matches!(
+            parse_param_template(Some("utm_source={{user}}")),
+            Err(LinkError::Invalid(_))
+        ));
+
+        let cleared: UpdateLinkRequest =
+            serde_json::from_str(r#"{"param_template": null}"#).unwrap();
+        assert_eq!(cleared.param_template, Some(None));
+    }
+
+    #[test]
+    fn parses_ttl_units() {
+        assert_eq!(parse_ttl("90s"), Some(Duration::seconds(90)));
+        assert_eq!(parse_ttl("30m"), Some(Duration::minutes(30)));
+        assert_eq!(parse_ttl("12h"), Some(Duration::hours(12)));
+        assert_eq!(parse_ttl(" 7d "), Some(Duration::days(7)));
+        assert_eq!(parse_ttl("2w"), Some(Duration::weeks(2)));
+        assert_eq!(parse_ttl("0d"), None);
+        assert_eq!(parse_ttl("7y"), None);
+        assert_eq!(parse_ttl("7é"), None);
+        assert_eq!(parse_ttl("d"), None);
+        assert_eq!(parse_ttl(""), None);
+    }
+
+    #[test]
+    fn resolve_expiry_accepts_either_absolute_or_relative() {
+        let now = DateTime::parse_from_rfc3339("2030-01-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        assert_eq!(resolve_expiry(&None, &None, now), Ok(None));
+        assert_eq!(
+            resolve_expiry(&None, &Some("7d".into()), now),
+            Ok(Some(now + Duration::days(7)))
+        );
+        assert_eq!(
+            resolve_expiry(&Some("2030-02-01T00:00:00Z".into()), &None, now),
+            Ok(Some(now + Duration::days(31)))
+        );
+    }
+
+    #[test]
+    fn resolve_expiry_rejects_invalid_input() {
+        let now = Utc::now();
+        let future = (now + Duration::days(1)).to_rfc3339();
+        let past = (now - Duration::days(1)).to_rfc3339();
+
+        assert_eq!(
+            resolve_expiry(&Some(future), &Some("1d".into()), now),
+            Err(StatusCode::BAD_REQUEST)
+        );
+        assert_eq!(
+            resolve_expiry(&Some(past), &None, now),
+            Err(StatusCode::BAD_REQUEST)
+        );
+        assert_eq!(
+            resolve_expiry(&None, &Some("soon".into()), now),
+            Err(StatusCode::BAD_REQUEST)
+        );
     }
 }
@@ -1,2 +1,7 @@
+pub mod api_keys;
 pub mod health;
+pub mod link_rules;
+pub mod link_variants;
 pub mod links;
+pub mod stats;
+pub mod tenants;
@@ -0,0 +1,263 @@
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, error, warn};
+use vym_fyi_model::models::url_shortener::{Scope, StatsGranularity};
+use vym_fyi_model::services::repos::ClickStats;
+
+use crate::app::CrudApp;
+use crate::auth::ApiKeyAuth;
+use crate::handlers::links::{parse_rfc3339_opt, tenant_scope};
+
+/// Number of referrers returned in `top_referrers`.
+const TOP_REFERRERS: i64 = 10;
+
+/// Query parameters for link statistics.
+#[derive(Deserialize)]
+pub struct LinkStatsQuery {
+    /// Start of the window (RFC3339, inclusive). Defaults to one day (hourly)
+    /// or 30 days (daily) before `to`.
+    pub from: Option<String>,
+    /// End of the window (RFC3339, exclusive). Defaults to now.
+    pub to: Option<String>,
+    pub granularity: Option<StatsGranularity>,
+}
+
+#[derive(Serialize)]
+pub struct StatsBucket {
+    pub start: DateTime<Utc>,
+    pub clicks: i64,
+}
+
+#[derive(Serialize)]
+pub struct ReferrerCount {
+    /// Referrer host; `null` for clicks without a referrer.
+    pub referrer: Option<String>,
+    pub clicks: i64,
+}
+
+#[derive(Serialize)]
+pub struct UserAgentCount {
+    pub class: String,
+    pub clicks: i64,
+}
+
+#[derive(Serialize)]
+pub struct VariantCount {
+    /// A/B variant name; `null` for clicks not sent to a variant.
+    pub variant: Option<String>,
+    pub clicks: i64,
+}
+
+#[derive(Serialize)]
+pub struct LinkStatsResponse {
+    pub slug: String,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub granularity: StatsGranularity,
+    pub total: i64,
+    pub series: Vec<StatsBucket>,
+    pub top_referrers: Vec<ReferrerCount>,
+    pub user_agents: Vec<UserAgentCount>,
+    pub variants: Vec<VariantCount>,
+}
+
+impl LinkStatsResponse {
+    fn new(
+        slug: String,
+        (from, to): (DateTime<Utc>, DateTime<Utc>),
+        granularity: StatsGranularity,
+        stats: ClickStats,
+    ) -> Self {
+        Self {
+            slug,
+            from,
+            to,
+            granularity,
+            total: stats.total,
+            series: stats
+                .series
+                .into_iter()
+                .map(|(start, clicks)| StatsBucket { start, clicks })
+                .collect(),
+            top_referrers: stats
+                .top_referrers
+                .into_iter()
+                .map(|(referrer, clicks)| ReferrerCount { referrer, clicks })
+                .collect(),
+            user_agents: stats
+                .user_agents
+                .into_iter()
+                .map(|(class, clicks)| UserAgentCount { class, clicks })
+                .collect(),
+            variants: stats
+                .variants
+                .into_iter()
+                .map(|(variant, clicks)| VariantCount { variant, clicks })
+                .collect(),
+        }
+    }
+}
+
+/// Resolve the requested window, applying defaults and rejecting empty or
+/// oversized ranges (at most 31 days hourly, 366 days daily).
+fn stats_window(
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+    granularity: StatsGranularity,
+    now: DateTime<Utc>,
+) -> Result<(DateTime<Utc>, DateTime<Utc>), StatusCode> {
+    let (default_span, max_span) = match granularity {
+        StatsGranularity::Hour => (Duration::days(1), Duration::days(31)),
+        StatsGranularity::Day => (Duration::days(30), Duration::days(366)),
+    };
+
+    let to = to.unwrap_or(now);
+    let from = from.unwrap_or(to - default_span);
+    if from >= to || to - from > max_span {
+        warn!(
+            "Rejected stats window from={} to={} granularity={}",
+            from,
+            to,
+            granularity.as_str()
+        );
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    Ok((from, to))
+}
+
+/// Click statistics for a single link: totals, a time series, top referrers
+/// and the user-agent breakdown.
+pub async fn link_stats(
+    State(app): State<CrudApp>,
+    auth: ApiKeyAuth,
+    Path(slug): Path<String>,
+    Query(query): Query<LinkStatsQuery>,
+) -> Result<Json<LinkStatsResponse>, StatusCode> {
+    auth.require(Scope::LinksRead)?;
+    let tenant_id = tenant_scope(&auth)?;
+    let granularity = query.granularity.unwrap_or_default();
+    let window = stats_window(
+        parse_rfc3339_opt("from", &query.from)?,
+        parse_rfc3339_opt("to", &query.to)?,
+        granularity,
+        Utc::now(),
+    )?;
+    debug!(
+        "Stats for slug={} tenant_id={:?} window={:?}",
+        slug, tenant_id, window
+    );
+
+    // Only links visible to the caller have stats.
+    app.short_link_repository()
+        .get(&slug, tenant_id)
+        .await
+        .map_err(|e| {
+            error!("Database error fetching short link {}: {}", slug, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let stats = app
+        .click_repository()
+        .stats(
+            &slug,
+            tenant_id,
+            window.0,
+            window.1,
+            granularity,
+            TOP_REFERRERS,
+        )
+        .await
+        .map_err(|e| {
+            error!("Database error computing stats for {}: {}", slug, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(LinkStatsResponse::new(
+        slug,
+        window,
+        granularity,
+        stats,
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stats_window_applies_defaults_per_granularity() {
+        let now = Utc::now();
+
+        assert_eq!(
+            stats_window(None, None, StatsGranularity::Day, now),
+            Ok((now - Duration::days(30), now))
+        );
+        assert_eq!(
+            stats_window(None, None, StatsGranularity::Hour, now),
+            Ok((now - Duration::days(1), now))
+        );
+    }
+
+    #[test]
+    fn stats_window_rejects_empty_and_oversized_ranges() {
+        let now = Utc::now();
+
+        assert_eq!(
+            stats_window(Some(now), Some(now), StatsGranularity::Day, now),
+            Err(StatusCode::BAD_REQUEST)
+        );
+        assert_eq!(
+            stats_window(
+                Some(now - Duration::days(32)),
+                None,
+                StatsGranularity::Hour,
+                now
+            ),
+            Err(StatusCode::BAD_REQUEST)
+        );
+        assert!(
+            stats_window(
+                Some(now - Duration::days(32)),
+                None,
+                StatsGranularity::Day,
+                now
+            )
+            .is_ok()
+        );
+    }
+
+    #[test]
+    fn response_serializes_buckets_and_breakdowns() {
+        let now = Utc::now();
+        let response = LinkStatsResponse::new(
+            "promo".into(),
+            (now - Duration::days(1), now),
+            StatsGranularity::Hour,
+            ClickStats {
+                total: 3,
+                series: vec![(now - Duration::hours(1), 3)],
+                top_referrers: vec![(Some("example.com".into()), 2), (None, 1)],
+                user_agents: vec![("mobile".into(), 3)],
+                variants: vec![(Some("b".into()), 2), (Some("a".into()), 1)],
+            },
+        );
+
+        let json = serde_json::to_value(&response).unwrap();
+        assert_eq!(json["granularity"], "hour");
+        assert_eq!(json["total"], 3);
+        assert_eq!(json["series"][0]["clicks"], 3);
+        assert_eq!(
+            json["top_referrers"][1]["referrer"],
+            serde_json::Value::Null
+        );
+        assert_eq!(json["user_agents"][0]["class"], "mobile");
+        assert_eq!(json["variants"][0]["variant"], "b");
+        assert_eq!(json["variants"][0]["clicks"], 2);
+    }
+}
@@ -0,0 +1,55 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, warn};
+use vym_fyi_model::models::url_shortener::TenantStatus;
+
+use crate::app::CrudApp;
+use crate::auth::ApiKeyAuth;
+
+#[derive(Deserialize)]
+pub struct TenantStatusRequest {
+    pub status: TenantStatus,
+}
+
+#[derive(Serialize)]
+pub struct TenantStatusResponse {
+    pub name: String,
+    pub status: TenantStatus,
+}
+
+/// Suspend or reactivate a tenant (master key only). Suspended tenants are
+/// rejected by the CRUD API and their slugs stop redirecting.
+pub async fn set_tenant_status(
+    State(app): State<CrudApp>,
+    auth: ApiKeyAuth,
+    Path(tenant): Path<String>,
+    Json(req): Json<TenantStatusRequest>,
+) -> Result<Json<TenantStatusResponse>, StatusCode> {
+    if !auth.is_master {
+        warn!("Tenant status change attempted without master key");
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let updated = app
+        .tenant_repository()
+        .set_status(&tenant, req.status)
+        .await
+        .map_err(|e| {
+            error!("Database error updating status of tenant {}: {}", tenant, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    if !updated {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    info!("Tenant {} is now {}", tenant, req.status.as_str());
+    Ok(Json(TenantStatusResponse {
+        name: tenant,
+        status: req.status,
+    }))
+}
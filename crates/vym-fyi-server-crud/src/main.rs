@@ -1,24 +1,42 @@
 use crate::app::{CrudApp, CrudAppBuilder};
+use crate::auth::{PreAuthRateLimit, authenticate, rate_limit_key, reject_exhausted_clients};
+use crate::config_reload::spawn_tenants_config_reloader;
+use crate::handlers::api_keys::{create_api_key, list_api_keys, revoke_api_key};
 use crate::handlers::health::health;
-use crate::handlers::links::{create_link, list_links};
+use crate::handlers::link_rules::{
+    create_link_rule, delete_link_rule, list_link_rules, replace_link_rules,
+};
+use crate::handlers::link_variants::{get_link_variants, replace_link_variants};
+use crate::handlers::links::{
+    create_link, delete_link, get_link, list_links, replace_link, update_link,
+};
+use crate::handlers::stats::link_stats;
+use crate::handlers::tenants::set_tenant_status;
 use axum::{
     Router, middleware,
-    routing::{get, post},
+    routing::{delete, get, post, put},
 };
 use mimalloc::MiMalloc;
 use std::net::SocketAddr;
+use std::sync::Arc;
 use tokio::net::TcpListener;
 use tracing::info;
 use vym_fyi_model::models::errors::AppResult;
-use vym_fyi_model::services::axum_metrics::{prometheus_layer_default, record_ip_metrics};
+use vym_fyi_model::services::axum_metrics::{
+    init_metrics_labels_from_env, prometheus_layer_default, record_ip_metrics,
+};
+use vym_fyi_model::services::client_ip::{init_client_ip_resolver_from_env, resolve_client_ip};
 use vym_fyi_model::services::config::bind_addr_from_env;
 use vym_fyi_model::services::logging::setup_logging;
+use vym_fyi_model::services::rate_limit::{RateLimitLayer, RateLimiter};
 use vym_fyi_model::services::static_assets;
 
 mod app;
 mod auth;
+mod config_reload;
 mod handlers;
 mod models;
+mod tenant_sync;
 
 #[global_allocator]
 static GLOBAL: MiMalloc = MiMalloc;
@@ -35,12 +53,60 @@ async fn main() -> AppResult<()> {
         .build()
         .await?;
 
+    // Pick up tenants config changes (file edits or SIGHUP) without a restart.
+    spawn_tenants_config_reloader(app.clone())?;
+
+    init_metrics_labels_from_env()?;
+    init_client_ip_resolver_from_env()?;
     let (prometheus_layer, prometheus_handle) = prometheus_layer_default();
     let metrics_handle = prometheus_handle.clone();
 
+    // Limit by API key binding (per-tenant overrides), or by client IP for
+    // unauthenticated requests. Clients whose IP bucket is empty are turned
+    // away before their key is looked up.
+    let default_rate_limit = app.rate_limit();
+    let limiter = Arc::new(RateLimiter::default());
+    let pre_auth_limit = PreAuthRateLimit {
+        limiter: limiter.clone(),
+        policy: default_rate_limit,
+    };
+    let rate_limit_layer =
+        RateLimitLayer::new(limiter, move |req| rate_limit_key(req, default_rate_limit));
+
     let router = Router::new()
         .route("/health", get(health))
-        .route("/api/links", post(create_link).get(list_links));
+        .route("/api/links", post(create_link).get(list_links))
+        .route(
+            "/api/links/{slug}",
+            get(get_link)
+                .put(replace_link)
+                .patch(update_link)
+                .delete(delete_link),
+        )
+        .route(
+            "/api/links/{slug}/rules",
+            get(list_link_rules)
+                .put(replace_link_rules)
+                .post(create_link_rule),
+        )
+        .route(
+            "/api/links/{slug}/rules/{rule_id}",
+            delete(delete_link_rule),
+        )
+        .route(
+            "/api/links/{slug}/variants",
+            get(get_link_variants).put(replace_link_variants),
+        )
+        .route("/api/links/{slug}/stats", get(link_stats))
+        .route(
+            "/api/admin/tenants/{tenant}/keys",
+            post(create_api_key).get(list_api_keys),
+        )
+        .route(
+            "/api/admin/tenants/{tenant}/keys/{key_id}",
+            delete(revoke_api_key),
+        )
+        .route("/api/admin/tenants/{tenant}/status", put(set_tenant_status));
 
     let router = static_assets::attach_static_routes(router)
         .route(
@@ -48,8 +114,15 @@ async fn main() -> AppResult<()> {
             get(move || async move { metrics_handle.render() }),
         )
         .with_state(app.clone())
+        .layer(rate_limit_layer)
+        .layer(middleware::from_fn_with_state(app.clone(), authenticate))
+        .layer(middleware::from_fn_with_state(
+            pre_auth_limit,
+            reject_exhausted_clients,
+        ))
         .layer(prometheus_layer)
-        .layer(middleware::from_fn(record_ip_metrics));
+        .layer(middleware::from_fn(record_ip_metrics))
+        .layer(middleware::from_fn(resolve_client_ip));
 
     let addr = bind_addr_from_env(8000)?;
     let listener = TcpListener::bind(addr).await?;
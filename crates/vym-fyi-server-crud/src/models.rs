@@ -12,3 +12,26 @@ impl Health {
         }
     }
 }
+
+/// Body of `422 Unprocessable Entity` responses for invalid request fields.
+#[derive(Serialize)]
+pub struct ValidationError {
+    /// Always `validation_failed`.
+    pub error: &'static str,
+    /// Request field that failed validation.
+    pub field: &'static str,
+    /// Machine-readable reason, e.g. `reserved`.
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl ValidationError {
+    pub fn new(field: &'static str, code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            error: "validation_failed",
+            field,
+            code,
+            message: message.into(),
+        }
+    }
+}
@@ -0,0 +1,334 @@
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+
+use tracing::{info, warn};
+use uuid::Uuid;
+use vym_fyi_model::models::errors::{AppError, AppResult};
+use vym_fyi_model::models::url_shortener::{ClientConfig, TenantStatus};
+use vym_fyi_model::services::repos::TenantRepository;
+
+/// How tenants missing from the config are treated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TenantSyncPolicy {
+    /// Only create tenants; never touch tenants missing from the config.
+    #[default]
+    CreateOnly,
+    /// Delete tenants missing from the config (unless they still own links).
+    Mirror,
+    /// Suspend tenants missing from the config instead of deleting them.
+    MirrorWithSuspend,
+}
+
+impl FromStr for TenantSyncPolicy {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "create-only" => Ok(TenantSyncPolicy::CreateOnly),
+            "mirror" => Ok(TenantSyncPolicy::Mirror),
+            "mirror-with-suspend" => Ok(TenantSyncPolicy::MirrorWithSuspend),
+            other => Err(AppError::Config(format!(
+                "unknown tenant sync policy: {} (expected create-only, mirror or mirror-with-suspend)",
+                other
+            ))),
+        }
+    }
+}
+
+/// Options controlling a tenant sync run.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TenantSyncOptions {
+    pub policy: TenantSyncPolicy,
+    /// Log the planned changes without applying them.
+    pub dry_run: bool,
+    /// Allow `mirror` to delete tenants that still own links.
+    pub force_delete: bool,
+}
+
+impl TenantSyncOptions {
+    /// Read `TENANT_SYNC_POLICY`, `TENANT_SYNC_DRY_RUN` and `TENANT_SYNC_FORCE_DELETE`.
+    pub fn from_env() -> AppResult<Self> {
+        fn flag(name: &str) -> bool {
+            std::env::var(name)
+                .map(|v| matches!(v.trim(), "1" | "true" | "yes"))
+                .unwrap_or(false)
+        }
+
+        let policy = match std::env::var("TENANT_SYNC_POLICY") {
+            Ok(raw) => raw.trim().parse()?,
+            Err(_) => TenantSyncPolicy::default(),
+        };
+
+        Ok(Self {
+            policy,
+            dry_run: flag("TENANT_SYNC_DRY_RUN"),
+            force_delete: flag("TENANT_SYNC_FORCE_DELETE"),
+        })
+    }
+}
+
+/// Changes needed to bring the database in line with the config.
+#[derive(Debug, Default, PartialEq)]
+pub struct TenantSyncPlan {
+    pub create: Vec<(String, TenantStatus)>,
+    pub set_status: Vec<(String, TenantStatus)>,
+    pub delete: Vec<String>,
+    pub suspend: Vec<String>,
+    /// Tenants `mirror` would delete but which still own links.
+    pub blocked: Vec<(String, i64)>,
+}
+
+impl TenantSyncPlan {
+    /// Compute the plan from the config and the current tenants as
+    /// (id, name, owned link count).
+    pub fn compute(
+        config: &ClientConfig,
+        existing: &[(Uuid, String, i64)],
+        options: &TenantSyncOptions,
+    ) -> Self {
+        let desired: HashSet<&String> = config.clients.keys().collect();
+        let existing_names: HashSet<&String> = existing.iter().map(|(_, name, _)| name).collect();
+        let mut plan = TenantSyncPlan::default();
+
+        for (name, entry) in &config.clients {
+            if existing_names.contains(name) {
+                if let Some(status) = entry.status {
+                    plan.set_status.push((name.clone(), status));
+                }
+            } else {
+                plan.create
+                    .push((name.clone(), entry.status.unwrap_or(TenantStatus::Active)));
+            }
+        }
+
+        for (_, name, link_count) in existing.iter().filter(|(_, n, _)| !desired.contains(n)) {
+            match options.policy {
+                TenantSyncPolicy::CreateOnly => {}
+                TenantSyncPolicy::MirrorWithSuspend => plan.suspend.push(name.clone()),
+                TenantSyncPolicy::Mirror if *link_count > 0 && !options.force_delete => {
+                    plan.blocked.push((name.clone(), *link_count))
+                }
+                TenantSyncPolicy::Mirror => plan.delete.push(name.clone()),
+            }
+        }
+
+        plan.create.sort_by(|a, b| a.0.cmp(&b.0));
+        plan.set_status.sort_by(|a, b| a.0.cmp(&b.0));
+        plan.delete.sort();
+        plan.suspend.sort();
+        plan.blocked.sort();
+        plan
+    }
+
+    fn log(&self, dry_run: bool) {
+        let mode = if dry_run { "dry-run" } else { "apply" };
+        for (name, status) in &self.create {
+            info!(
+                "tenant sync [{}]: create name={} status={}",
+                mode,
+                name,
+                status.as_str()
+            );
+        }
+        for (name, status) in &self.set_status {
+            info!(
+                "tenant sync [{}]: set name={} status={}",
+                mode,
+                name,
+                status.as_str()
+            );
+        }
+        for name in &self.suspend {
+            info!(
+                "tenant sync [{}]: suspend name={} (missing from config)",
+                mode, name
+            );
+        }
+        for name in &self.delete {
+            info!("tenant sync [{}]: delete name={}", mode, name);
+        }
+        for (name, links) in &self.blocked {
+            warn!(
+                "tenant sync [{}]: refusing to delete name={} which owns {} link(s); set TENANT_SYNC_FORCE_DELETE=true to override",
+                mode, name, links
+            );
+        }
+    }
+}
+
+/// Synchronize tenants in the database with the config according to `options`.
+pub async fn sync_tenants(
+    repo: &TenantRepository,
+    config: &ClientConfig,
+    options: &TenantSyncOptions,
+) -> AppResult<TenantSyncPlan> {
+    info!(
+        "tenant sync: {} tenants defined in config (policy={:?}, dry_run={})",
+        config.clients.len(),
+        options.policy,
+        options.dry_run
+    );
+
+    let existing = repo.list_with_link_counts().await?;
+    let plan = TenantSyncPlan::compute(config, &existing, options);
+    plan.log(options.dry_run);
+
+    if options.dry_run {
+        return Ok(plan);
+    }
+
+    for (name, status) in &plan.create {
+        let _ = repo.create(name, *status).await?;
+    }
+    for (name, status) in &plan.set_status {
+        repo.set_status(name, *status).await?;
+    }
+    for name in &plan.suspend {
+        repo.set_status(name, TenantStatus::Suspended).await?;
+    }
+    for name in &plan.delete {
+        repo.delete_by_name(name).await?;
+    }
+
+    // Templates are not part of the plan: every configured tenant gets its
+    // template, and only real changes are written.
+    for (name, entry) in &config.clients {
+        let template = entry
+            .param_template
+            .as_deref()
+            .map(str::trim)
+            .filter(|t| !t.is_empty());
+        if repo.set_param_template(name, template).await? {
+            info!(
+                "tenant sync [apply]: set name={} param_template={:?}",
+                name, template
+            );
+        }
+    }
+
+    Ok(plan)
+}
+
+/// Map tenant name -> id for the tenants currently in the database.
+pub async fn tenant_ids_by_name(repo: &TenantRepository) -> AppResult<HashMap<String, Uuid>> {
+    Ok(repo
+        .list_all()
+        .await?
+        .into_iter()
+        .map(|(id, name)| (name, id))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vym_fyi_model::models::url_shortener::{ClientEntry, ServerSection};
+
+    fn config(clients: &[(&str, Option<TenantStatus>)]) -> ClientConfig {
+        ClientConfig {
+            server: ServerSection {
+                base_url: "http://localhost".into(),
+                master_api_key: None,
+            },
+            clients: clients
+                .iter()
+                .map(|(name, status)| {
+                    (
+                        name.to_string(),
+                        ClientEntry {
+                            name: name.to_string(),
+                            api_key: "key".into(),
+                            status: *status,
+                            ..Default::default()
+                        },
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    fn existing(tenants: &[(&str, i64)]) -> Vec<(Uuid, String, i64)> {
+        tenants
+            .iter()
+            .map(|(name, links)| (Uuid::new_v4(), name.to_string(), *links))
+            .collect()
+    }
+
+    fn options(policy: TenantSyncPolicy, force_delete: bool) -> TenantSyncOptions {
+        TenantSyncOptions {
+            policy,
+            dry_run: false,
+            force_delete,
+        }
+    }
+
+    #[test]
+    fn parses_policy_names() {
+        assert_eq!(
+            "create-only".parse::<TenantSyncPolicy>().unwrap(),
+            TenantSyncPolicy::CreateOnly
+        );
+        assert_eq!(
+            "mirror".parse::<TenantSyncPolicy>().unwrap(),
+            TenantSyncPolicy::Mirror
+        );
+        assert_eq!(
+            "mirror-with-suspend".parse::<TenantSyncPolicy>().unwrap(),
+            TenantSyncPolicy::MirrorWithSuspend
+        );
+        assert!("delete-all".parse::<TenantSyncPolicy>().is_err());
+    }
+
+    #[test]
+    fn create_only_never_removes_tenants() {
+        let cfg = config(&[("a", None), ("b", Some(TenantStatus::Suspended))]);
+        let plan = TenantSyncPlan::compute(
+            &cfg,
+            &existing(&[("a", 0), ("gone", 3)]),
+            &options(TenantSyncPolicy::CreateOnly, false),
+        );
+
+        assert_eq!(
+            plan.create,
+            vec![("b".to_string(), TenantStatus::Suspended)]
+        );
+        assert!(plan.set_status.is_empty());
+        assert!(plan.delete.is_empty());
+        assert!(plan.suspend.is_empty());
+        assert!(plan.blocked.is_empty());
+    }
+
+    #[test]
+    fn mirror_refuses_to_delete_tenants_with_links_unless_forced() {
+        let cfg = config(&[("a", Some(TenantStatus::Active))]);
+        let current = existing(&[("a", 1), ("empty", 0), ("busy", 2)]);
+
+        let plan =
+            TenantSyncPlan::compute(&cfg, &current, &options(TenantSyncPolicy::Mirror, false));
+        assert_eq!(
+            plan.set_status,
+            vec![("a".to_string(), TenantStatus::Active)]
+        );
+        assert_eq!(plan.delete, vec!["empty".to_string()]);
+        assert_eq!(plan.blocked, vec![("busy".to_string(), 2)]);
+
+        let forced =
+            TenantSyncPlan::compute(&cfg, &current, &options(TenantSyncPolicy::Mirror, true));
+        assert_eq!(forced.delete, vec!["busy".to_string(), "empty".to_string()]);
+        assert!(forced.blocked.is_empty());
+    }
+
+    #[test]
+    fn mirror_with_suspend_keeps_missing_tenants() {
+        let cfg = config(&[("a", None)]);
+        let plan = TenantSyncPlan::compute(
+            &cfg,
+            &existing(&[("a", 0), ("busy", 5)]),
+            &options(TenantSyncPolicy::MirrorWithSuspend, false),
+        );
+
+        assert_eq!(plan.suspend, vec!["busy".to_string()]);
+        assert!(plan.delete.is_empty());
+        assert!(plan.blocked.is_empty());
+    }
+}
@@ -1,18 +1,38 @@
+use chrono::Utc;
 use sqlx::{Pool, Postgres, postgres::PgPoolOptions};
 use std::sync::Arc;
+use tracing::warn;
 use vym_fyi_model::models::errors::AppResult;
-use vym_fyi_model::services::repos::{PgRepositoryFactory, RepositoryFactory, ShortLinkRepository};
+use vym_fyi_model::services::repos::{
+    PgRepositoryFactory, RepositoryFactory, ResolvedLink, ShortLinkRepository,
+};
+
+use crate::cache::{CacheLookup, SlugCache, SlugCacheConfig};
+use crate::clicks::{ClickRecorder, ClickWriterConfig};
+use crate::enumeration::{EnumerationGuard, EnumerationGuardConfig};
 
 /// Facade over redirect server components.
 #[derive(Clone)]
 pub struct RedirectApp {
     repos: Arc<dyn RepositoryFactory>,
+    cache: Option<Arc<SlugCache>>,
+    invalidation_pool: Option<Pool<Postgres>>,
+    clicks: ClickRecorder,
+    enumeration: Option<Arc<EnumerationGuard>>,
 }
 
 /// Builder for `RedirectApp`.
 pub struct RedirectAppBuilder {
     database_url_ro: String,
     max_connections: u32,
+    cache: SlugCacheConfig,
+    /// `LISTEN` needs the primary; a hot standby rejects it.
+    cache_invalidation_database_url: Option<String>,
+    /// Click events need a user allowed to insert into `link_clicks`;
+    /// without one click recording is off.
+    clicks_database_url: Option<String>,
+    clicks: ClickWriterConfig,
+    enumeration: EnumerationGuardConfig,
 }
 
 impl RedirectAppBuilder {
@@ -20,10 +40,17 @@ impl RedirectAppBuilder {
         let database_url_ro = std::env::var("DATABASE_URL_RO").map_err(|_| {
             vym_fyi_model::models::errors::AppError::Config("DATABASE_URL_RO not set".into())
         })?;
+        let clicks_database_url = std::env::var("CLICKS_DATABASE_URL").ok();
+        let cache_invalidation_database_url = std::env::var("CACHE_INVALIDATION_DATABASE_URL").ok();
 
         Ok(Self {
             database_url_ro,
             max_connections: 5,
+            cache: SlugCacheConfig::from_env()?,
+            cache_invalidation_database_url,
+            clicks_database_url,
+            clicks: ClickWriterConfig::from_env()?,
+            enumeration: EnumerationGuardConfig::from_env()?,
         })
     }
 
@@ -39,8 +66,46 @@ impl RedirectAppBuilder {
             .await?;
 
         let repos: Arc<dyn RepositoryFactory> = Arc::new(PgRepositoryFactory::new(pool));
+        let cache = SlugCache::new(self.cache).map(Arc::new);
+        let invalidation_pool = match (&cache, &self.cache_invalidation_database_url) {
+            (Some(_), Some(url)) => {
+                Some(PgPoolOptions::new().max_connections(1).connect_lazy(url)?)
+            }
+            (Some(_), None) => {
+                warn!(
+                    "CACHE_INVALIDATION_DATABASE_URL not set; cached slugs only expire through their TTL"
+                );
+                None
+            }
+            (None, _) => None,
+        };
+
+        // Click writes get their own small pool so a slow insert never
+        // starves slug resolution of connections.
+        let clicks = match &self.clicks_database_url {
+            Some(url) => {
+                let clicks_pool: Pool<Postgres> =
+                    PgPoolOptions::new().max_connections(2).connect_lazy(url)?;
+                ClickRecorder::spawn(
+                    self.clicks,
+                    PgRepositoryFactory::new(clicks_pool).click_repo(),
+                )
+            }
+            None => {
+                if self.clicks.queue_capacity > 0 {
+                    warn!("CLICKS_DATABASE_URL not set; click recording disabled");
+                }
+                ClickRecorder::disabled()
+            }
+        };
 
-        Ok(RedirectApp { repos })
+        Ok(RedirectApp {
+            repos,
+            cache,
+            invalidation_pool,
+            clicks,
+            enumeration: EnumerationGuard::new(self.enumeration).map(Arc::new),
+        })
     }
 }
 
@@ -48,4 +113,51 @@ impl RedirectApp {
     pub fn short_link_repository(&self) -> ShortLinkRepository {
         self.repos.short_link_repo()
     }
+
+    /// Connection to the primary used to `LISTEN` for link changes; `None`
+    /// when the cache is off or no invalidation URL is configured.
+    pub fn cache_invalidation_pool(&self) -> Option<&Pool<Postgres>> {
+        self.invalidation_pool.as_ref()
+    }
+
+    pub fn click_recorder(&self) -> &ClickRecorder {
+        &self.clicks
+    }
+
+    pub fn slug_cache(&self) -> Option<Arc<SlugCache>> {
+        self.cache.clone()
+    }
+
+    pub fn enumeration_guard(&self) -> Option<&EnumerationGuard> {
+        self.enumeration.as_deref()
+    }
+
+    /// Resolve a slug, consulting the in-process cache first. Database
+    /// errors are never cached.
+    pub async fn resolve_slug(&self, slug: &str) -> AppResult<Option<ResolvedLink>> {
+        let Some(cache) = &self.cache else {
+            return self.short_link_repository().resolve(slug).await;
+        };
+
+        match cache.get(slug, Utc::now()) {
+            CacheLookup::Hit(link) => {
+                record_cache_lookup("hit");
+                return Ok(Some(link));
+            }
+            CacheLookup::NegativeHit => {
+                record_cache_lookup("negative_hit");
+                return Ok(None);
+            }
+            CacheLookup::Miss => record_cache_lookup("miss"),
+        }
+
+        let resolved = self.short_link_repository().resolve(slug).await?;
+        cache.insert(slug, resolved.clone(), Utc::now());
+        metrics::gauge!("redirect_cache_entries").set(cache.len() as f64);
+        Ok(resolved)
+    }
+}
+
+fn record_cache_lookup(result: &'static str) {
+    metrics::counter!("redirect_cache_lookups_total", "result" => result).increment(1);
 }
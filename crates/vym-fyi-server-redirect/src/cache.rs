@@ -0,0 +1,254 @@
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
+
+use chrono::{DateTime, Duration, Utc};
+use lru::LruCache;
+use vym_fyi_model::models::errors::{AppError, AppResult};
+use vym_fyi_model::services::repos::ResolvedLink;
+
+/// Size and lifetimes of the slug resolution cache.
+#[derive(Debug, Clone, Copy)]
+pub struct SlugCacheConfig {
+    /// Maximum number of cached slugs; `0` disables the cache.
+    pub capacity: usize,
+    /// How long a resolved link is served from cache.
+    pub ttl: Duration,
+    /// How long an unknown/inactive slug is remembered as missing.
+    pub negative_ttl: Duration,
+}
+
+impl Default for SlugCacheConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 10_000,
+            ttl: Duration::seconds(30),
+            negative_ttl: Duration::seconds(5),
+        }
+    }
+}
+
+impl SlugCacheConfig {
+    /// Read `REDIRECT_CACHE_CAPACITY`, `REDIRECT_CACHE_TTL_SECS` and
+    /// `REDIRECT_CACHE_NEGATIVE_TTL_SECS`, falling back to the defaults.
+    pub fn from_env() -> AppResult<Self> {
+        fn var(name: &str) -> AppResult<Option<i64>> {
+            match std::env::var(name) {
+                Ok(raw) => raw
+                    .trim()
+                    .parse::<i64>()
+                    .ok()
+                    .filter(|v| *v >= 0)
+                    .map(Some)
+                    .ok_or_else(|| AppError::Config(format!("invalid {}: {}", name, raw))),
+                Err(_) => Ok(None),
+            }
+        }
+
+        let defaults = Self::default();
+        Ok(Self {
+            capacity: var("REDIRECT_CACHE_CAPACITY")?.map_or(defaults.capacity, |v| v as usize),
+            ttl: var("REDIRECT_CACHE_TTL_SECS")?.map_or(defaults.ttl, Duration::seconds),
+            negative_ttl: var("REDIRECT_CACHE_NEGATIVE_TTL_SECS")?
+                .map_or(defaults.negative_ttl, Duration::seconds),
+        })
+    }
+}
+
+/// Result of a cache lookup.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CacheLookup {
+    /// The slug resolves to this link.
+    Hit(ResolvedLink),
+    /// The slug is known not to resolve.
+    NegativeHit,
+    Miss,
+}
+
+struct CacheEntry {
+    link: Option<ResolvedLink>,
+    valid_until: DateTime<Utc>,
+}
+
+/// Bounded LRU cache of slug resolutions, with positive and negative
+/// entries. Positive entries never outlive the link's `expires_at`.
+pub struct SlugCache {
+    config: SlugCacheConfig,
+    entries: Mutex<LruCache<String, CacheEntry>>,
+}
+
+impl SlugCache {
+    /// Create a cache, or `None` when the configured capacity is `0`.
+    pub fn new(config: SlugCacheConfig) -> Option<Self> {
+        let capacity = NonZeroUsize::new(config.capacity)?;
+        Some(Self {
+            config,
+            entries: Mutex::new(LruCache::new(capacity)),
+        })
+    }
+
+    pub fn get(&self, slug: &str, now: DateTime<Utc>) -> CacheLookup {
+        let mut entries = self.lock();
+        match entries.get(slug) {
+            Some(entry) if entry.valid_until > now => match &entry.link {
+                Some(link) => CacheLookup::Hit(link.clone()),
+                None => CacheLookup::NegativeHit,
+            },
+            Some(_) => {
+                entries.pop(slug);
+                CacheLookup::Miss
+            }
+            None => CacheLookup::Miss,
+        }
+    }
+
+    /// Remember the resolution of `slug` as of `now`.
+    pub fn insert(&self, slug: &str, link: Option<ResolvedLink>, now: DateTime<Utc>) {
+        let valid_until = match &link {
+            Some(link) => {
+                let until = now + self.config.ttl;
+                link.expires_at.map_or(until, |expires| expires.min(until))
+            }
+            None => now + self.config.negative_ttl,
+        };
+        if valid_until <= now {
+            return;
+        }
+
+        self.lock()
+            .put(slug.to_owned(), CacheEntry { link, valid_until });
+    }
+
+    /// Drop the cached resolution of `slug`, if any.
+    pub fn invalidate(&self, slug: &str) {
+        self.lock().pop(slug);
+    }
+
+    /// Drop every cached resolution.
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, LruCache<String, CacheEntry>> {
+        // The cache holds no invariants worth failing requests over.
+        self.entries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vym_fyi_model::models::url_shortener::{QueryPassthrough, RedirectType, TenantStatus};
+    use vym_fyi_model::services::link_variants::LinkVariants;
+    use vym_fyi_model::services::param_template::ParamTemplate;
+
+    fn cache(capacity: usize) -> SlugCache {
+        SlugCache::new(SlugCacheConfig {
+            capacity,
+            ttl: Duration::seconds(30),
+            negative_ttl: Duration::seconds(5),
+        })
+        .expect("non-zero capacity")
+    }
+
+    fn link(expires_at: Option<DateTime<Utc>>) -> ResolvedLink {
+        ResolvedLink {
+            target_url: "https://example.com".into(),
+            tenant_status: TenantStatus::Active,
+            tenant_id: None,
+            expires_at,
+            redirect_type: RedirectType::default(),
+            query_passthrough: QueryPassthrough::Off,
+            path_passthrough: false,
+            param_template: ParamTemplate::default(),
+            tenant_name: None,
+            rules: Vec::new(),
+            variants: LinkVariants::default(),
+        }
+    }
+
+    #[test]
+    fn zero_capacity_disables_cache() {
+        assert!(
+            SlugCache::new(SlugCacheConfig {
+                capacity: 0,
+                ..SlugCacheConfig::default()
+            })
+            .is_none()
+        );
+    }
+
+    #[test]
+    fn serves_positive_and_negative_entries_until_ttl() {
+        let cache = cache(10);
+        let now = Utc::now();
+        cache.insert("a", Some(link(None)), now);
+        cache.insert("missing", None, now);
+
+        assert_eq!(cache.get("a", now), CacheLookup::Hit(link(None)));
+        assert_eq!(cache.get("missing", now), CacheLookup::NegativeHit);
+        assert_eq!(cache.get("other", now), CacheLookup::Miss);
+
+        let later = now + Duration::seconds(6);
+        assert_eq!(cache.get("a", later), CacheLookup::Hit(link(None)));
+        assert_eq!(cache.get("missing", later), CacheLookup::Miss);
+        assert_eq!(
+            cache.get("a", now + Duration::seconds(31)),
+            CacheLookup::Miss
+        );
+    }
+
+    #[test]
+    fn never_serves_links_past_expires_at() {
+        let cache = cache(10);
+        let now = Utc::now();
+        let expiring = link(Some(now + Duration::seconds(2)));
+        cache.insert("a", Some(expiring.clone()), now);
+
+        assert_eq!(
+            cache.get("a", now + Duration::seconds(1)),
+            CacheLookup::Hit(expiring)
+        );
+        assert_eq!(
+            cache.get("a", now + Duration::seconds(2)),
+            CacheLookup::Miss
+        );
+
+        cache.insert("b", Some(link(Some(now))), now);
+        assert_eq!(cache.get("b", now), CacheLookup::Miss);
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let cache = cache(2);
+        let now = Utc::now();
+        cache.insert("a", None, now);
+        cache.insert("b", None, now);
+        cache.get("a", now);
+        cache.insert("c", None, now);
+
+        assert_eq!(cache.get("b", now), CacheLookup::Miss);
+        assert_eq!(cache.get("a", now), CacheLookup::NegativeHit);
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[test]
+    fn invalidates_single_slugs_or_everything() {
+        let cache = cache(10);
+        let now = Utc::now();
+        cache.insert("a", None, now);
+        cache.insert("b", Some(link(None)), now);
+
+        cache.invalidate("a");
+        assert_eq!(cache.get("a", now), CacheLookup::Miss);
+        assert_eq!(cache.get("b", now), CacheLookup::Hit(link(None)));
+
+        cache.clear();
+        assert_eq!(cache.len(), 0);
+    }
+}
@@ -0,0 +1,222 @@
+use std::time::Duration;
+
+use axum::http::{
+    HeaderMap,
+    header::{REFERER, USER_AGENT},
+};
+use chrono::Utc;
+use tokio::sync::mpsc::{self, Receiver, Sender, error::TrySendError};
+use tracing::{debug, error, info};
+use vym_fyi_model::models::errors::{AppError, AppResult};
+use vym_fyi_model::services::repos::{ClickEvent, ClickRepository, ResolvedLink};
+use vym_fyi_model::services::user_agent::UserAgentClass;
+
+/// Queue and batching settings for click recording.
+#[derive(Debug, Clone, Copy)]
+pub struct ClickWriterConfig {
+    /// Maximum queued events; `0` disables click recording.
+    pub queue_capacity: usize,
+    pub batch_size: usize,
+    pub flush_interval: Duration,
+}
+
+impl Default for ClickWriterConfig {
+    fn default() -> Self {
+        Self {
+            queue_capacity: 10_000,
+            batch_size: 500,
+            flush_interval: Duration::from_secs(1),
+        }
+    }
+}
+
+impl ClickWriterConfig {
+    /// Read `CLICK_QUEUE_CAPACITY`, `CLICK_BATCH_SIZE` and `CLICK_FLUSH_MS`,
+    /// falling back to the defaults.
+    pub fn from_env() -> AppResult<Self> {
+        fn var(name: &str) -> AppResult<Option<u64>> {
+            match std::env::var(name) {
+                Ok(raw) => raw
+                    .trim()
+                    .parse::<u64>()
+                    .map(Some)
+                    .map_err(|e| AppError::Config(format!("invalid {} {}: {}", name, raw, e))),
+                Err(_) => Ok(None),
+            }
+        }
+
+        let defaults = Self::default();
+        Ok(Self {
+            queue_capacity: var("CLICK_QUEUE_CAPACITY")?
+                .map_or(defaults.queue_capacity, |v| v as usize),
+            batch_size: var("CLICK_BATCH_SIZE")?
+                .map_or(defaults.batch_size, |v| (v as usize).max(1)),
+            flush_interval: var("CLICK_FLUSH_MS")?
+                .map_or(defaults.flush_interval, |v| Duration::from_millis(v.max(1))),
+        })
+    }
+}
+
+/// Non-blocking handle used on the redirect path to enqueue click events.
+/// When the queue is full, events are dropped and counted.
+#[derive(Clone)]
+pub struct ClickRecorder {
+    sender: Option<Sender<ClickEvent>>,
+}
+
+impl ClickRecorder {
+    /// Start the background writer and return the recorder feeding it.
+    pub fn spawn(config: ClickWriterConfig, repo: ClickRepository) -> Self {
+        if config.queue_capacity == 0 {
+            info!("click recording disabled (CLICK_QUEUE_CAPACITY=0)");
+            return Self::disabled();
+        }
+
+        let (sender, receiver) = mpsc::channel(config.queue_capacity);
+        tokio::spawn(run_writer(receiver, repo, config));
+        Self {
+            sender: Some(sender),
+        }
+    }
+
+    /// Recorder that drops every event.
+    pub fn disabled() -> Self {
+        Self { sender: None }
+    }
+
+    pub fn record(
+        &self,
+        slug: &str,
+        link: &ResolvedLink,
+        variant: Option<&str>,
+        headers: &HeaderMap,
+    ) {
+        let Some(sender) = &self.sender else {
+            return;
+        };
+
+        let event = ClickEvent {
+            slug: slug.to_owned(),
+            tenant_id: link.tenant_id,
+            clicked_at: Utc::now(),
+            referrer: headers
+                .get(REFERER)
+                .and_then(|h| h.to_str().ok())
+                .and_then(referrer_host),
+            user_agent_class: classify_user_agent(
+                headers.get(USER_AGENT).and_then(|h| h.to_str().ok()),
+            )
+            .to_owned(),
+            country: None,
+            variant: variant.map(str::to_owned),
+        };
+
+        match sender.try_send(event) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => {
+                metrics::counter!("redirect_clicks_dropped_total", "reason" => "queue_full")
+                    .increment(1);
+            }
+            Err(TrySendError::Closed(_)) => {
+                metrics::counter!("redirect_clicks_dropped_total", "reason" => "writer_stopped")
+                    .increment(1);
+            }
+        }
+    }
+}
+
+async fn run_writer(
+    mut receiver: Receiver<ClickEvent>,
+    repo: ClickRepository,
+    config: ClickWriterConfig,
+) {
+    let mut batch = Vec::with_capacity(config.batch_size);
+    loop {
+        // Wait for the first event, then collect until the batch is full or
+        // the flush interval elapses.
+        let Some(first) = receiver.recv().await else {
+            return;
+        };
+        batch.push(first);
+
+        let deadline = tokio::time::Instant::now() + config.flush_interval;
+        while batch.len() < config.batch_size {
+            match tokio::time::timeout_at(deadline, receiver.recv()).await {
+                Ok(Some(event)) => batch.push(event),
+                Ok(None) | Err(_) => break,
+            }
+        }
+
+        let count = batch.len() as u64;
+        match repo.insert_batch(&batch).await {
+            Ok(()) => {
+                debug!("recorded {} click events", count);
+                metrics::counter!("redirect_clicks_recorded_total").increment(count);
+            }
+            Err(e) => {
+                error!("failed to record {} click events: {}", count, e);
+                metrics::counter!("redirect_clicks_dropped_total", "reason" => "write_error")
+                    .increment(count);
+            }
+        }
+        batch.clear();
+    }
+}
+
+/// Host part of a `Referer` URL, lowercased.
+fn referrer_host(referer: &str) -> Option<String> {
+    let rest = referer.split_once("://").map_or(referer, |(_, rest)| rest);
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let host = authority.rsplit('@').next()?;
+    let host = host.split(':').next()?;
+    (!host.is_empty()).then(|| host.to_ascii_lowercase())
+}
+
+/// Coarse user-agent class (`bot`, `mobile`, `desktop` or `other`); keeps
+/// analytics free of raw user-agent strings. Scripts count as bots.
+fn classify_user_agent(user_agent: Option<&str>) -> &'static str {
+    match UserAgentClass::classify(user_agent) {
+        UserAgentClass::Bot | UserAgentClass::Cli => "bot",
+        UserAgentClass::Ios | UserAgentClass::Android | UserAgentClass::Mobile => "mobile",
+        UserAgentClass::Desktop => "desktop",
+        UserAgentClass::Unknown | UserAgentClass::Other => "other",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn referrer_host_extracts_lowercased_host() {
+        assert_eq!(
+            referrer_host("https://News.Example.com:443/a?b#c").as_deref(),
+            Some("news.example.com")
+        );
+        assert_eq!(
+            referrer_host("http://user@example.org/").as_deref(),
+            Some("example.org")
+        );
+        assert_eq!(referrer_host("https:///path"), None);
+    }
+
+    #[test]
+    fn classifies_user_agents() {
+        assert_eq!(classify_user_agent(None), "other");
+        assert_eq!(classify_user_agent(Some("curl/8.5.0")), "bot");
+        assert_eq!(
+            classify_user_agent(Some("Mozilla/5.0 (compatible; Googlebot/2.1)")),
+            "bot"
+        );
+        assert_eq!(
+            classify_user_agent(Some(
+                "Mozilla/5.0 (iPhone; CPU iPhone OS 17_0 like Mac OS X) Mobile/15E148"
+            )),
+            "mobile"
+        );
+        assert_eq!(
+            classify_user_agent(Some("Mozilla/5.0 (X11; Linux x86_64) Firefox/128.0")),
+            "desktop"
+        );
+    }
+}
@@ -0,0 +1,256 @@
+use std::net::IpAddr;
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use lru::LruCache;
+use tracing::warn;
+use vym_fyi_model::models::errors::{AppError, AppResult};
+use vym_fyi_model::services::client_ip::client_network;
+
+/// Thresholds for detecting clients that scan `/{slug}` for valid links.
+#[derive(Debug, Clone, Copy)]
+pub struct EnumerationGuardConfig {
+    /// Window over which a client's 404s are counted.
+    pub window: Duration,
+    /// 404s per window after which further 404s are delayed; `0` disables.
+    pub tarpit_after: u32,
+    pub tarpit_delay: Duration,
+    /// 404s per window after which the client is blocked; `0` disables.
+    pub block_after: u32,
+    pub block_for: Duration,
+    /// Maximum number of clients tracked at once (least recently seen are
+    /// dropped first).
+    pub tracked_clients: usize,
+}
+
+impl Default for EnumerationGuardConfig {
+    fn default() -> Self {
+        Self {
+            window: Duration::from_secs(60),
+            tarpit_after: 10,
+            tarpit_delay: Duration::from_secs(1),
+            block_after: 30,
+            block_for: Duration::from_secs(600),
+            tracked_clients: 100_000,
+        }
+    }
+}
+
+impl EnumerationGuardConfig {
+    /// Read `ENUMERATION_WINDOW_SECS`, `ENUMERATION_TARPIT_AFTER`,
+    /// `ENUMERATION_TARPIT_MS`, `ENUMERATION_BLOCK_AFTER`,
+    /// `ENUMERATION_BLOCK_SECS` and `ENUMERATION_TRACKED_CLIENTS`, falling
+    /// back to the defaults.
+    pub fn from_env() -> AppResult<Self> {
+        fn var(name: &str) -> AppResult<Option<u64>> {
+            match std::env::var(name) {
+                Ok(raw) => raw
+                    .trim()
+                    .parse::<u64>()
+                    .map(Some)
+                    .map_err(|_| AppError::Config(format!("invalid {}: {}", name, raw))),
+                Err(_) => Ok(None),
+            }
+        }
+        fn count(name: &str, default: u32) -> AppResult<u32> {
+            var(name)?.map_or(Ok(default), |v| {
+                u32::try_from(v).map_err(|_| AppError::Config(format!("invalid {}: {}", name, v)))
+            })
+        }
+
+        let defaults = Self::default();
+        Ok(Self {
+            window: var("ENUMERATION_WINDOW_SECS")?.map_or(defaults.window, Duration::from_secs),
+            tarpit_after: count("ENUMERATION_TARPIT_AFTER", defaults.tarpit_after)?,
+            tarpit_delay: var("ENUMERATION_TARPIT_MS")?
+                .map_or(defaults.tarpit_delay, Duration::from_millis),
+            block_after: count("ENUMERATION_BLOCK_AFTER", defaults.block_after)?,
+            block_for: var("ENUMERATION_BLOCK_SECS")?
+                .map_or(defaults.block_for, Duration::from_secs),
+            tracked_clients: var("ENUMERATION_TRACKED_CLIENTS")?
+                .map_or(defaults.tracked_clients, |v| v as usize),
+        })
+    }
+}
+
+/// What to do with a request whose slug did not resolve.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MissAction {
+    Allow,
+    /// Delay the response by this long.
+    Tarpit(Duration),
+    /// The client is now blocked for this long.
+    Block(Duration),
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ClientState {
+    window_start: Instant,
+    misses: u32,
+    blocked_until: Option<Instant>,
+}
+
+/// Tracks per-client 404 rates on the redirect endpoint and decides when to
+/// tarpit or block a client that looks like it is enumerating slugs.
+///
+/// IPv6 clients are tracked per /64, since a single host usually controls a
+/// whole /64.
+pub struct EnumerationGuard {
+    config: EnumerationGuardConfig,
+    clients: Mutex<LruCache<IpAddr, ClientState>>,
+}
+
+impl EnumerationGuard {
+    /// Create a guard, or `None` when both tarpitting and blocking are
+    /// disabled or no clients may be tracked.
+    pub fn new(config: EnumerationGuardConfig) -> Option<Self> {
+        if config.tarpit_after == 0 && config.block_after == 0 {
+            return None;
+        }
+        let capacity = NonZeroUsize::new(config.tracked_clients)?;
+        Some(Self {
+            config,
+            clients: Mutex::new(LruCache::new(capacity)),
+        })
+    }
+
+    /// Time left on the client's block, if it is blocked.
+    pub fn blocked_for(&self, ip: IpAddr, now: Instant) -> Option<Duration> {
+        let mut clients = self.lock();
+        let state = clients.get(&client_network(ip))?;
+        state
+            .blocked_until
+            .map(|until| until.saturating_duration_since(now))
+            .filter(|left| !left.is_zero())
+    }
+
+    /// Count a 404 for the client and decide how to answer it.
+    pub fn record_miss(&self, ip: IpAddr, now: Instant) -> MissAction {
+        let key = client_network(ip);
+        let mut clients = self.lock();
+        let state = clients.get_or_insert_mut(key, || ClientState {
+            window_start: now,
+            misses: 0,
+            blocked_until: None,
+        });
+
+        if now.saturating_duration_since(state.window_start) >= self.config.window {
+            state.window_start = now;
+            state.misses = 0;
+        }
+        state.misses = state.misses.saturating_add(1);
+
+        if self.config.block_after > 0 && state.misses >= self.config.block_after {
+            state.blocked_until = Some(now + self.config.block_for);
+            state.window_start = now;
+            state.misses = 0;
+            warn!(
+                "suspected slug enumeration from {}: blocking for {:?}",
+                key, self.config.block_for
+            );
+            record_suspected("block");
+            return MissAction::Block(self.config.block_for);
+        }
+
+        if self.config.tarpit_after > 0 && state.misses > self.config.tarpit_after {
+            if state.misses == self.config.tarpit_after + 1 {
+                warn!("suspected slug enumeration from {}: tarpitting", key);
+                record_suspected("tarpit");
+            }
+            return MissAction::Tarpit(self.config.tarpit_delay);
+        }
+
+        MissAction::Allow
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, LruCache<IpAddr, ClientState>> {
+        self.clients
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+fn record_suspected(action: &'static str) {
+    metrics::counter!("redirect_enumeration_suspected_total", "action" => action).increment(1);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn guard() -> EnumerationGuard {
+        EnumerationGuard::new(EnumerationGuardConfig {
+            window: Duration::from_secs(60),
+            tarpit_after: 2,
+            tarpit_delay: Duration::from_millis(500),
+            block_after: 4,
+            block_for: Duration::from_secs(300),
+            tracked_clients: 16,
+        })
+        .expect("guard is enabled")
+    }
+
+    #[test]
+    fn escalates_from_tarpit_to_block() {
+        let guard = guard();
+        let ip: IpAddr = "203.0.113.7".parse().unwrap();
+        let now = Instant::now();
+
+        assert_eq!(guard.record_miss(ip, now), MissAction::Allow);
+        assert_eq!(guard.record_miss(ip, now), MissAction::Allow);
+        assert_eq!(
+            guard.record_miss(ip, now),
+            MissAction::Tarpit(Duration::from_millis(500))
+        );
+        assert_eq!(guard.blocked_for(ip, now), None);
+        assert_eq!(
+            guard.record_miss(ip, now),
+            MissAction::Block(Duration::from_secs(300))
+        );
+
+        let later = now + Duration::from_secs(100);
+        assert_eq!(guard.blocked_for(ip, later), Some(Duration::from_secs(200)));
+        assert_eq!(guard.blocked_for(ip, now + Duration::from_secs(300)), None);
+
+        let other: IpAddr = "203.0.113.8".parse().unwrap();
+        assert_eq!(guard.blocked_for(other, later), None);
+    }
+
+    #[test]
+    fn misses_reset_after_window() {
+        let guard = guard();
+        let ip: IpAddr = "203.0.113.7".parse().unwrap();
+        let now = Instant::now();
+
+        guard.record_miss(ip, now);
+        guard.record_miss(ip, now);
+        let later = now + Duration::from_secs(61);
+        assert_eq!(guard.record_miss(ip, later), MissAction::Allow);
+    }
+
+    #[test]
+    fn tracks_ipv6_clients_per_64() {
+        let guard = guard();
+        let now = Instant::now();
+        for suffix in 1..=4 {
+            let ip: IpAddr = format!("2001:db8:1:2::{}", suffix).parse().unwrap();
+            guard.record_miss(ip, now);
+        }
+
+        let same_64: IpAddr = "2001:db8:1:2:ffff::1".parse().unwrap();
+        let other_64: IpAddr = "2001:db8:1:3::1".parse().unwrap();
+        assert!(guard.blocked_for(same_64, now).is_some());
+        assert!(guard.blocked_for(other_64, now).is_none());
+    }
+
+    #[test]
+    fn disabled_when_no_stage_is_enabled() {
+        let config = EnumerationGuardConfig {
+            tarpit_after: 0,
+            block_after: 0,
+            ..Default::default()
+        };
+        assert!(EnumerationGuard::new(config).is_none());
+    }
+}
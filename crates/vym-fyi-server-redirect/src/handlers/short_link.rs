@@ -1,14 +1,40 @@
+use std::time::Instant;
+
 use axum::{
-    extract::{Path, State},
-    http::{HeaderValue, header::CACHE_CONTROL},
-    response::{IntoResponse, Redirect, Response},
+    Extension,
+    extract::{Path, RawQuery, State},
+    http::{
+        HeaderMap, HeaderValue, StatusCode,
+        header::{
+            ACCEPT_LANGUAGE, CACHE_CONTROL, COOKIE, LOCATION, RETRY_AFTER, SET_COOKIE, USER_AGENT,
+        },
+    },
+    response::{IntoResponse, Response},
 };
+use chrono::{DateTime, Utc};
 use tracing::{debug, error};
 
-use vym_fyi_model::services::repos::ShortLinkRepository;
+use vym_fyi_model::models::url_shortener::{RedirectType, TenantStatus};
+use vym_fyi_model::services::axum_metrics::{OTHER_LABEL, metrics_labels};
+use vym_fyi_model::services::client_ip::ClientIp;
+use vym_fyi_model::services::link_rules::Visit;
+use vym_fyi_model::services::slug_policy::is_reserved_slug;
 use vym_fyi_model::services::static_assets;
 
 use crate::RedirectApp;
+use crate::enumeration::MissAction;
+use crate::target::{choose_variant, redirect_target};
+
+/// How long shared caches and browsers may keep a permanent redirect. Kept
+/// short-ish so a retargeted or disabled link is picked up within a day.
+const PERMANENT_REDIRECT_MAX_AGE_SECS: i64 = 86_400;
+
+/// Cookie remembering a visitor's variant of a sticky A/B link. It is
+/// scoped to the link's path, so each link keeps its own assignment.
+const VARIANT_COOKIE: &str = "vym_variant";
+
+/// How long a sticky variant assignment is remembered.
+const VARIANT_COOKIE_MAX_AGE_SECS: u32 = 30 * 86_400;
 
 /// Redirect endpoint skeleton.
 ///
@@ -17,48 +43,223 @@ use crate::RedirectApp;
 pub async fn redirect_short_link(
     Path(slug): Path<String>,
     State(app): State<RedirectApp>,
+    RawQuery(query): RawQuery,
+    client_ip: Option<Extension<ClientIp>>,
+    headers: HeaderMap,
 ) -> Response {
-    debug!("Redirect requested: slug={}", slug);
-    // Bucket slug lengths to avoid unbounded label cardinality from user input.
+    redirect(app, slug, None, query, client_ip, headers).await
+}
+
+/// `/{slug}/{*rest}`: only prefix links (`path_passthrough`) match; for any
+/// other link the request is a miss.
+pub async fn redirect_short_link_with_path(
+    Path((slug, rest)): Path<(String, String)>,
+    State(app): State<RedirectApp>,
+    RawQuery(query): RawQuery,
+    client_ip: Option<Extension<ClientIp>>,
+    headers: HeaderMap,
+) -> Response {
+    redirect(app, slug, Some(rest), query, client_ip, headers).await
+}
+
+async fn redirect(
+    app: RedirectApp,
+    slug: String,
+    rest: Option<String>,
+    query: Option<String>,
+    client_ip: Option<Extension<ClientIp>>,
+    headers: HeaderMap,
+) -> Response {
+    debug!("Redirect requested: slug={} rest={:?}", slug, rest);
+    let client_ip = client_ip.map(|Extension(ClientIp(ip))| ip);
+    let guard = app.enumeration_guard().zip(client_ip);
+
+    if let Some(retry_after) = guard.and_then(|(guard, ip)| guard.blocked_for(ip, Instant::now())) {
+        debug!("Rejecting slug={} from blocked client", slug);
+        metrics::counter!("redirect_enumeration_blocked_requests_total").increment(1);
+        return (
+            StatusCode::TOO_MANY_REQUESTS,
+            [
+                (CACHE_CONTROL, HeaderValue::from_static("no-store")),
+                (RETRY_AFTER, HeaderValue::from(retry_after.as_secs().max(1))),
+            ],
+        )
+            .into_response();
+    }
+
+    // Reserved names can never be links; answer without touching the DB.
+    if is_reserved_slug(&slug) {
+        debug!("Reserved slug={} requested", slug);
+        return not_found().await;
+    }
+
+    // Only allow-listed slugs get their own label; slug lengths are bucketed,
+    // so random paths cannot create new series.
+    let slug_label = metrics_labels().slug_label(&slug);
     let slug_counter = metrics::counter!(
         "redirect_slug_requests_total",
-        "slug" => slug.clone(),
+        "slug" => slug_label.clone(),
         "slug_len" => bucket_slug_len(slug.len())
     );
     slug_counter.increment(1);
 
-    let repo: ShortLinkRepository = app.short_link_repository();
-    let result = repo.resolve(&slug).await;
+    let result = app.resolve_slug(&slug).await;
+
+    // Resolve the final target up front so a path suffix that does not fit
+    // the link is handled like an unknown slug.
+    let header = |name| headers.get(name).and_then(|v| v.to_str().ok());
+    let visit = Visit::new(header(ACCEPT_LANGUAGE), header(USER_AGENT), Utc::now());
+    let assigned = assigned_variant(&headers);
+    let result = result.map(|link| {
+        link.and_then(|link| {
+            let variant = choose_variant(&link, &visit, assigned).cloned();
+            let target = redirect_target(
+                &slug,
+                &link,
+                &visit,
+                variant.as_ref(),
+                rest.as_deref(),
+                query.as_deref(),
+            )?;
+            Some((link, variant, target))
+        })
+    });
 
     match result {
-        Ok(Some(target)) => {
-            debug!("Redirecting slug={} to {}", slug, target);
-            let mut response = Redirect::temporary(&target).into_response();
-            response.headers_mut().insert(
-                CACHE_CONTROL,
-                HeaderValue::from_static("public, max-age=60"),
+        Ok(Some((link, _, _))) if link.tenant_status == TenantStatus::Suspended => {
+            debug!("Tenant of slug={} is suspended", slug);
+            let mut response = static_assets::suspended().await;
+            response
+                .headers_mut()
+                .insert(CACHE_CONTROL, HeaderValue::from_static("no-store"));
+            response
+        }
+        Ok(Some((link, variant, target))) => {
+            let Ok(location) = HeaderValue::from_str(&target) else {
+                error!("Target of slug={} is not a valid Location header", slug);
+                return internal_error().await;
+            };
+            debug!(
+                "Redirecting slug={} to {} with {}",
+                slug,
+                target,
+                link.redirect_type.status_code()
             );
+            let variant_name = variant.as_ref().map(|v| v.name.as_str());
+            if let Some(name) = variant_name {
+                // Variant names are free-form, so they only become labels
+                // for allow-listed slugs.
+                let variant_label = if slug_label == slug {
+                    name.to_string()
+                } else {
+                    OTHER_LABEL.to_string()
+                };
+                metrics::counter!(
+                    "redirect_variant_requests_total",
+                    "slug" => slug_label,
+                    "variant" => variant_label
+                )
+                .increment(1);
+            }
+            app.click_recorder()
+                .record(&slug, &link, variant_name, &headers);
+            let status = StatusCode::from_u16(link.redirect_type.status_code())
+                .expect("redirect types are valid status codes");
+            // Links with rules or variants answer differently per visitor
+            // and over time, so no cache may keep them, whatever the
+            // redirect type.
+            let cache_control = if link.rules.is_empty() && link.variants.is_empty() {
+                cache_control(link.redirect_type, link.expires_at, visit.at)
+            } else {
+                HeaderValue::from_static("no-store")
+            };
+            let mut response = (
+                status,
+                [(LOCATION, location), (CACHE_CONTROL, cache_control)],
+            )
+                .into_response();
+            if link.variants.sticky
+                && let Some(name) = variant_name.filter(|name| assigned != Some(*name))
+                && let Some(cookie) = variant_cookie(&slug, name)
+            {
+                response.headers_mut().insert(SET_COOKIE, cookie);
+            }
             response
         }
         Ok(None) => {
             debug!("No active short link found for slug={}", slug);
-            let mut response = static_assets::not_found().await;
-            response
-                .headers_mut()
-                .insert(CACHE_CONTROL, HeaderValue::from_static("no-store"));
-            response
+            // Slow down clients probing for valid slugs; the request that
+            // trips the block is still answered with a plain 404.
+            if let Some((guard, ip)) = guard
+                && let MissAction::Tarpit(delay) = guard.record_miss(ip, Instant::now())
+            {
+                tokio::time::sleep(delay).await;
+            }
+            not_found().await
         }
         Err(e) => {
             error!("Database error while resolving slug {}: {}", slug, e);
-            let mut response = static_assets::internal_error().await;
-            response
-                .headers_mut()
-                .insert(CACHE_CONTROL, HeaderValue::from_static("no-store"));
-            response
+            internal_error().await
         }
     }
 }
 
+/// Cache policy matching the redirect type. Temporary redirects (campaign
+/// links, click counting) must reach the server every time; permanent ones
+/// may be cached, but never past the link's expiry.
+fn cache_control(
+    redirect_type: RedirectType,
+    expires_at: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> HeaderValue {
+    if !redirect_type.is_permanent() {
+        return HeaderValue::from_static("no-store");
+    }
+    let max_age = expires_at.map_or(PERMANENT_REDIRECT_MAX_AGE_SECS, |expires| {
+        (expires - now)
+            .num_seconds()
+            .clamp(0, PERMANENT_REDIRECT_MAX_AGE_SECS)
+    });
+    HeaderValue::from_str(&format!("public, max-age={}", max_age)).expect("ASCII header value")
+}
+
+/// Variant remembered in the request's cookies, if any.
+fn assigned_variant(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get_all(COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(name, _)| *name == VARIANT_COOKIE)
+        .map(|(_, value)| value)
+}
+
+/// `Set-Cookie` value remembering `variant` for the link's path.
+fn variant_cookie(slug: &str, variant: &str) -> Option<HeaderValue> {
+    HeaderValue::from_str(&format!(
+        "{}={}; Path=/{}; Max-Age={}; HttpOnly; SameSite=Lax",
+        VARIANT_COOKIE, variant, slug, VARIANT_COOKIE_MAX_AGE_SECS
+    ))
+    .ok()
+}
+
+async fn internal_error() -> Response {
+    let mut response = static_assets::internal_error().await;
+    response
+        .headers_mut()
+        .insert(CACHE_CONTROL, HeaderValue::from_static("no-store"));
+    response
+}
+
+async fn not_found() -> Response {
+    let mut response = static_assets::not_found().await;
+    response
+        .headers_mut()
+        .insert(CACHE_CONTROL, HeaderValue::from_static("no-store"));
+    response
+}
+
 fn bucket_slug_len(len: usize) -> &'static str {
     match len {
         0 => "len_0",
@@ -69,3 +270,47 @@ fn bucket_slug_len(len: usize) -> &'static str {
         _ => "len_over_20",
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn cache_policy_follows_redirect_type() {
+        let now = Utc::now();
+        for temporary in [RedirectType::Found, RedirectType::TemporaryRedirect] {
+            assert_eq!(cache_control(temporary, None, now), "no-store");
+        }
+        assert_eq!(
+            cache_control(RedirectType::MovedPermanently, None, now),
+            "public, max-age=86400"
+        );
+        assert_eq!(
+            cache_control(
+                RedirectType::PermanentRedirect,
+                Some(now + Duration::seconds(90)),
+                now
+            ),
+            "public, max-age=90"
+        );
+    }
+
+    #[test]
+    fn variant_cookie_round_trips() {
+        let cookie = variant_cookie("promo", "b").unwrap();
+        assert_eq!(
+            cookie,
+            "vym_variant=b; Path=/promo; Max-Age=2592000; HttpOnly; SameSite=Lax"
+        );
+
+        let mut headers = HeaderMap::new();
+        assert_eq!(assigned_variant(&headers), None);
+        headers.append(COOKIE, HeaderValue::from_static("session=1"));
+        headers.append(
+            COOKIE,
+            HeaderValue::from_static("theme=dark; vym_variant=b"),
+        );
+        assert_eq!(assigned_variant(&headers), Some("b"));
+    }
+}
@@ -0,0 +1,99 @@
+use std::time::Duration;
+
+use sqlx::postgres::PgListener;
+use sqlx::{Pool, Postgres};
+use tracing::{debug, info, warn};
+use vym_fyi_model::models::errors::AppResult;
+use vym_fyi_model::services::repos::SHORT_LINK_CHANGES_CHANNEL;
+
+use crate::app::RedirectApp;
+use crate::cache::SlugCache;
+
+const MIN_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Spawn a task that keeps a `LISTEN` connection on the short-link change
+/// channel and evicts affected slugs from the cache. While disconnected the
+/// cache falls back to TTL-only expiry. Does nothing when the cache is off
+/// or `CACHE_INVALIDATION_DATABASE_URL` is not set.
+pub fn spawn_cache_invalidation_listener(app: &RedirectApp) {
+    let (Some(cache), Some(pool)) = (app.slug_cache(), app.cache_invalidation_pool().cloned())
+    else {
+        return;
+    };
+
+    tokio::spawn(async move {
+        let mut backoff = MIN_BACKOFF;
+        loop {
+            match listen(&pool, &cache).await {
+                Ok(()) => backoff = MIN_BACKOFF,
+                Err(e) => warn!(
+                    "cache invalidation listener failed: {}; retrying in {:?}",
+                    e, backoff
+                ),
+            }
+            metrics::gauge!("redirect_cache_listener_connected").set(0.0);
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+        }
+    });
+}
+
+/// Listen until the connection fails. Returns `Ok(())` when the connection
+/// was lost after being established.
+async fn listen(pool: &Pool<Postgres>, cache: &SlugCache) -> AppResult<()> {
+    let mut listener = PgListener::connect_with(pool).await?;
+    listener.listen(SHORT_LINK_CHANGES_CHANNEL).await?;
+
+    // Changes made while we were not listening were missed.
+    cache.clear();
+    metrics::gauge!("redirect_cache_listener_connected").set(1.0);
+    info!(
+        "listening for short link changes on {}",
+        SHORT_LINK_CHANGES_CHANNEL
+    );
+
+    loop {
+        match listener.try_recv().await? {
+            Some(notification) => apply(cache, notification.payload()),
+            None => {
+                warn!("cache invalidation listener lost its connection");
+                return Ok(());
+            }
+        }
+    }
+}
+
+fn apply(cache: &SlugCache, payload: &str) {
+    if payload.is_empty() {
+        debug!("cache invalidation: clearing all slugs");
+        cache.clear();
+        metrics::counter!("redirect_cache_invalidations_total", "scope" => "all").increment(1);
+    } else {
+        debug!("cache invalidation: slug={}", payload);
+        cache.invalidate(payload);
+        metrics::counter!("redirect_cache_invalidations_total", "scope" => "slug").increment(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::{CacheLookup, SlugCacheConfig};
+    use chrono::Utc;
+
+    #[test]
+    fn apply_evicts_slug_or_clears_on_empty_payload() {
+        let cache = SlugCache::new(SlugCacheConfig::default()).unwrap();
+        let now = Utc::now();
+        cache.insert("a", None, now);
+        cache.insert("b", None, now);
+
+        apply(&cache, "a");
+        assert_eq!(cache.get("a", now), CacheLookup::Miss);
+        assert_eq!(cache.get("b", now), CacheLookup::NegativeHit);
+
+        apply(&cache, "");
+        assert_eq!(cache.len(), 0);
+    }
+}
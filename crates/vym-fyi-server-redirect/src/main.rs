@@ -1,20 +1,39 @@
 use crate::app::{RedirectApp, RedirectAppBuilder};
 use crate::handlers::health::health;
-use crate::handlers::short_link::redirect_short_link;
+use crate::handlers::short_link::{redirect_short_link, redirect_short_link_with_path};
+use crate::invalidation::spawn_cache_invalidation_listener;
 use axum::{Router, middleware, routing::get};
 use mimalloc::MiMalloc;
 use std::net::SocketAddr;
+use std::sync::Arc;
 use tokio::net::TcpListener;
 use tracing::info;
 use vym_fyi_model::models::errors::AppResult;
-use vym_fyi_model::services::axum_metrics::{prometheus_layer_default, record_ip_metrics};
+use vym_fyi_model::models::url_shortener::RateLimitPolicy;
+use vym_fyi_model::services::axum_metrics::{
+    init_metrics_labels_from_env, prometheus_layer_default, record_ip_metrics,
+};
+use vym_fyi_model::services::client_ip::{init_client_ip_resolver_from_env, resolve_client_ip};
 use vym_fyi_model::services::config::bind_addr_from_env;
 use vym_fyi_model::services::logging::setup_logging;
+use vym_fyi_model::services::rate_limit::{RateLimitKey, RateLimitLayer, RateLimiter};
 use vym_fyi_model::services::static_assets;
 
 mod app;
+mod cache;
+mod clicks;
+mod enumeration;
 mod handlers;
+mod invalidation;
 mod models;
+mod target;
+
+/// Default token-bucket limit per client IP, overridable with
+/// `RATE_LIMIT_BURST` / `RATE_LIMIT_PER_SECOND`.
+const DEFAULT_RATE_LIMIT: RateLimitPolicy = RateLimitPolicy {
+    burst: 120,
+    per_second: 20.0,
+};
 
 #[global_allocator]
 static GLOBAL: MiMalloc = MiMalloc;
@@ -30,12 +49,27 @@ async fn main() -> AppResult<()> {
         .build()
         .await?;
 
+    // Evict cached slugs as soon as the CRUD server changes them.
+    spawn_cache_invalidation_listener(&app);
+
+    init_metrics_labels_from_env()?;
+    init_client_ip_resolver_from_env()?;
     let (prometheus_layer, prometheus_handle) = prometheus_layer_default();
     let metrics_handle = prometheus_handle.clone();
 
+    // Redirects are anonymous, so limit by client IP; probes are exempt.
+    let rate_limit = RateLimitPolicy::from_env(DEFAULT_RATE_LIMIT)?;
+    let rate_limit_layer = RateLimitLayer::new(Arc::new(RateLimiter::default()), move |req| {
+        match req.uri().path() {
+            "/health" | "/metrics" => None,
+            _ => RateLimitKey::client_ip(req, rate_limit?),
+        }
+    });
+
     let router = Router::new()
         .route("/health", get(health))
-        .route("/{slug}", get(redirect_short_link));
+        .route("/{slug}", get(redirect_short_link))
+        .route("/{slug}/{*rest}", get(redirect_short_link_with_path));
 
     let router = static_assets::attach_static_routes(router)
         .route(
@@ -43,8 +77,10 @@ async fn main() -> AppResult<()> {
             get(move || async move { metrics_handle.render() }),
         )
         .with_state(app.clone())
+        .layer(rate_limit_layer)
         .layer(prometheus_layer)
-        .layer(middleware::from_fn(record_ip_metrics));
+        .layer(middleware::from_fn(record_ip_metrics))
+        .layer(middleware::from_fn(resolve_client_ip));
 
     let addr = bind_addr_from_env(8000)?;
     let listener = TcpListener::bind(addr).await?;
@@ -0,0 +1,293 @@
+use url::Url;
+use vym_fyi_model::models::url_shortener::QueryPassthrough;
+use vym_fyi_model::services::link_rules::{Visit, select_rule};
+use vym_fyi_model::services::link_variants::LinkVariant;
+use vym_fyi_model::services::param_template::TemplateVars;
+use vym_fyi_model::services::repos::ResolvedLink;
+
+/// The A/B variant to send `visit` to: none when one of the link's rules
+/// matches, since rules take precedence, or when the link has no split.
+/// `assigned` is the variant remembered for the visitor, if any.
+pub fn choose_variant<'a>(
+    link: &'a ResolvedLink,
+    visit: &Visit,
+    assigned: Option<&str>,
+) -> Option<&'a LinkVariant> {
+    if select_rule(&link.rules, visit).is_some() {
+        return None;
+    }
+    link.variants.choose(assigned)
+}
+
+/// URL to redirect to for a request of `/{slug}` (`rest` is `None`) or
+/// `/{slug}/{rest}`, with the request's raw `query` string. The first of
+/// the link's rules matching `visit` replaces the link's target, and so
+/// does the chosen `variant` otherwise; the parameter template is applied
+/// next, so passed-through parameters are merged with the templated ones.
+///
+/// Returns `None` when the request does not map onto the link: a path
+/// suffix on a link that is not a prefix link, a suffix with `.`/`..`
+/// segments, or a target that cannot take a path.
+pub fn redirect_target(
+    slug: &str,
+    link: &ResolvedLink,
+    visit: &Visit,
+    variant: Option<&LinkVariant>,
+    rest: Option<&str>,
+    query: Option<&str>,
+) -> Option<String> {
+    let target_url = select_rule(&link.rules, visit)
+        .map(|r| &r.target_url)
+        .or(variant.map(|v| &v.target_url))
+        .unwrap_or(&link.target_url);
+    let target = link.param_template.apply(
+        target_url,
+        TemplateVars {
+            tenant: link.tenant_name.as_deref().unwrap_or_default(),
+            slug,
+        },
+    );
+    let query = query
+        .filter(|q| !q.is_empty())
+        .filter(|_| link.query_passthrough != QueryPassthrough::Off);
+    if rest.is_none() && query.is_none() {
+        return Some(target);
+    }
+
+    let mut url = Url::parse(&target).ok()?;
+    if let Some(rest) = rest {
+        if !link.path_passthrough {
+            return None;
+        }
+        append_path(&mut url, rest)?;
+    }
+    if let Some(query) = query {
+        merge_query(&mut url, query, link.query_passthrough);
+    }
+    Some(url.into())
+}
+
+fn append_path(url: &mut Url, rest: &str) -> Option<()> {
+    let segments: Vec<&str> = rest.split('/').collect();
+    if segments.iter().any(|s| *s == "." || *s == "..") {
+        return None;
+    }
+    url.path_segments_mut()
+        .ok()?
+        .pop_if_empty()
+        .extend(segments);
+    Some(())
+}
+
+fn merge_query(url: &mut Url, query: &str, policy: QueryPassthrough) {
+    let target: Vec<(String, String)> = url.query_pairs().into_owned().collect();
+    let incoming: Vec<(String, String)> = url::form_urlencoded::parse(query.as_bytes())
+        .into_owned()
+        .collect();
+    let has = |pairs: &[(String, String)], name: &str| pairs.iter().any(|(n, _)| n == name);
+
+    let merged: Vec<&(String, String)> = match policy {
+        QueryPassthrough::Off => return,
+        QueryPassthrough::TargetWins => target
+            .iter()
+            .chain(incoming.iter().filter(|(n, _)| !has(&target, n)))
+            .collect(),
+        QueryPassthrough::RequestWins => target
+            .iter()
+            .filter(|(n, _)| !has(&incoming, n))
+            .chain(incoming.iter())
+            .collect(),
+        QueryPassthrough::Append => target.iter().chain(incoming.iter()).collect(),
+    };
+
+    if merged.is_empty() {
+        url.set_query(None);
+    } else {
+        url.query_pairs_mut().clear().extend_pairs(merged);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use vym_fyi_model::models::url_shortener::{RedirectType, TenantStatus};
+    use vym_fyi_model::services::link_rules::{LinkRule, RuleConditions};
+    use vym_fyi_model::services::link_variants::LinkVariants;
+    use vym_fyi_model::services::param_template::ParamTemplate;
+
+    fn link(target_url: &str, query: QueryPassthrough, path: bool) -> ResolvedLink {
+        ResolvedLink {
+            target_url: target_url.into(),
+            tenant_status: TenantStatus::Active,
+            tenant_id: None,
+            expires_at: None,
+            redirect_type: RedirectType::default(),
+            query_passthrough: query,
+            path_passthrough: path,
+            param_template: ParamTemplate::default(),
+            tenant_name: None,
+            rules: Vec::new(),
+            variants: LinkVariants::default(),
+        }
+    }
+
+    fn visit() -> Visit {
+        Visit::new(None, None, Utc::now())
+    }
+
+    #[test]
+    fn plain_links_ignore_query_and_reject_suffixes() {
+        let link = link("https://example.com/a?x=1", QueryPassthrough::Off, false);
+        assert_eq!(
+            redirect_target("s", &link, &visit(), None, None, Some("utm_source=x")).as_deref(),
+            Some("https://example.com/a?x=1")
+        );
+        assert_eq!(
+            redirect_target("s", &link, &visit(), None, Some("docs"), None),
+            None
+        );
+    }
+
+    #[test]
+    fn merges_query_by_policy() {
+        let target = "https://example.com/?utm_source=site&id=1";
+        let query = Some("utm_source=mail&utm_medium=x%20y");
+        let merged = |policy| {
+            redirect_target(
+                "s",
+                &link(target, policy, false),
+                &visit(),
+                None,
+                None,
+                query,
+            )
+        };
+
+        assert_eq!(
+            merged(QueryPassthrough::TargetWins).as_deref(),
+            Some("https://example.com/?utm_source=site&id=1&utm_medium=x+y")
+        );
+        assert_eq!(
+            merged(QueryPassthrough::RequestWins).as_deref(),
+            Some("https://example.com/?id=1&utm_source=mail&utm_medium=x+y")
+        );
+        assert_eq!(
+            merged(QueryPassthrough::Append).as_deref(),
+            Some("https://example.com/?utm_source=site&id=1&utm_source=mail&utm_medium=x+y")
+        );
+    }
+
+    #[test]
+    fn applies_param_template_before_passthrough() {
+        let link = ResolvedLink {
+            param_template: ParamTemplate::parse("utm_source={{tenant}}&utm_campaign={{slug}}")
+                .unwrap(),
+            tenant_name: Some("acme".into()),
+            ..link("https://example.com/", QueryPassthrough::RequestWins, false)
+        };
+        assert_eq!(
+            redirect_target("sale", &link, &visit(), None, None, None).as_deref(),
+            Some("https://example.com/?utm_source=acme&utm_campaign=sale")
+        );
+        assert_eq!(
+            redirect_target("sale", &link, &visit(), None, None, Some("utm_source=mail"))
+                .as_deref(),
+            Some("https://example.com/?utm_campaign=sale&utm_source=mail")
+        );
+    }
+
+    #[test]
+    fn matching_rule_replaces_target() {
+        let link = ResolvedLink {
+            rules: vec![LinkRule::new(
+                RuleConditions {
+                    languages: vec!["fr".into()],
+                    ..Default::default()
+                },
+                "https://example.com/fr/",
+            )],
+            ..link("https://example.com/", QueryPassthrough::Off, true)
+        };
+        let french = Visit::new(Some("fr-CA"), None, Utc::now());
+        assert_eq!(
+            redirect_target("s", &link, &french, None, Some("faq"), None).as_deref(),
+            Some("https://example.com/fr/faq")
+        );
+        assert_eq!(
+            redirect_target("s", &link, &visit(), None, None, None).as_deref(),
+            Some("https://example.com/")
+        );
+    }
+
+    #[test]
+    fn variants_apply_when_no_rule_matches() {
+        let variant = |name: &str| LinkVariant {
+            name: name.into(),
+            target_url: format!("https://example.com/{name}"),
+            weight: 1,
+        };
+        let link = ResolvedLink {
+            rules: vec![LinkRule::new(
+                RuleConditions {
+                    languages: vec!["fr".into()],
+                    ..Default::default()
+                },
+                "https://example.com/fr",
+            )],
+            variants: LinkVariants {
+                sticky: false,
+                variants: vec![variant("b")],
+            },
+            ..link("https://example.com/", QueryPassthrough::Off, false)
+        };
+        let chosen = choose_variant(&link, &visit(), None);
+        assert_eq!(chosen.map(|v| v.name.as_str()), Some("b"));
+        assert_eq!(
+            redirect_target("s", &link, &visit(), chosen, None, None).as_deref(),
+            Some("https://example.com/b")
+        );
+
+        let french = Visit::new(Some("fr"), None, Utc::now());
+        assert_eq!(choose_variant(&link, &french, None), None);
+        assert_eq!(
+            redirect_target("s", &link, &french, None, None, None).as_deref(),
+            Some("https://example.com/fr")
+        );
+    }
+
+    #[test]
+    fn prefix_links_append_the_path_suffix() {
+        let docs = link(
+            "https://docs.example.com/v2/",
+            QueryPassthrough::Append,
+            true,
+        );
+        assert_eq!(
+            redirect_target(
+                "s",
+                &docs,
+                &visit(),
+                None,
+                Some("getting-started/install"),
+                Some("lang=en")
+            )
+            .as_deref(),
+            Some("https://docs.example.com/v2/getting-started/install?lang=en")
+        );
+
+        let no_slash = link(
+            "https://docs.example.com/v2#top",
+            QueryPassthrough::Off,
+            true,
+        );
+        assert_eq!(
+            redirect_target("s", &no_slash, &visit(), None, Some("a b/c%2Fd"), None).as_deref(),
+            Some("https://docs.example.com/v2/a%20b/c%252Fd#top")
+        );
+        assert_eq!(
+            redirect_target("s", &docs, &visit(), None, Some("../admin"), None),
+            None
+        );
+    }
+}